@@ -12,30 +12,54 @@ use crate::game::asset_loading::nation_assets::UnitKey;
 
 #[cfg(feature = "bevy")]
 #[derive(serde::Deserialize, serde::Serialize, TypePath, Debug, PartialEq, Asset)]
-pub struct DynamicNationAssetsDefinition(pub Vec<NationAssetsDefinition>);
+pub struct DynamicNationAssetsDefinition {
+    pub nations: Vec<NationAssetsDefinition>,
+    #[serde(default)]
+    pub maps: Vec<MapAssetsDefinition>,
+    #[serde(default)]
+    pub abilities: Vec<AbilityAssetsDefinition>,
+}
 
 #[cfg(not(feature = "bevy"))]
 #[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq)]
-pub struct DynamicNationAssetsDefinition(pub Vec<NationAssetsDefinition>);
+pub struct DynamicNationAssetsDefinition {
+    pub nations: Vec<NationAssetsDefinition>,
+    #[serde(default)]
+    pub maps: Vec<MapAssetsDefinition>,
+    #[serde(default)]
+    pub abilities: Vec<AbilityAssetsDefinition>,
+}
 
 #[cfg(feature = "bevy")]
 impl DynamicAssetCollection for DynamicNationAssetsDefinition {
     fn register(&self, dynamic_assets: &mut DynamicAssets) {
         let image_assets = self
-            .0
+            .nations
             .iter()
             .flat_map(|nation_assets| nation_assets.get_units())
             .map(|unit_key| unit_key.get_image_asset_path())
             .collect();
         let unit_stats_files = self
-            .0
+            .nations
             .iter()
             .flat_map(|nation_assets| nation_assets.get_units())
             .map(|unit_key| unit_key.get_stats_asset_path())
             .collect();
+        let map_files = self
+            .maps
+            .iter()
+            .map(|map_assets| map_assets.get_asset_path())
+            .collect();
+        let ability_files = self
+            .abilities
+            .iter()
+            .map(|ability_assets| ability_assets.get_asset_path())
+            .collect();
 
         info!("Registering unit images: {image_assets:?}");
         info!("Registering unit stats files: {unit_stats_files:?}");
+        info!("Registering map files: {map_files:?}");
+        info!("Registering ability files: {ability_files:?}");
 
         dynamic_assets.register_asset(
             "unit_images",
@@ -49,6 +73,16 @@ impl DynamicAssetCollection for DynamicNationAssetsDefinition {
                 paths: unit_stats_files,
             }),
         );
+        dynamic_assets.register_asset(
+            "map_files",
+            Box::new(StandardDynamicAsset::Files { paths: map_files }),
+        );
+        dynamic_assets.register_asset(
+            "ability_files",
+            Box::new(StandardDynamicAsset::Files {
+                paths: ability_files,
+            }),
+        );
     }
 }
 
@@ -75,3 +109,25 @@ impl NationAssetsDefinition {
 pub struct UnitAssetsDefinition {
     pub path: String,
 }
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+pub struct MapAssetsDefinition {
+    pub path: String,
+}
+
+impl MapAssetsDefinition {
+    pub fn get_asset_path(&self) -> String {
+        format!("maps/{}", self.path)
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+pub struct AbilityAssetsDefinition {
+    pub path: String,
+}
+
+impl AbilityAssetsDefinition {
+    pub fn get_asset_path(&self) -> String {
+        format!("abilities/{}", self.path)
+    }
+}
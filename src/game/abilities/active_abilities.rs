@@ -3,33 +3,75 @@ use std::collections::HashSet;
 use crate::game::ingame::action_points::ActionPoints;
 use bevy::ecs::system::SystemId;
 use bevy::log::warn;
-use bevy::prelude::{Component, Entity, EventWriter, FromWorld, In, Parent, Query, With, World};
+use bevy::prelude::{
+    Component, Entity, EventWriter, FromWorld, In, NextState, Parent, Query, ResMut, World,
+};
 use bevy::utils::HashMap;
 use enum_iterator::{all, Sequence};
 use hexx::Hex;
 
-use crate::game::ingame::combat::{Attack, AttackOrDefault, CombatEvent};
+use crate::game::abilities::defined_abilities::DefinedAbilityInput;
+use crate::game::asset_loading::ability_assets::{
+    AbilityActivation, AbilityDefinition, Allegiance,
+};
+use crate::game::ingame::ammo::Ammo;
+use crate::game::ingame::area_of_effect::{AreaAttackEvent, AreaOfEffect, AreaShape};
+use crate::game::ingame::combat::{AmmoSource, Attack, HealthPoints};
+use crate::game::ingame::damage_type::DamageType;
+use crate::game::ingame::elevation::get_elevation_at;
 use crate::game::ingame::game_log::LogEvent;
-use crate::game::ingame::hex::{HexComponent, HexMarker};
+use crate::game::ingame::healing::HealEvent;
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::line_of_sight::LineOfSightMap;
+use crate::game::ingame::morale::{Morale, MoraleState};
 use crate::game::ingame::selected_unit::UpdateReachableHexesUnitsQuery;
-use crate::game::ingame::terrain::Terrain;
-use crate::game::ingame::unit::UnitFilter;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::states::round_state::RoundState;
+use crate::game::util::dice_expression::DiceExpression;
 use crate::game::util::find_units_within_range::FindUnitsWithinRange;
 
+const FIREBOMB_RANGE: u32 = 3;
+/// used unless the unit stats configure the ammo of the ability
+const DEFAULT_FIREBOMB_AMMO: usize = 1;
+const FIREBOMB: &str = "Firebomb";
+const FIREBOMB_AREA: AreaOfEffect = AreaOfEffect {
+    shape: AreaShape::Radius(1),
+    friendly_fire: true,
+};
+const RALLY_RANGE: u32 = 2;
+/// used unless the unit stats configure the ammo of the ability
+const DEFAULT_RALLY_AMMO: usize = 1;
+const HEAL: &str = "Heal";
+const HEAL_RANGE: u32 = 1;
+const HEAL_AMOUNT: usize = 2;
+pub const HEAL_ACTION_POINT_COST: usize = 2;
+
 #[derive(Component, Debug, Clone)]
 pub struct ActivatedAbilityMarker;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Hash, PartialEq, Eq, Sequence)]
 pub enum ActiveAbilityType {
-    ThrowJavelin,
+    /// Makes a shaken or routing ally steady again
+    Rally,
+    /// Sets every unit around the target hex on fire, allies included
+    Firebomb,
+    /// Heals an adjacent ally
+    Heal,
 }
 
 impl ActiveAbilityType {
     fn get_ability(&self, world: &mut World) -> ActiveAbility {
         match self {
-            ActiveAbilityType::ThrowJavelin => ActiveAbility::ThrowJavelin {
-                throw_javelin_system: world.register_system(throw_javelin_system),
-                usages_left: 1,
+            ActiveAbilityType::Rally => ActiveAbility::Rally {
+                rally_system: world.register_system(rally_system),
+                ammo: Ammo::new(DEFAULT_RALLY_AMMO),
+            },
+            ActiveAbilityType::Firebomb => ActiveAbility::Firebomb {
+                firebomb_system: world.register_system(firebomb_system),
+                ammo: Ammo::new(DEFAULT_FIREBOMB_AMMO),
+            },
+            ActiveAbilityType::Heal => ActiveAbility::Heal {
+                heal_system: world.register_system(heal_system),
             },
         }
     }
@@ -58,37 +100,149 @@ impl FromWorld for ActiveAbilityRegistry {
 
 #[derive(Component, Debug, Clone)]
 pub enum ActiveAbility {
-    ThrowJavelin {
-        throw_javelin_system: SystemId<ThrowJavelinInput>,
-        usages_left: u8,
+    Rally {
+        rally_system: SystemId<RallyInput>,
+        ammo: Ammo,
+    },
+    Firebomb {
+        firebomb_system: SystemId<FirebombInput>,
+        ammo: Ammo,
+    },
+    Heal {
+        heal_system: SystemId<HealInput>,
+    },
+    /// An ability from an ability file, see [`AbilityActivation::Active`]
+    Defined {
+        definition: AbilityDefinition,
+        system_id: SystemId<DefinedAbilityInput>,
+        ammo: Option<Ammo>,
     },
 }
 
 impl ActiveAbility {
     pub fn get_display_name(&self) -> String {
         match self {
-            ActiveAbility::ThrowJavelin { .. } => "Throw Javelin".to_string(),
+            ActiveAbility::Rally { .. } => "Rally".to_string(),
+            ActiveAbility::Firebomb { .. } => FIREBOMB.to_string(),
+            ActiveAbility::Heal { .. } => HEAL.to_string(),
+            ActiveAbility::Defined { definition, .. } => definition.name.clone(),
         }
     }
 
     pub fn get_reachable_hexes(
         &self,
         units: &UpdateReachableHexesUnitsQuery,
-        _: &Query<(&HexComponent, &Terrain), With<HexMarker>>,
+        hexes: &TerrainHexesQuery,
+        unit_morale: &UnitMoraleQuery,
         parent: &Parent,
     ) -> Option<HashSet<Hex>> {
         match self {
-            ActiveAbility::ThrowJavelin { .. } => {
+            ActiveAbility::Defined { definition, .. } => {
+                let AbilityActivation::Active { range, targets, .. } = definition.trigger else {
+                    warn!("{} is not an active ability", definition.name);
+                    return None;
+                };
                 let Ok((_, selected_unit_hex, selected_unit_team, _, _)) = units.get(**parent)
                 else {
                     warn!("Units query did not contain parent of activated ability {parent:?}");
                     return None;
                 };
 
+                let selected_unit_elevation = get_elevation_at(hexes, selected_unit_hex.0);
+                let line_of_sight_map = LineOfSightMap::from_terrain(
+                    hexes,
+                    units
+                        .iter()
+                        .map(|(_, hex_component, _, _, _)| hex_component.0),
+                );
+
                 Some(
-                    units.find_units_within_range(selected_unit_hex.0, 2, |team| {
-                        team != selected_unit_team
-                    }),
+                    units
+                        .find_units_within_range(
+                            selected_unit_hex.0,
+                            selected_unit_elevation.get_max_effective_range(range),
+                            |team| match targets {
+                                Allegiance::Enemy => team != selected_unit_team,
+                                Allegiance::Ally => team == selected_unit_team,
+                            },
+                        )
+                        .into_iter()
+                        .filter(|hex| hex != &selected_unit_hex.0)
+                        .filter(|hex| {
+                            let effective_range = selected_unit_elevation
+                                .get_effective_range(range, &get_elevation_at(hexes, *hex));
+                            selected_unit_hex.0.unsigned_distance_to(*hex) <= effective_range
+                        })
+                        .filter(|hex| line_of_sight_map.can_see(selected_unit_hex.0, *hex))
+                        .collect(),
+                )
+            }
+            ActiveAbility::Rally { .. } => {
+                let Ok((_, selected_unit_hex, selected_unit_team, _, _)) = units.get(**parent)
+                else {
+                    warn!("Units query did not contain parent of activated ability {parent:?}");
+                    return None;
+                };
+
+                Some(
+                    units
+                        .find_units_within_range(selected_unit_hex.0, RALLY_RANGE, |team| {
+                            team == selected_unit_team
+                        })
+                        .into_iter()
+                        .filter(|hex| hex != &selected_unit_hex.0)
+                        .filter(|hex| {
+                            unit_morale.iter().any(|(unit_hex, morale)| {
+                                unit_hex.0 == *hex && morale.state != MoraleState::Steady
+                            })
+                        })
+                        .collect(),
+                )
+            }
+            ActiveAbility::Firebomb { .. } => {
+                let Ok((_, selected_unit_hex, _, _, _)) = units.get(**parent) else {
+                    warn!("Units query did not contain parent of activated ability {parent:?}");
+                    return None;
+                };
+
+                let selected_unit_elevation = get_elevation_at(hexes, selected_unit_hex.0);
+                let line_of_sight_map = LineOfSightMap::from_terrain(
+                    hexes,
+                    units
+                        .iter()
+                        .map(|(_, hex_component, _, _, _)| hex_component.0),
+                );
+
+                Some(
+                    hexes
+                        .iter()
+                        .filter(|(hex_component, _, elevation)| {
+                            let effective_range = selected_unit_elevation
+                                .get_effective_range(FIREBOMB_RANGE, elevation);
+                            hex_component.0 != selected_unit_hex.0
+                                && selected_unit_hex.0.unsigned_distance_to(hex_component.0)
+                                    <= effective_range
+                        })
+                        .map(|(hex_component, _, _)| hex_component.0)
+                        .filter(|hex| line_of_sight_map.can_see(selected_unit_hex.0, *hex))
+                        .collect(),
+                )
+            }
+            ActiveAbility::Heal { .. } => {
+                let Ok((_, selected_unit_hex, selected_unit_team, _, _)) = units.get(**parent)
+                else {
+                    warn!("Units query did not contain parent of activated ability {parent:?}");
+                    return None;
+                };
+
+                Some(
+                    units
+                        .find_units_within_range(selected_unit_hex.0, HEAL_RANGE, |team| {
+                            team == selected_unit_team
+                        })
+                        .into_iter()
+                        .filter(|hex| hex != &selected_unit_hex.0)
+                        .collect(),
                 )
             }
         }
@@ -96,37 +250,349 @@ impl ActiveAbility {
 
     pub fn can_be_used(&self, action_points: &ActionPoints) -> bool {
         match self {
-            ActiveAbility::ThrowJavelin { usages_left, .. } => {
-                usages_left > &0 && action_points.can_still_attack_this_turn()
+            ActiveAbility::Firebomb { ammo, .. } => {
+                !ammo.is_empty() && action_points.can_still_attack_this_turn()
+            }
+            ActiveAbility::Defined {
+                definition, ammo, ..
+            } => {
+                let has_action_points = if definition.deals_damage() {
+                    action_points.can_still_attack_this_turn()
+                } else {
+                    action_points.left >= action_points.attack_action_point_cost()
+                };
+                !ammo.is_some_and(|ammo| ammo.is_empty()) && has_action_points
             }
+            ActiveAbility::Rally { ammo, .. } => {
+                !ammo.is_empty() && action_points.left >= action_points.attack_action_point_cost()
+            }
+            ActiveAbility::Heal { .. } => action_points.left >= HEAL_ACTION_POINT_COST,
+        }
+    }
+
+    pub fn get_ammo(&self) -> Option<&Ammo> {
+        match self {
+            ActiveAbility::Firebomb { ammo, .. } | ActiveAbility::Rally { ammo, .. } => Some(ammo),
+            ActiveAbility::Defined { ammo, .. } => ammo.as_ref(),
+            ActiveAbility::Heal { .. } => None,
+        }
+    }
+
+    pub fn get_ammo_mut(&mut self) -> Option<&mut Ammo> {
+        match self {
+            ActiveAbility::Firebomb { ammo, .. } | ActiveAbility::Rally { ammo, .. } => Some(ammo),
+            ActiveAbility::Defined { ammo, .. } => ammo.as_mut(),
+            ActiveAbility::Heal { .. } => None,
+        }
+    }
+
+    /// The area hit around the targeted hex, for abilities that hit more than one unit
+    pub fn get_area(&self) -> Option<AreaOfEffect> {
+        match self {
+            ActiveAbility::Firebomb { .. } => Some(FIREBOMB_AREA),
+            ActiveAbility::Rally { .. }
+            | ActiveAbility::Heal { .. }
+            | ActiveAbility::Defined { .. } => None,
+        }
+    }
+
+    /// Replaces the default ammo of abilities that use ammo
+    pub fn with_ammo(mut self, max: usize) -> Self {
+        match self.get_ammo_mut() {
+            Some(ammo) => *ammo = Ammo::new(max),
+            None => warn!("{} does not use ammo", self.get_display_name()),
         }
+        self
     }
 }
 
-pub struct ThrowJavelinInput {
+pub struct FirebombInput {
     pub attacker: Entity,
+    /// the entity of the [`ActiveAbility`], which uses up its ammo
+    pub ability: Entity,
+    pub target_hex: Hex,
+    pub ammo: Ammo,
+}
+
+fn firebomb_system(
+    input: In<FirebombInput>,
+    mut area_attack_event: EventWriter<AreaAttackEvent>,
+    mut round_state: ResMut<NextState<RoundState>>,
+) {
+    round_state.set(RoundState::Input);
+
+    area_attack_event.send(AreaAttackEvent {
+        attacker: input.attacker,
+        attack: Attack {
+            name: FIREBOMB.to_string(),
+            damage: DiceExpression::constant(1),
+            damage_type: DamageType::Fire,
+            passive_combat_abilities: vec![],
+            range: FIREBOMB_RANGE,
+            action_point_cost: None,
+            ammo: Some(input.ammo),
+            ammo_source: Some(AmmoSource::Ability(input.ability)),
+            area: Some(FIREBOMB_AREA),
+        },
+        target_hex: input.target_hex,
+    });
+}
+
+pub struct HealInput {
+    pub healer: Entity,
     pub target_hex: Hex,
 }
 
-fn throw_javelin_system(
-    input: In<ThrowJavelinInput>,
-    units: Query<(Entity, &HexComponent), UnitFilter>,
-    mut combat_event: EventWriter<CombatEvent>,
+/// Units at full health are not healed, so the healer keeps its action points
+fn heal_system(
+    input: In<HealInput>,
+    mut units: Query<
+        (
+            Entity,
+            &HexComponent,
+            &UnitMarker,
+            &HealthPoints,
+            &mut ActionPoints,
+        ),
+        UnitFilter,
+    >,
+    mut heal_event: EventWriter<HealEvent>,
     mut log_event: EventWriter<LogEvent>,
+    mut round_state: ResMut<NextState<RoundState>>,
 ) {
-    let Some((defender, _)) = units.iter().find(|(_, hex)| hex.0 == input.target_hex) else {
+    round_state.set(RoundState::Input);
+
+    let Some((target, _, target_marker, target_health_points, _)) = units
+        .iter()
+        .find(|(_, hex, _, _, _)| hex.0 == input.target_hex)
+    else {
         return;
     };
-    log_event.send(LogEvent {
-        message: format!("{:?} throws a javelin at {defender:?}", input.attacker),
+    if target_health_points.left >= target_health_points.get_max() {
+        log_event.send(LogEvent {
+            message: format!("{} is already at full health", target_marker.0),
+        });
+        return;
+    }
+    let Ok((_, _, healer_marker, _, mut action_points)) = units.get_mut(input.healer) else {
+        return;
+    };
+    action_points.left = action_points.left.saturating_sub(HEAL_ACTION_POINT_COST);
+    heal_event.send(HealEvent {
+        unit: target,
+        amount: HEAL_AMOUNT,
+        source: format!("{HEAL} by {}", healer_marker.0),
     });
-    combat_event.send(CombatEvent {
-        attack: AttackOrDefault::Attack(Attack {
-            damage: 1,
-            passive_combat_abilities: vec![],
-            range: 2,
-        }),
-        attacker: input.attacker,
-        defender,
+}
+
+pub type UnitMoraleQuery<'world, 'state, 'a> =
+    Query<'world, 'state, (&'a HexComponent, &'a Morale), UnitFilter>;
+
+pub struct RallyInput {
+    pub rallier: Entity,
+    /// the entity of the [`ActiveAbility`], which uses up its ammo
+    pub ability: Entity,
+    pub target_hex: Hex,
+}
+
+/// Steady units are not rallied, so the rallier keeps its ammo and action points
+fn rally_system(
+    input: In<RallyInput>,
+    mut units: Query<(&HexComponent, &UnitMarker, &mut Morale), UnitFilter>,
+    mut active_abilities: Query<&mut ActiveAbility>,
+    mut action_points: Query<&mut ActionPoints>,
+    mut log_event: EventWriter<LogEvent>,
+    mut round_state: ResMut<NextState<RoundState>>,
+) {
+    round_state.set(RoundState::Input);
+
+    let Some((_, unit_marker, mut morale)) = units
+        .iter_mut()
+        .find(|(hex, _, morale)| hex.0 == input.target_hex && morale.state != MoraleState::Steady)
+    else {
+        return;
+    };
+    let Some(ammo) = active_abilities
+        .get_mut(input.ability)
+        .ok()
+        .and_then(|ability| ability.into_inner().get_ammo_mut())
+    else {
+        warn!("Rally was used without its ability");
+        return;
+    };
+    if ammo.is_empty() {
+        return;
+    }
+    ammo.use_one();
+    if let Ok(mut action_points) = action_points.get_mut(input.rallier) {
+        action_points.left = action_points
+            .left
+            .saturating_sub(action_points.attack_action_point_cost());
+    }
+    log_event.send(LogEvent {
+        message: format!(
+            "{} is rallied and {} again",
+            unit_marker.0,
+            MoraleState::Steady
+        ),
     });
+    morale.rally();
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::prelude::{App, BuildWorldChildren, Children};
+
+    use crate::game::ingame::team_setup::Team;
+    use crate::generate_test_app;
+    use crate::tests::{spawn_unit, AppWrapper};
+
+    use super::*;
+
+    #[test]
+    fn only_shaken_and_routing_allies_can_be_rallied() {
+        let mut app = TestApp::build();
+
+        assert_eq!(
+            app.get_rally_targets(),
+            Some(HashSet::from([Hex::new(1, 0), Hex::new(1, -1)]))
+        );
+    }
+
+    #[test]
+    fn rallying_makes_the_target_steady_again() {
+        let mut app = TestApp::build();
+        let mut log_event_reader = app.get_event_reader::<LogEvent>();
+
+        app.rally(Hex::new(1, 0));
+
+        assert_eq!(app.get_morale_state(app.shaken_ally), MoraleState::Steady);
+        assert_eq!(app.get_events(&mut log_event_reader).len(), 1);
+        assert_eq!(app.get_ammo_left(), 0);
+        assert_eq!(app.get_action_points_left(), 9);
+    }
+
+    #[test]
+    fn rallying_without_ammo_does_nothing() {
+        let mut app = TestApp::build();
+        app.rally(Hex::new(1, 0));
+        let mut log_event_reader = app.get_event_reader::<LogEvent>();
+
+        app.rally(Hex::new(1, -1));
+
+        assert_eq!(app.get_events(&mut log_event_reader).len(), 0);
+        assert_eq!(app.get_action_points_left(), 9);
+    }
+
+    #[test]
+    fn steady_units_are_not_rallied() {
+        let mut app = TestApp::build();
+        let mut log_event_reader = app.get_event_reader::<LogEvent>();
+
+        app.rally(Hex::new(0, 1));
+
+        assert_eq!(app.get_events(&mut log_event_reader).len(), 0);
+        assert_eq!(app.get_ammo_left(), 1);
+        assert_eq!(app.get_action_points_left(), 10);
+    }
+
+    generate_test_app!(rallier: Entity, shaken_ally: Entity);
+
+    impl TestApp {
+        fn build() -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.add_event::<LogEvent>();
+
+            let [rallier, shaken_ally, ..] = [
+                (Team::Red, Hex::ZERO, MoraleState::Steady),
+                (Team::Red, Hex::new(1, 0), MoraleState::Shaken),
+                (Team::Red, Hex::new(1, -1), MoraleState::Routing),
+                (Team::Red, Hex::new(0, 1), MoraleState::Steady),
+                (Team::Blue, Hex::new(-1, 0), MoraleState::Shaken),
+            ]
+            .map(|(team, hex, morale_state)| {
+                let unit = spawn_unit(&mut app, team, hex);
+                app.world.get_mut::<Morale>(unit).unwrap().state = morale_state;
+                unit
+            });
+
+            let rally = ActiveAbilityRegistry::from_world(&mut app.world)
+                .get_registered_ability(&ActiveAbilityType::Rally);
+            let ability = app.world.spawn((rally, ActivatedAbilityMarker)).id();
+            app.world.entity_mut(rallier).add_child(ability);
+
+            app.update();
+
+            TestApp {
+                app,
+                rallier,
+                shaken_ally,
+            }
+        }
+
+        fn get_rally_targets(&mut self) -> Option<HashSet<Hex>> {
+            self.app.world.run_system_once(
+                |units: UpdateReachableHexesUnitsQuery,
+                 hexes: TerrainHexesQuery,
+                 unit_morale: UnitMoraleQuery,
+                 abilities: Query<(&ActiveAbility, &Parent)>| {
+                    let (ability, parent) = abilities.single();
+                    ability.get_reachable_hexes(&units, &hexes, &unit_morale, parent)
+                },
+            )
+        }
+
+        fn rally(&mut self, target_hex: Hex) {
+            let ActiveAbility::Rally { rally_system, .. } = self
+                .app
+                .world
+                .get::<ActiveAbility>(self.get_ability())
+                .unwrap()
+                .clone()
+            else {
+                panic!("Rally is not a rally ability");
+            };
+            self.app
+                .world
+                .run_system_with_input(
+                    rally_system,
+                    RallyInput {
+                        rallier: self.rallier,
+                        ability: self.get_ability(),
+                        target_hex,
+                    },
+                )
+                .unwrap();
+            self.update();
+        }
+
+        fn get_ability(&self) -> Entity {
+            self.app.world.get::<Children>(self.rallier).unwrap()[0]
+        }
+
+        fn get_morale_state(&self, unit: Entity) -> MoraleState {
+            self.app.world.get::<Morale>(unit).unwrap().state
+        }
+
+        fn get_ammo_left(&self) -> usize {
+            self.app
+                .world
+                .get::<ActiveAbility>(self.get_ability())
+                .unwrap()
+                .get_ammo()
+                .unwrap()
+                .left
+        }
+
+        fn get_action_points_left(&self) -> usize {
+            self.app
+                .world
+                .get::<ActionPoints>(self.rallier)
+                .unwrap()
+                .left
+        }
+    }
 }
@@ -0,0 +1,374 @@
+use bevy::ecs::system::{SystemId, SystemParam};
+use bevy::log::warn;
+use bevy::prelude::{
+    debug, Entity, EventWriter, In, NextState, Query, Res, ResMut, Resource, World,
+};
+use bevy::utils::HashMap;
+use hexx::Hex;
+
+use crate::game::abilities::active_abilities::ActiveAbility;
+use crate::game::abilities::passive_combat_abilities::{
+    AbilityTrigger, PassiveAbility, RegisteredPassiveCombatAbility, TriggeredAbilityResource,
+};
+use crate::game::asset_loading::ability_assets::{
+    AbilityActivation, AbilityAssetsResource, AbilityDefinition, AbilityEffect, AbilityTarget,
+};
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::ammo::Ammo;
+use crate::game::ingame::combat::{Attack, AttackOrDefault, CombatEvent, CombatResource};
+use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::stat_modifiers::{StatModifier, StatModifiers};
+use crate::game::ingame::status_effects::{StatusEffect, StatusEffects};
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::terrain::MovementCost;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::ingame::unit_status::UnitStatus;
+use crate::game::states::round_state::RoundState;
+
+/// An ability from an ability file with its effects registered as a system
+#[derive(Debug, Clone)]
+pub enum DefinedAbility {
+    Passive(RegisteredPassiveCombatAbility),
+    Active(ActiveAbility),
+}
+
+/// The abilities from the ability files by their name
+#[derive(Resource, Debug, Default)]
+pub struct DefinedAbilityRegistry(HashMap<String, DefinedAbility>);
+
+impl DefinedAbilityRegistry {
+    pub fn new(
+        world: &mut World,
+        definitions: impl IntoIterator<Item = AbilityDefinition>,
+    ) -> Self {
+        let abilities = definitions
+            .into_iter()
+            .map(|definition| {
+                let ability = match definition.trigger.clone() {
+                    AbilityActivation::Passive(ability_trigger) => {
+                        DefinedAbility::Passive(RegisteredPassiveCombatAbility {
+                            ability: PassiveAbility::Defined(definition.clone()),
+                            system_id: Some(register_passive_ability(
+                                world,
+                                definition.clone(),
+                                ability_trigger.clone(),
+                            )),
+                            ability_trigger,
+                        })
+                    }
+                    AbilityActivation::Active { ammo, .. } => {
+                        DefinedAbility::Active(ActiveAbility::Defined {
+                            system_id: register_active_ability(world, definition.clone()),
+                            ammo: ammo.map(Ammo::new),
+                            definition: definition.clone(),
+                        })
+                    }
+                };
+                (definition.name, ability)
+            })
+            .collect();
+
+        Self(abilities)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&DefinedAbility> {
+        let ability = self.0.get(name);
+        if ability.is_none() {
+            warn!("No ability named {name} is defined in the ability files");
+        }
+        ability
+    }
+}
+
+pub(crate) fn register_defined_abilities(world: &mut World) {
+    let definitions: Vec<_> = world
+        .resource::<AbilityAssetsResource>()
+        .abilities
+        .values()
+        .cloned()
+        .collect();
+    let registry = DefinedAbilityRegistry::new(world, definitions);
+    world.insert_resource(registry);
+}
+
+pub struct DefinedAbilityInput {
+    pub user: Entity,
+    /// the entity of the [`ActiveAbility`], which uses up its ammo
+    pub ability: Entity,
+    pub target_hex: Hex,
+}
+
+fn register_passive_ability(
+    world: &mut World,
+    definition: AbilityDefinition,
+    ability_trigger: AbilityTrigger,
+) -> SystemId {
+    world.register_system(
+        move |combat_resource: Option<Res<CombatResource>>,
+              triggered_ability: Option<Res<TriggeredAbilityResource>>,
+              mut ability_effects: AbilityEffects| {
+            let (owner, other_unit, combat_result) =
+                match (&ability_trigger, &combat_resource, &triggered_ability) {
+                    (
+                        AbilityTrigger::OnAttack(_)
+                        | AbilityTrigger::OnCriticalHit
+                        | AbilityTrigger::OnFumble,
+                        Some(combat_resource),
+                        _,
+                    ) => (
+                        combat_resource.attacker,
+                        Some(combat_resource.defender),
+                        Some(&combat_resource.combat_result),
+                    ),
+                    (AbilityTrigger::OnDefense(_), Some(combat_resource), _) => (
+                        combat_resource.defender,
+                        Some(combat_resource.attacker),
+                        Some(&combat_resource.combat_result),
+                    ),
+                    (_, _, Some(triggered_ability)) => {
+                        (triggered_ability.unit, triggered_ability.other_unit, None)
+                    }
+                    _ => return,
+                };
+
+            let takes_effect = match combat_result {
+                Some(combat_result) => definition.takes_effect_on(combat_result),
+                None => definition.conditions.is_empty(),
+            };
+            if takes_effect {
+                ability_effects.apply(&definition, owner, other_unit);
+            }
+        },
+    )
+}
+
+fn register_active_ability(
+    world: &mut World,
+    definition: AbilityDefinition,
+) -> SystemId<DefinedAbilityInput> {
+    world.register_system(
+        move |input: In<DefinedAbilityInput>,
+              mut ability_effects: AbilityEffects,
+              mut active_abilities: Query<&mut ActiveAbility>,
+              mut action_points: Query<&mut ActionPoints>,
+              mut round_state: ResMut<NextState<RoundState>>| {
+            round_state.set(RoundState::Input);
+
+            let Some((picked_unit, ..)) = ability_effects
+                .units
+                .iter()
+                .find(|(_, _, hex, ..)| hex.0 == input.target_hex)
+            else {
+                return;
+            };
+            let Ok(mut ability) = active_abilities.get_mut(input.ability) else {
+                warn!("{} was used without an active ability", definition.name);
+                return;
+            };
+            if let Some(ammo) = ability.get_ammo_mut() {
+                if ammo.is_empty() {
+                    return;
+                }
+                ammo.left -= 1;
+            }
+            // attacks pay for themselves when they are resolved
+            if !definition.deals_damage() {
+                if let Ok(mut action_points) = action_points.get_mut(input.user) {
+                    action_points.left = action_points
+                        .left
+                        .saturating_sub(action_points.attack_action_point_cost());
+                }
+            }
+            ability_effects.apply(&definition, input.user, Some(picked_unit));
+        },
+    )
+}
+
+type AbilityUnitsQuery<'world, 'state, 'a> = Query<
+    'world,
+    'state,
+    (
+        Entity,
+        &'a UnitMarker,
+        &'a mut HexComponent,
+        &'a Team,
+        &'a mut UnitStatus,
+        &'a mut StatusEffects,
+        &'a mut StatModifiers,
+    ),
+    UnitFilter,
+>;
+
+/// Everything the effects of defined abilities change
+#[derive(SystemParam)]
+struct AbilityEffects<'w, 's> {
+    units: AbilityUnitsQuery<'w, 's, 'static>,
+    hexes: TerrainHexesQuery<'w, 's, 'static>,
+    combat_event: EventWriter<'w, CombatEvent>,
+    log_event: EventWriter<'w, LogEvent>,
+}
+
+impl AbilityEffects<'_, '_> {
+    fn apply(&mut self, definition: &AbilityDefinition, owner: Entity, other_unit: Option<Entity>) {
+        let targets = self.get_targets(definition.target, owner, other_unit);
+        debug!("{} of {owner:?} affects {targets:?}", definition.name);
+
+        for target in targets {
+            // the owner moves or disengages relative to the other unit, every other target relative to the owner
+            let relative_to = if target == owner {
+                other_unit
+            } else {
+                Some(owner)
+            };
+            for effect in &definition.effects {
+                self.apply_effect(definition, effect, owner, target, relative_to);
+            }
+        }
+    }
+
+    fn get_targets(
+        &self,
+        target: AbilityTarget,
+        owner: Entity,
+        other_unit: Option<Entity>,
+    ) -> Vec<Entity> {
+        let Ok((_, _, owner_hex, owner_team, ..)) = self.units.get(owner) else {
+            return vec![];
+        };
+        match target {
+            AbilityTarget::Owner => vec![owner],
+            AbilityTarget::OtherUnit => other_unit.into_iter().collect(),
+            AbilityTarget::AdjacentAllies | AbilityTarget::AdjacentEnemies => self
+                .units
+                .iter()
+                .filter(|(_, _, hex, team, ..)| {
+                    let is_ally = team == &owner_team;
+                    hex.0.unsigned_distance_to(owner_hex.0) == 1
+                        && is_ally == (target == AbilityTarget::AdjacentAllies)
+                })
+                .map(|(entity, ..)| entity)
+                .collect(),
+        }
+    }
+
+    fn apply_effect(
+        &mut self,
+        definition: &AbilityDefinition,
+        effect: &AbilityEffect,
+        owner: Entity,
+        target: Entity,
+        relative_to: Option<Entity>,
+    ) {
+        let relative_to_hex = relative_to
+            .and_then(|entity| self.units.get(entity).ok())
+            .map(|(_, _, hex, ..)| hex.0);
+        let blocked_hexes: Vec<_> = self.units.iter().map(|(_, _, hex, ..)| hex.0).collect();
+        let Ok((
+            _,
+            unit_marker,
+            mut hex,
+            _,
+            mut unit_status,
+            mut status_effects,
+            mut stat_modifiers,
+        )) = self.units.get_mut(target)
+        else {
+            return;
+        };
+        let name = &definition.name;
+
+        match effect {
+            AbilityEffect::ModifyStat {
+                stat,
+                value,
+                rounds,
+            } => {
+                self.log_event.send(LogEvent {
+                    message: format!("{name} changes {stat} of {} by {value}", unit_marker.0),
+                });
+                stat_modifiers.add(StatModifier {
+                    stat: *stat,
+                    value: *value,
+                    source: name.clone(),
+                    remaining_rounds: *rounds,
+                });
+            }
+            AbilityEffect::DealDamage {
+                damage,
+                damage_type,
+            } => {
+                if target == owner {
+                    warn!("{name} can not deal damage to the unit that has the ability");
+                    return;
+                }
+                let range = match definition.trigger {
+                    AbilityActivation::Active { range, .. } => range,
+                    AbilityActivation::Passive(_) => 1,
+                };
+                self.combat_event.send(CombatEvent {
+                    attacker: owner,
+                    attack: AttackOrDefault::Attack(Attack {
+                        name: name.clone(),
+                        damage: damage.clone(),
+                        damage_type: *damage_type,
+                        passive_combat_abilities: vec![],
+                        range,
+                        action_point_cost: None,
+                        ammo: None,
+                        ammo_source: None,
+                        area: None,
+                    }),
+                    defender: target,
+                });
+            }
+            AbilityEffect::ApplyStatus { effect, rounds } => {
+                self.log_event.send(LogEvent {
+                    message: format!(
+                        "{} is affected by {effect} for {rounds} rounds due to {name}",
+                        unit_marker.0
+                    ),
+                });
+                status_effects.add(StatusEffect {
+                    effect_type: *effect,
+                    source: name.clone(),
+                    stacks: 1,
+                    remaining_rounds: *rounds,
+                });
+            }
+            AbilityEffect::MoveUnit { distance } => {
+                let Some(relative_to_hex) = relative_to_hex else {
+                    return;
+                };
+                let direction = relative_to_hex.main_direction_to(hex.0);
+                let mut destination = hex.0;
+                for _ in 0..*distance {
+                    let next = destination.neighbor(direction);
+                    let is_passable = self.hexes.iter().any(|(hex_component, terrain, _)| {
+                        hex_component.0 == next
+                            && matches!(terrain.movement_cost, MovementCost::Passable(_))
+                    });
+                    if !is_passable || blocked_hexes.contains(&next) {
+                        break;
+                    }
+                    destination = next;
+                }
+                if destination != hex.0 {
+                    self.log_event.send(LogEvent {
+                        message: format!("{} is moved by {name}", unit_marker.0),
+                    });
+                    hex.0 = destination;
+                }
+            }
+            AbilityEffect::Disengage => {
+                let Some(relative_to) = relative_to else {
+                    return;
+                };
+                self.log_event.send(LogEvent {
+                    message: format!("{} disengaged due to {name}", unit_marker.0),
+                });
+                unit_status.disengage_with(&relative_to);
+            }
+        }
+    }
+}
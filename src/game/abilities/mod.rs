@@ -1,2 +1,3 @@
 pub mod active_abilities;
+pub mod defined_abilities;
 pub mod passive_combat_abilities;
@@ -1,57 +1,280 @@
+use std::fmt::{Display, Formatter};
+
 use bevy::ecs::system::SystemId;
-use bevy::prelude::{EventWriter, FromWorld, Query, Res, World};
+use bevy::prelude::{
+    debug, Commands, Entity, EventWriter, FromWorld, Query, Res, ResMut, Resource, World,
+};
 use bevy::utils::HashMap;
 use enum_iterator::{all, Sequence};
 
 use game_log::LogEvent;
 
-use crate::game::ingame::combat::{CombatConfig, CombatResource, CombatResult};
+use crate::game::asset_loading::ability_assets::AbilityDefinition;
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::attack_roll::{RollModification, RollModificationKind};
+#[cfg(doc)]
+use crate::game::ingame::charge::TravelledPath;
+#[cfg(doc)]
+use crate::game::ingame::combat::Attack;
+use crate::game::ingame::combat::{
+    AttackOrDefault, CombatConfig, CombatEvent, CombatResource, CombatResult,
+};
 use crate::game::ingame::game_log;
+use crate::game::ingame::healing::HealEvent;
+use crate::game::ingame::stat_modifiers::{ModifierValue, Stat, StatModifier, StatModifiers};
+use crate::game::ingame::status_effects::{StatusEffect, StatusEffectType, StatusEffects};
 use crate::game::ingame::unit::UnitMarker;
-use crate::game::ingame::unit_status::UnitStatus;
+
+const POISON_ROUNDS: usize = 2;
+const STUN_ROUNDS: usize = 1;
+const HAMSTRING_ROUNDS: usize = 1;
+const VENGEANCE_DEFENSE_BONUS: i32 = 2;
+const VENGEANCE_ROUNDS: usize = 2;
+const EVASIVE_ROLL_MODIFIER: i32 = -2;
+const BRUTAL_DAMAGE_MODIFIER: i32 = 1;
+const REGENERATION_HEALING: usize = 1;
 
 #[derive(Debug, Clone)]
 pub struct RegisteredPassiveCombatAbility {
-    pub ability: PassiveCombatAbility,
-    pub system_id: SystemId,
+    pub ability: PassiveAbility,
+    /// not set for abilities that are properties of the attack, see [`PassiveCombatAbility::register_system`]
+    pub system_id: Option<SystemId>,
     pub ability_trigger: AbilityTrigger,
 }
 
+/// Either one of the abilities built into the game or one defined in an ability file
+#[derive(Debug, Clone, PartialEq)]
+pub enum PassiveAbility {
+    BuiltIn(PassiveCombatAbility),
+    Defined(AbilityDefinition),
+}
+
+impl PassiveAbility {
+    /// Whether the ability takes effect once it is triggered by an attack with the given result
+    pub fn takes_effect_on(&self, combat_result: &CombatResult) -> bool {
+        match self {
+            PassiveAbility::BuiltIn(ability) => ability.takes_effect_on(combat_result),
+            PassiveAbility::Defined(definition) => definition.takes_effect_on(combat_result),
+        }
+    }
+
+    /// The change to the attack roll the ability makes before the attack is rolled
+    pub fn get_roll_modification(&self) -> Option<RollModification> {
+        match self {
+            PassiveAbility::BuiltIn(ability) => ability.get_roll_modification(),
+            PassiveAbility::Defined(_) => None,
+        }
+    }
+}
+
+impl Display for PassiveAbility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PassiveAbility::BuiltIn(ability) => write!(f, "{ability}"),
+            PassiveAbility::Defined(definition) => write!(f, "{}", definition.name),
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Hash, PartialEq, Eq, Sequence)]
 pub enum PassiveCombatAbility {
-    ArmorBreak,
-    HitAndRun,
+    /// Attacks of this unit can not be countered
+    FirstStrike,
+    /// Hits poison the defender
+    PoisonedWeapons,
+    /// Critical hits stun the defender
+    Stunning,
+    /// Hits slow the defender down
+    Hamstring,
+    /// Attacks at the end of a straight run are easier to hit, see [`TravelledPath::is_charge`]
+    Charge,
+    /// Attacks enemies that move next to the unit
+    Ambush,
+    /// The unit defends itself better for a few rounds after an adjacent ally was killed
+    Vengeance,
+    /// A missed attack roll is rolled again
+    Lucky,
+    /// Attack rolls against the unit are lower
+    Evasive,
+    /// Hits deal more damage
+    Brutal,
+    /// The unit recovers health points at the end of its turn
+    Regeneration,
 }
 
 impl PassiveCombatAbility {
-    pub fn register_system(&self, world: &mut World) -> SystemId {
+    /// First strike and charge have no system, they are checked when the attack is resolved,
+    /// see [`Attack::is_first_strike`] and [`Attack::is_charge`]
+    pub fn register_system(&self, world: &mut World) -> Option<SystemId> {
+        let system_id = match self {
+            PassiveCombatAbility::FirstStrike | PassiveCombatAbility::Charge => return None,
+            PassiveCombatAbility::PoisonedWeapons => world.register_system(status_effect_action(
+                PassiveCombatAbility::PoisonedWeapons,
+                StatusEffectType::Poisoned,
+                POISON_ROUNDS,
+            )),
+            PassiveCombatAbility::Stunning => world.register_system(status_effect_action(
+                PassiveCombatAbility::Stunning,
+                StatusEffectType::Stunned,
+                STUN_ROUNDS,
+            )),
+            PassiveCombatAbility::Hamstring => world.register_system(status_effect_action(
+                PassiveCombatAbility::Hamstring,
+                StatusEffectType::Slowed,
+                HAMSTRING_ROUNDS,
+            )),
+            PassiveCombatAbility::Ambush => world.register_system(ambush_action),
+            PassiveCombatAbility::Vengeance => world.register_system(vengeance_action),
+            PassiveCombatAbility::Lucky => world.register_system(lucky_action),
+            PassiveCombatAbility::Evasive => world.register_system(evasive_action),
+            PassiveCombatAbility::Brutal => world.register_system(brutal_action),
+            PassiveCombatAbility::Regeneration => world.register_system(regeneration_action),
+        };
+        Some(system_id)
+    }
+
+    pub fn get_trigger(&self) -> AbilityTrigger {
         match self {
-            PassiveCombatAbility::ArmorBreak => world.register_system(armor_break_action),
-            PassiveCombatAbility::HitAndRun => world.register_system(hit_and_run_action),
+            PassiveCombatAbility::FirstStrike => AbilityTrigger::OnAttack(CombatPhase::PreCombat),
+            PassiveCombatAbility::PoisonedWeapons => {
+                AbilityTrigger::OnAttack(CombatPhase::PostCombat)
+            }
+            PassiveCombatAbility::Stunning => AbilityTrigger::OnCriticalHit,
+            PassiveCombatAbility::Hamstring => AbilityTrigger::OnAttack(CombatPhase::PostCombat),
+            PassiveCombatAbility::Charge => AbilityTrigger::OnAttack(CombatPhase::PreCombat),
+            PassiveCombatAbility::Ambush => AbilityTrigger::OnEngaged,
+            PassiveCombatAbility::Vengeance => AbilityTrigger::OnAllyKilled,
+            PassiveCombatAbility::Lucky | PassiveCombatAbility::Brutal => {
+                AbilityTrigger::OnAttack(CombatPhase::PreCombat)
+            }
+            PassiveCombatAbility::Evasive => AbilityTrigger::OnDefense(CombatPhase::PreCombat),
+            PassiveCombatAbility::Regeneration => AbilityTrigger::OnRoundEnd,
         }
     }
 
-    pub fn get_trigger(&self) -> AbilityTrigger {
+    /// Whether the ability takes effect once it is triggered by an attack with the given result
+    pub fn takes_effect_on(&self, combat_result: &CombatResult) -> bool {
+        match self {
+            PassiveCombatAbility::FirstStrike
+            | PassiveCombatAbility::Charge
+            | PassiveCombatAbility::Ambush
+            | PassiveCombatAbility::Vengeance
+            | PassiveCombatAbility::Evasive
+            | PassiveCombatAbility::Regeneration => true,
+            PassiveCombatAbility::Lucky => combat_result.is_miss(),
+            PassiveCombatAbility::PoisonedWeapons
+            | PassiveCombatAbility::Brutal
+            | PassiveCombatAbility::Stunning
+            | PassiveCombatAbility::Hamstring => combat_result.is_hit(),
+        }
+    }
+
+    /// The change to the attack roll the ability makes before the attack is rolled,
+    /// see [`CombatResource::roll_modifications`]
+    pub fn get_roll_modification(&self) -> Option<RollModification> {
+        let kind = match self {
+            PassiveCombatAbility::Lucky => RollModificationKind::Reroll,
+            PassiveCombatAbility::Evasive => RollModificationKind::Roll(EVASIVE_ROLL_MODIFIER),
+            PassiveCombatAbility::Brutal => RollModificationKind::Damage(BRUTAL_DAMAGE_MODIFIER),
+            _ => return None,
+        };
+        Some(RollModification {
+            kind,
+            source: self.to_string(),
+        })
+    }
+}
+
+impl Display for PassiveCombatAbility {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            PassiveCombatAbility::ArmorBreak => AbilityTrigger::OnAttack(CombatPhase::PostCombat),
-            PassiveCombatAbility::HitAndRun => AbilityTrigger::OnAttack(CombatPhase::PostCombat),
+            PassiveCombatAbility::FirstStrike => write!(f, "First strike"),
+            PassiveCombatAbility::PoisonedWeapons => write!(f, "Poisoned weapons"),
+            PassiveCombatAbility::Stunning => write!(f, "Stunning"),
+            PassiveCombatAbility::Hamstring => write!(f, "Hamstring"),
+            PassiveCombatAbility::Charge => write!(f, "Charge"),
+            PassiveCombatAbility::Ambush => write!(f, "Ambush"),
+            PassiveCombatAbility::Vengeance => write!(f, "Vengeance"),
+            PassiveCombatAbility::Lucky => write!(f, "Lucky"),
+            PassiveCombatAbility::Evasive => write!(f, "Evasive"),
+            PassiveCombatAbility::Brutal => write!(f, "Brutal"),
+            PassiveCombatAbility::Regeneration => write!(f, "Regeneration"),
         }
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub enum AbilityTrigger {
     OnAttack(CombatPhase),
     OnDefense(CombatPhase),
+    /// after an attack of the unit that was a [`CombatResult::CriticalHit`]
+    OnCriticalHit,
+    /// after an attack of the unit that was a [`CombatResult::Fumble`]
+    OnFumble,
+    /// at the start of the unit's turn
+    OnRoundStart,
+    /// at the end of the unit's turn
+    OnRoundEnd,
+    /// after every step the unit moves
+    OnMoveStep,
+    /// when the unit stops on the last hex of its path
+    OnEnterHex,
+    /// when an enemy moves next to the unit
+    OnEngaged,
+    /// when the unit dealt the killing blow to an enemy
+    OnKill,
+    /// when an adjacent ally was killed
+    OnAllyKilled,
+    /// when the unit lost health points
+    OnDamaged,
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+/// The unit whose passive abilities were triggered outside of combat, read by their systems like the [`CombatResource`] in combat
+#[derive(Resource, Debug, Clone)]
+pub struct TriggeredAbilityResource {
+    pub unit: Entity,
+    pub trigger: AbilityTrigger,
+    /// the enemy that engaged the unit, the unit that was killed or the unit that dealt the damage
+    pub other_unit: Option<Entity>,
+}
+
+/// Runs the unit's passive abilities with the trigger, which are not tied to a single attack
+pub fn run_triggered_abilities(
+    commands: &mut Commands,
+    unit: Entity,
+    other_unit: Option<Entity>,
+    combat_config: &CombatConfig,
+    trigger: AbilityTrigger,
+) {
+    let abilities: Vec<_> = combat_config
+        .passive_combat_abilities
+        .iter()
+        .filter(|ability| ability.ability_trigger == trigger)
+        .collect();
+    if abilities.is_empty() {
+        return;
+    }
+
+    commands.insert_resource(TriggeredAbilityResource {
+        unit,
+        trigger,
+        other_unit,
+    });
+    for ability in abilities {
+        debug!("{unit:?} trying {}", ability.ability);
+        if let Some(system_id) = ability.system_id {
+            commands.run_system(system_id);
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Hash, PartialEq, Eq)]
 pub enum CombatPhase {
     PreCombat,
     PostCombat,
 }
 
-pub struct PassiveCombatAbilityRegistry(HashMap<PassiveCombatAbility, SystemId>);
+pub struct PassiveCombatAbilityRegistry(HashMap<PassiveCombatAbility, Option<SystemId>>);
 
 impl FromWorld for PassiveCombatAbilityRegistry {
     fn from_world(world: &mut World) -> Self {
@@ -76,49 +299,114 @@ impl PassiveCombatAbilityRegistry {
         let ability_trigger = ability.get_trigger();
 
         RegisteredPassiveCombatAbility {
-            ability,
+            ability: PassiveAbility::BuiltIn(ability),
             system_id,
             ability_trigger,
         }
     }
 }
 
-fn armor_break_action(
-    mut units: Query<(&mut CombatConfig, &UnitMarker)>,
-    combat_resource: Res<CombatResource>,
-    mut log_event: EventWriter<LogEvent>,
-) {
-    if combat_resource.combat_result != CombatResult::Miss {
-        return;
-    }
+type StatusEffectUnitsQuery<'world, 'state, 'a> =
+    Query<'world, 'state, (&'a mut StatusEffects, &'a UnitMarker)>;
 
-    if let Ok((mut combat_config, unit_marker)) = units.get_mut(combat_resource.defender) {
-        log_event.send(LogEvent {
-            message: format!(
-                "{} has lost 1 defense point due to armor break",
-                unit_marker.0
-            ),
-        });
-        combat_config.defense -= 1;
+/// The system of an ability that affects the defender with the status effect for the rounds once it takes effect
+fn status_effect_action(
+    ability: PassiveCombatAbility,
+    effect_type: StatusEffectType,
+    remaining_rounds: usize,
+) -> impl FnMut(StatusEffectUnitsQuery, Res<CombatResource>, EventWriter<LogEvent>) {
+    move |mut units, combat_resource, mut log_event| {
+        if !ability.takes_effect_on(&combat_resource.combat_result) {
+            return;
+        }
+
+        if let Ok((mut status_effects, unit_marker)) = units.get_mut(combat_resource.defender) {
+            log_event.send(LogEvent {
+                message: format!(
+                    "{} is affected by {effect_type} for {remaining_rounds} rounds due to {ability}",
+                    unit_marker.0
+                ),
+            });
+            status_effects.add(StatusEffect {
+                effect_type,
+                source: ability.to_string(),
+                stacks: 1,
+                remaining_rounds,
+            });
+        }
     }
 }
 
-fn hit_and_run_action(
-    mut units: Query<(&mut UnitStatus, &UnitMarker)>,
-    combat_resource: Res<CombatResource>,
+/// Ambushers that already spent their attacks, action points or ammo this round let the enemy pass
+fn ambush_action(
+    triggered_ability: Res<TriggeredAbilityResource>,
+    units: Query<(&UnitMarker, &ActionPoints, &CombatConfig)>,
+    mut combat_event: EventWriter<CombatEvent>,
     mut log_event: EventWriter<LogEvent>,
 ) {
-    if combat_resource.combat_result != CombatResult::Hit {
+    let Some(enemy) = triggered_ability.other_unit else {
+        return;
+    };
+    let Ok((unit_marker, action_points, combat_config)) = units.get(triggered_ability.unit) else {
+        return;
+    };
+    if !action_points.can_still_attack_with(&combat_config.get_default_attack()) {
         return;
     }
+    log_event.send(LogEvent {
+        message: format!("{} springs an ambush", unit_marker.0),
+    });
+    combat_event.send(CombatEvent {
+        attacker: triggered_ability.unit,
+        attack: AttackOrDefault::Default,
+        defender: enemy,
+    });
+}
 
-    if let Ok((mut unit_status, unit_marker)) = units.get_mut(combat_resource.attacker) {
+fn vengeance_action(
+    triggered_ability: Res<TriggeredAbilityResource>,
+    mut units: Query<(&mut StatModifiers, &UnitMarker)>,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    if let Ok((mut stat_modifiers, unit_marker)) = units.get_mut(triggered_ability.unit) {
         log_event.send(LogEvent {
-            message: format!(
-                "{} {:?} disengaged due to Hit&Run",
-                unit_marker.0, combat_resource.attacker
-            ),
+            message: format!("{} swears vengeance", unit_marker.0),
+        });
+        stat_modifiers.add(StatModifier {
+            stat: Stat::Defense,
+            value: ModifierValue::Add(VENGEANCE_DEFENSE_BONUS),
+            source: PassiveCombatAbility::Vengeance.to_string(),
+            remaining_rounds: Some(VENGEANCE_ROUNDS),
         });
-        unit_status.disengage_with(&combat_resource.defender);
     }
 }
+
+/// Roll modifications are applied once the attack is rolled, see [`CombatResource::roll_modifications`]
+fn add_roll_modification(combat_resource: &mut CombatResource, ability: PassiveCombatAbility) {
+    combat_resource
+        .roll_modifications
+        .extend(ability.get_roll_modification());
+}
+
+fn lucky_action(mut combat_resource: ResMut<CombatResource>) {
+    add_roll_modification(&mut combat_resource, PassiveCombatAbility::Lucky);
+}
+
+fn evasive_action(mut combat_resource: ResMut<CombatResource>) {
+    add_roll_modification(&mut combat_resource, PassiveCombatAbility::Evasive);
+}
+
+fn brutal_action(mut combat_resource: ResMut<CombatResource>) {
+    add_roll_modification(&mut combat_resource, PassiveCombatAbility::Brutal);
+}
+
+fn regeneration_action(
+    triggered_ability: Res<TriggeredAbilityResource>,
+    mut heal_event: EventWriter<HealEvent>,
+) {
+    heal_event.send(HealEvent {
+        unit: triggered_ability.unit,
+        amount: REGENERATION_HEALING,
+        source: "regeneration".to_string(),
+    });
+}
@@ -0,0 +1,273 @@
+use bevy::prelude::{Asset, Assets, Commands, Handle, Res, ResMut, Resource};
+use bevy::reflect::TypePath;
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::AssetCollection;
+
+#[cfg(doc)]
+use crate::game::abilities::active_abilities::ActiveAbility;
+use crate::game::abilities::passive_combat_abilities::AbilityTrigger;
+use crate::game::asset_loading::nation_asset_resource::NationAssetsResource;
+#[cfg(doc)]
+use crate::game::asset_loading::nation_assets::UnitStats;
+use crate::game::asset_loading::AssetLoadingErrors;
+use crate::game::ingame::combat::CombatResult;
+use crate::game::ingame::damage_type::DamageType;
+use crate::game::ingame::stat_modifiers::{ModifierValue, Stat};
+use crate::game::ingame::status_effects::StatusEffectType;
+use crate::game::util::dice_expression::DiceExpression;
+
+#[derive(AssetCollection, Resource, Debug, Clone)]
+pub struct AbilityAssetCollection {
+    #[asset(key = "ability_files", collection(typed, mapped))]
+    pub ability_files: HashMap<String, Handle<AbilityDefinition>>,
+}
+
+/// An ability composed of effects, which are applied to its targets once it is triggered and all conditions hold
+#[derive(serde::Deserialize, serde::Serialize, TypePath, Clone, Debug, PartialEq, Asset)]
+pub struct AbilityDefinition {
+    /// units refer to the ability by this name, see [`UnitStats::abilities`]
+    pub name: String,
+    pub trigger: AbilityActivation,
+    pub target: AbilityTarget,
+    #[serde(default)]
+    pub conditions: Vec<AbilityCondition>,
+    pub effects: Vec<AbilityEffect>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub enum AbilityActivation {
+    /// triggered during the game like the built-in passive combat abilities
+    Passive(AbilityTrigger),
+    /// used by the player on a unit within range, see [`ActiveAbility::Defined`]
+    ///
+    /// Costs the unit's attack action points, abilities that deal damage pay them per attack instead
+    Active {
+        range: u32,
+        targets: Allegiance,
+        /// the number of times the ability can be used, unlimited if not set
+        #[serde(default)]
+        ammo: Option<usize>,
+    },
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Allegiance {
+    Enemy,
+    Ally,
+}
+
+/// The units the effects of an ability are applied to
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityTarget {
+    /// the unit that has the ability
+    Owner,
+    /// the enemy in combat, the unit that triggered the ability or the unit picked for an active ability
+    OtherUnit,
+    AdjacentAllies,
+    AdjacentEnemies,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AbilityCondition {
+    /// the attack that triggered the ability hit, critical hits included
+    Hit,
+    /// the attack that triggered the ability missed, fumbles included
+    Miss,
+    CriticalHit,
+    Fumble,
+}
+
+impl AbilityCondition {
+    pub fn holds_for(&self, combat_result: &CombatResult) -> bool {
+        match self {
+            AbilityCondition::Hit => combat_result.is_hit(),
+            AbilityCondition::Miss => combat_result.is_miss(),
+            AbilityCondition::CriticalHit => combat_result == &CombatResult::CriticalHit,
+            AbilityCondition::Fumble => combat_result == &CombatResult::Fumble,
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub enum AbilityEffect {
+    /// lasts until the end of the game if no rounds are set
+    ModifyStat {
+        stat: Stat,
+        value: ModifierValue,
+        #[serde(default)]
+        rounds: Option<usize>,
+    },
+    /// The owner attacks the target, rolled like any other attack named after the ability
+    DealDamage {
+        damage: DiceExpression,
+        #[serde(default)]
+        damage_type: DamageType,
+    },
+    ApplyStatus {
+        effect: StatusEffectType,
+        rounds: usize,
+    },
+    /// Pushes the target away from the owner, or the owner away from the other unit, until a hex is blocked
+    MoveUnit { distance: u32 },
+    /// The target is no longer engaged with the owner, or the owner with the other unit
+    Disengage,
+}
+
+impl AbilityDefinition {
+    /// Whether the ability takes effect once it is triggered by an attack with the given result
+    pub fn takes_effect_on(&self, combat_result: &CombatResult) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds_for(combat_result))
+    }
+
+    pub fn deals_damage(&self) -> bool {
+        self.effects
+            .iter()
+            .any(|effect| matches!(effect, AbilityEffect::DealDamage { .. }))
+    }
+
+    /// Conditions only make sense for abilities triggered by an attack, which has a result they can hold for
+    pub fn validate(&self) -> Result<(), String> {
+        let is_triggered_by_attack = matches!(
+            self.trigger,
+            AbilityActivation::Passive(
+                AbilityTrigger::OnAttack(_)
+                    | AbilityTrigger::OnDefense(_)
+                    | AbilityTrigger::OnCriticalHit
+                    | AbilityTrigger::OnFumble
+            )
+        );
+        if !self.conditions.is_empty() && !is_triggered_by_attack {
+            return Err(format!(
+                "{} has conditions, but is not triggered by an attack",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Resource, Default)]
+pub struct AbilityAssetsResource {
+    /// by the name of the ability
+    pub(crate) abilities: HashMap<String, AbilityDefinition>,
+}
+
+pub(super) fn insert_ability_assets_resource(
+    mut commands: Commands,
+    ability_assets_collection: Res<AbilityAssetCollection>,
+    ability_assets: Res<Assets<AbilityDefinition>>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
+) {
+    let mut abilities = HashMap::new();
+    for (path, handle) in &ability_assets_collection.ability_files {
+        let Some(ability) = ability_assets.get(handle) else {
+            asset_loading_errors
+                .0
+                .push(format!("The ability file {path} could not be loaded"));
+            continue;
+        };
+        if let Err(error) = ability.validate() {
+            asset_loading_errors.0.push(format!("{path}: {error}"));
+        }
+        if abilities
+            .insert(ability.name.clone(), ability.clone())
+            .is_some()
+        {
+            asset_loading_errors.0.push(format!(
+                "More than one ability file defines an ability named {}",
+                ability.name
+            ));
+        }
+    }
+
+    commands.insert_resource(AbilityAssetsResource { abilities });
+
+    commands.remove_resource::<AbilityAssetCollection>();
+}
+
+/// Every ability a unit refers to has to be defined in an ability file
+pub(super) fn validate_unit_abilities(
+    nation_assets: Res<NationAssetsResource>,
+    ability_assets: Res<AbilityAssetsResource>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
+) {
+    for (path, unit_stats) in &nation_assets.unit_stats {
+        for name in unit_stats
+            .abilities
+            .iter()
+            .filter(|name| !ability_assets.abilities.contains_key(*name))
+        {
+            asset_loading_errors.0.push(format!(
+                "{path} refers to the ability {name}, which no ability file defines"
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throw_javelin_is_an_active_ability_with_one_javelin() {
+        let definition: AbilityDefinition = ron::from_str(include_str!(
+            "../../../assets/abilities/throw_javelin.ability.ron"
+        ))
+        .unwrap();
+
+        assert_eq!(
+            definition.trigger,
+            AbilityActivation::Active {
+                range: 2,
+                targets: Allegiance::Enemy,
+                ammo: Some(1),
+            }
+        );
+        assert_eq!(definition.target, AbilityTarget::OtherUnit);
+        assert!(definition.deals_damage());
+        assert!(definition.takes_effect_on(&CombatResult::Miss));
+    }
+
+    #[test]
+    fn all_conditions_must_hold_for_the_combat_result() {
+        let definition = AbilityDefinition {
+            name: "test".to_string(),
+            trigger: AbilityActivation::Passive(AbilityTrigger::OnCriticalHit),
+            target: AbilityTarget::OtherUnit,
+            conditions: vec![AbilityCondition::Hit, AbilityCondition::CriticalHit],
+            effects: vec![AbilityEffect::Disengage],
+        };
+
+        assert!(definition.takes_effect_on(&CombatResult::CriticalHit));
+        assert!(!definition.takes_effect_on(&CombatResult::Hit));
+        assert!(!definition.takes_effect_on(&CombatResult::Miss));
+    }
+
+    #[test]
+    fn only_abilities_triggered_by_an_attack_can_have_conditions() {
+        let mut definition = AbilityDefinition {
+            name: "test".to_string(),
+            trigger: AbilityActivation::Passive(AbilityTrigger::OnRoundStart),
+            target: AbilityTarget::Owner,
+            conditions: vec![AbilityCondition::Hit],
+            effects: vec![AbilityEffect::Disengage],
+        };
+        assert!(definition.validate().is_err());
+
+        definition.trigger = AbilityActivation::Active {
+            range: 1,
+            targets: Allegiance::Enemy,
+            ammo: None,
+        };
+        assert!(definition.validate().is_err());
+
+        definition.trigger = AbilityActivation::Passive(AbilityTrigger::OnFumble);
+        assert!(definition.validate().is_ok());
+
+        definition.trigger = AbilityActivation::Passive(AbilityTrigger::OnRoundStart);
+        definition.conditions.clear();
+        assert!(definition.validate().is_ok());
+    }
+}
@@ -0,0 +1,140 @@
+use bevy::prelude::{Asset, Assets, Commands, Handle, Res, ResMut, Resource};
+use bevy::reflect::TypePath;
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::AssetCollection;
+use hexx::Hex;
+
+use crate::game::asset_loading::AssetLoadingErrors;
+
+#[derive(AssetCollection, Resource, Debug, Clone)]
+pub struct MapAssetCollection {
+    #[asset(key = "map_files", collection(typed, mapped))]
+    pub map_files: HashMap<String, Handle<MapDefinition>>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, TypePath, Clone, Debug, PartialEq, Asset)]
+pub struct MapDefinition {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub hexes: Vec<MapHexDefinition>,
+}
+
+impl MapDefinition {
+    /// The width and height have to match the extent of the hexes
+    pub fn validate(&self) -> Result<(), String> {
+        let width = get_extent(self.hexes.iter().map(|hex| hex.x));
+        let height = get_extent(self.hexes.iter().map(|hex| hex.y));
+        if (width, height) != (self.width, self.height) {
+            return Err(format!(
+                "{} is declared as {}x{}, but its hexes span {width}x{height}",
+                self.name, self.width, self.height
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn get_extent(coordinates: impl Iterator<Item = i32> + Clone) -> u32 {
+    match (coordinates.clone().min(), coordinates.max()) {
+        (Some(min), Some(max)) => max.abs_diff(min) + 1,
+        _ => 0,
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct MapHexDefinition {
+    pub x: i32,
+    pub y: i32,
+    pub terrain: String,
+    #[serde(default)]
+    pub elevation: u32,
+}
+
+impl MapHexDefinition {
+    pub fn get_hex(&self) -> Hex {
+        Hex::new(self.x, self.y)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MapKey(pub String);
+
+#[derive(Debug, Clone, Resource, Default)]
+pub struct MapAssetsResource {
+    pub(crate) maps: HashMap<String, MapDefinition>,
+}
+
+impl MapAssetsResource {
+    pub fn get_maps(&self) -> Vec<MapKey> {
+        let mut map_keys: Vec<_> = self.maps.keys().cloned().map(MapKey).collect();
+        map_keys.sort_by(|left, right| left.0.cmp(&right.0));
+        map_keys
+    }
+
+    pub fn get_map(&self, map_key: &MapKey) -> Option<&MapDefinition> {
+        self.maps.get(&map_key.0)
+    }
+}
+
+pub(super) fn insert_map_assets_resource(
+    mut commands: Commands,
+    map_assets_collection: Res<MapAssetCollection>,
+    map_assets: Res<Assets<MapDefinition>>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
+) {
+    let mut maps = HashMap::new();
+    for (path, handle) in &map_assets_collection.map_files {
+        let Some(map) = map_assets.get(handle) else {
+            asset_loading_errors
+                .0
+                .push(format!("The map file {path} could not be loaded"));
+            continue;
+        };
+        if let Err(error) = map.validate() {
+            asset_loading_errors.0.push(format!("{path}: {error}"));
+        }
+        maps.insert(path.clone(), map.clone());
+    }
+
+    commands.insert_resource(MapAssetsResource { maps });
+
+    commands.remove_resource::<MapAssetCollection>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_maps_match_their_declared_size() {
+        for map_file in [
+            include_str!("../../../assets/maps/default.map.ron"),
+            include_str!("../../../assets/maps/river_crossing.map.ron"),
+        ] {
+            let map: MapDefinition = ron::from_str(map_file).unwrap();
+
+            assert_eq!(map.validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn maps_with_hexes_outside_their_declared_size_are_invalid() {
+        let map = MapDefinition {
+            name: "too small".to_string(),
+            width: 1,
+            height: 1,
+            hexes: Hex::ZERO
+                .spiral_range(0..2)
+                .map(|hex| MapHexDefinition {
+                    x: hex.x,
+                    y: hex.y,
+                    terrain: "plains".to_string(),
+                    elevation: 0,
+                })
+                .collect(),
+        };
+
+        assert!(map.validate().is_err());
+    }
+}
@@ -1,17 +1,35 @@
 use bevy::app::App;
-use bevy::prelude::{OnEnter, Plugin};
+use bevy::asset::UntypedAssetLoadFailedEvent;
+use bevy::log::error;
+use bevy::prelude::{
+    EventReader, IntoSystemConfigs, NextState, OnEnter, Plugin, Res, ResMut, Resource, Update,
+};
 use bevy_asset_loader::prelude::{ConfigureLoadingState, LoadingStateAppExt};
 use bevy_common_assets::ron::RonAssetPlugin;
 
 use crate::common::DynamicNationAssetsDefinition;
+use crate::game::abilities::defined_abilities::register_defined_abilities;
+use crate::game::asset_loading::ability_assets::{
+    insert_ability_assets_resource, validate_unit_abilities, AbilityAssetCollection,
+    AbilityDefinition,
+};
+use crate::game::asset_loading::map_assets::{
+    insert_map_assets_resource, MapAssetCollection, MapDefinition,
+};
 use crate::game::asset_loading::nation_asset_resource::{
     insert_nation_assets_resource, NationAssetsResourceHelperAssets,
 };
 use crate::game::asset_loading::nation_assets::{LoadingState, NationAssetCollection, UnitStats};
+use crate::game::asset_loading::terrain_assets::{
+    insert_terrain_assets_resource, TerrainAssetCollection, TerrainRegistryDefinition,
+};
 use crate::scan_assets::GENERATED_NATIONS_ASSETS_FILE;
 
+pub mod ability_assets;
+pub mod map_assets;
 pub mod nation_asset_resource;
 pub mod nation_assets;
+pub mod terrain_assets;
 
 pub struct AssetLoadingPlugin;
 
@@ -20,26 +38,138 @@ impl Plugin for AssetLoadingPlugin {
         app.add_plugins((
             RonAssetPlugin::<DynamicNationAssetsDefinition>::new(&["assets.ron"]),
             RonAssetPlugin::<UnitStats>::new(&["stats.ron"]),
+            RonAssetPlugin::<MapDefinition>::new(&["map.ron"]),
+            RonAssetPlugin::<TerrainRegistryDefinition>::new(&["terrain.ron"]),
+            RonAssetPlugin::<AbilityDefinition>::new(&["ability.ron"]),
         ))
         .init_state::<LoadingState>()
+        .init_resource::<AssetLoadingErrors>()
         .add_loading_state(
             bevy_asset_loader::loading_state::LoadingState::new(LoadingState::LoadingDynamicAssets)
                 .continue_to_state(LoadingState::LoadingNationAssetsDefinition)
+                .on_failure_continue_to_state(LoadingState::Failed)
                 .set_standard_dynamic_asset_collection_file_endings(vec![])
                 .register_dynamic_asset_collection::<DynamicNationAssetsDefinition>()
                 .with_dynamic_assets_file::<DynamicNationAssetsDefinition>(
                     GENERATED_NATIONS_ASSETS_FILE,
                 )
-                .load_collection::<NationAssetCollection>(),
+                .load_collection::<NationAssetCollection>()
+                .load_collection::<MapAssetCollection>()
+                .load_collection::<TerrainAssetCollection>()
+                .load_collection::<AbilityAssetCollection>(),
         )
         .add_loading_state(
             bevy_asset_loader::loading_state::LoadingState::new(
                 LoadingState::LoadingNationAssetsDefinition,
             )
             .continue_to_state(LoadingState::Done)
+            .on_failure_continue_to_state(LoadingState::Failed)
             .set_standard_dynamic_asset_collection_file_endings(vec![])
             .load_collection::<NationAssetsResourceHelperAssets>(),
         )
-        .add_systems(OnEnter(LoadingState::Done), insert_nation_assets_resource);
+        .add_systems(Update, record_asset_load_failures)
+        .add_systems(
+            OnEnter(LoadingState::Done),
+            (
+                (
+                    insert_nation_assets_resource,
+                    insert_map_assets_resource,
+                    insert_terrain_assets_resource,
+                    (insert_ability_assets_resource, register_defined_abilities).chain(),
+                ),
+                validate_unit_abilities,
+                report_asset_loading_errors,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Problems with the asset files found once they are loaded, a game can not be started with them
+#[derive(Resource, Debug, Default)]
+pub struct AssetLoadingErrors(pub Vec<String>);
+
+/// Files that can not be loaded, like RON files with syntax errors, stop the loading states in [`LoadingState::Failed`]
+fn record_asset_load_failures(
+    mut asset_load_failed_events: EventReader<UntypedAssetLoadFailedEvent>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
+) {
+    for asset_load_failed_event in asset_load_failed_events.read() {
+        let asset_loading_error = format!(
+            "The file {} could not be loaded: {}",
+            asset_load_failed_event.path, asset_load_failed_event.error
+        );
+        error!("{asset_loading_error}");
+        asset_loading_errors.0.push(asset_loading_error);
+    }
+}
+
+fn report_asset_loading_errors(
+    asset_loading_errors: Res<AssetLoadingErrors>,
+    mut loading_state: ResMut<NextState<LoadingState>>,
+) {
+    if asset_loading_errors.0.is_empty() {
+        return;
+    }
+    for asset_loading_error in &asset_loading_errors.0 {
+        error!("{asset_loading_error}");
+    }
+    loading_state.set(LoadingState::Failed);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::asset::{AssetPlugin, AssetServer, Handle};
+    use bevy::prelude::{default, MinimalPlugins};
+
+    use super::*;
+
+    #[test]
+    fn invalid_dice_expressions_in_unit_stats_are_reported_with_the_file_path() {
+        let asset_folder = std::env::temp_dir().join("invalid_dice_expression_assets");
+        std::fs::create_dir_all(&asset_folder).unwrap();
+        std::fs::write(
+            asset_folder.join("broken.unit.stats.ron"),
+            r#"(
+                name: "Broken",
+                max_action_points: 5,
+                max_health_points: 5,
+                damage: "1d",
+                defense: 10,
+                passive_combat_abilities: [],
+                active_abilities: [],
+            )"#,
+        )
+        .unwrap();
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: asset_folder.to_string_lossy().to_string(),
+                ..default()
+            },
+            RonAssetPlugin::<UnitStats>::new(&["stats.ron"]),
+        ))
+        .init_resource::<AssetLoadingErrors>()
+        .add_systems(Update, record_asset_load_failures);
+
+        let _handle: Handle<UnitStats> = app
+            .world
+            .resource::<AssetServer>()
+            .load("broken.unit.stats.ron");
+        for _ in 0..100 {
+            app.update();
+            if !app.world.resource::<AssetLoadingErrors>().0.is_empty() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let asset_loading_errors = &app.world.resource::<AssetLoadingErrors>().0;
+        assert_eq!(asset_loading_errors.len(), 1);
+        assert!(asset_loading_errors[0].contains("broken.unit.stats.ron"));
+        assert!(asset_loading_errors[0].contains("Invalid term '1d' in dice expression '1d'"));
     }
 }
@@ -1,10 +1,11 @@
-use bevy::prelude::{Assets, Commands, Handle, Image, Res, Resource};
+use bevy::prelude::{Assets, Commands, Handle, Image, Res, ResMut, Resource};
 use bevy::utils::HashMap;
 use bevy_asset_loader::prelude::AssetCollection;
 
 use crate::common::DynamicNationAssetsDefinition;
 use crate::common::NationAssetsDefinition;
 use crate::game::asset_loading::nation_assets::{NationAssetCollection, UnitKey, UnitStats};
+use crate::game::asset_loading::AssetLoadingErrors;
 
 #[derive(AssetCollection, Resource)]
 pub struct NationAssetsResourceHelperAssets {
@@ -77,22 +78,25 @@ pub(super) fn insert_nation_assets_resource(
     nation_assets_collection: Res<NationAssetCollection>,
     dynamic_nation_assets: Res<Assets<DynamicNationAssetsDefinition>>,
     unit_stats_assets: Res<Assets<UnitStats>>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
 ) {
     let dynamic_nation_assets = dynamic_nation_assets
         .get(&nation_assets_resource_helper.handle)
         .expect("The DynamicNationAssets should be loaded by now");
 
-    let unit_stats = nation_assets_collection
-        .unit_stats_files
-        .iter()
-        .map(|(key, handle)| {
-            let stats = unit_stats_assets.get(handle).unwrap().clone();
-            (key.clone(), stats)
-        })
-        .collect();
+    let mut unit_stats = HashMap::new();
+    for (path, handle) in &nation_assets_collection.unit_stats_files {
+        let Some(stats) = unit_stats_assets.get(handle) else {
+            asset_loading_errors
+                .0
+                .push(format!("The unit stats file {path} could not be loaded"));
+            continue;
+        };
+        unit_stats.insert(path.clone(), stats.clone());
+    }
 
     commands.insert_resource(NationAssetsResource {
-        nation_assets_definition: dynamic_nation_assets.0.clone(),
+        nation_assets_definition: dynamic_nation_assets.nations.clone(),
         unit_images: nation_assets_collection.unit_images.clone(),
         unit_stats,
     });
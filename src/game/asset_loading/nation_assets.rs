@@ -1,5 +1,10 @@
 use crate::game::abilities::active_abilities::ActiveAbilityType;
 use crate::game::abilities::passive_combat_abilities::PassiveCombatAbility;
+#[cfg(doc)]
+use crate::game::asset_loading::ability_assets::AbilityDefinition;
+use crate::game::ingame::area_of_effect::AreaOfEffect;
+use crate::game::ingame::damage_type::{DamageResistances, DamageType};
+use crate::game::util::dice_expression::DiceExpression;
 use anyhow::Error;
 use bevy::prelude::{Asset, AssetServer, Handle, Image, Resource, States, UntypedHandle, World};
 use bevy::reflect::TypePath;
@@ -97,16 +102,78 @@ pub struct UnitStats {
     pub name: String,
     pub max_action_points: usize,
     pub max_health_points: usize,
-    pub damage: usize,
+    pub damage: DiceExpression,
+    #[serde(default)]
+    pub damage_type: DamageType,
     pub defense: usize,
+    /// flat reduction of physical damage taken
+    #[serde(default)]
+    pub armor: usize,
+    #[serde(default)]
+    pub resistances: Vec<DamageType>,
+    #[serde(default)]
+    pub vulnerabilities: Vec<DamageType>,
     #[serde(default = "default_attack_action_point_cost")]
     pub attack_action_point_cost: usize,
     #[serde(default = "default_max_attacks_per_round")]
     pub max_attacks_per_round: usize,
     #[serde(default = "default_range")]
     pub range: u32,
+    /// the number of times the default attack can be used, unlimited if not set
+    #[serde(default)]
+    pub ammo: Option<usize>,
+    #[serde(default = "default_can_counter")]
+    pub can_counter: bool,
+    /// A morale check succeeds if a D20 roll does not exceed this value
+    #[serde(default = "default_morale")]
+    pub morale: usize,
+    /// rallies nearby shaken and routing allies at the start of its turn
+    #[serde(default)]
+    pub commander: bool,
+    /// refills the ammo of adjacent allies at the start of their turn
+    #[serde(default)]
+    pub supplier: bool,
     pub passive_combat_abilities: Vec<PassiveCombatAbility>,
     pub active_abilities: Vec<ActiveAbilityType>,
+    /// overrides the default ammo of active abilities
+    #[serde(default)]
+    pub active_ability_ammo: HashMap<ActiveAbilityType, usize>,
+    /// named attacks besides the default attack described above
+    #[serde(default)]
+    pub attacks: Vec<AttackProfile>,
+    /// names of passive and active abilities from the ability files, see [`AbilityDefinition`]
+    #[serde(default)]
+    pub abilities: Vec<String>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct AttackProfile {
+    pub name: String,
+    pub damage: DiceExpression,
+    #[serde(default)]
+    pub damage_type: DamageType,
+    #[serde(default = "default_range")]
+    pub range: u32,
+    /// defaults to the unit's attack action point cost
+    #[serde(default)]
+    pub action_point_cost: Option<usize>,
+    #[serde(default)]
+    pub passive_combat_abilities: Vec<PassiveCombatAbility>,
+    /// the number of times the attack can be used, unlimited if not set
+    #[serde(default)]
+    pub ammo: Option<usize>,
+    #[serde(default)]
+    pub area: Option<AreaOfEffect>,
+}
+
+impl UnitStats {
+    pub fn get_damage_resistances(&self) -> DamageResistances {
+        DamageResistances {
+            armor: self.armor,
+            resistances: self.resistances.clone(),
+            vulnerabilities: self.vulnerabilities.clone(),
+        }
+    }
 }
 
 fn default_attack_action_point_cost() -> usize {
@@ -121,10 +188,20 @@ fn default_range() -> u32 {
     1
 }
 
+fn default_can_counter() -> bool {
+    true
+}
+
+fn default_morale() -> usize {
+    12
+}
+
 #[derive(Debug, Default, Clone, States, PartialEq, Eq, Hash)]
 pub enum LoadingState {
     #[default]
     LoadingDynamicAssets,
     LoadingNationAssetsDefinition,
     Done,
+    /// some asset files could not be loaded or are invalid, see [`AssetLoadingErrors`]
+    Failed,
 }
@@ -0,0 +1,133 @@
+use bevy::prelude::{Asset, Assets, Commands, Handle, Res, ResMut, Resource};
+use bevy::reflect::TypePath;
+use bevy::utils::HashMap;
+use bevy_asset_loader::prelude::AssetCollection;
+
+use crate::game::asset_loading::AssetLoadingErrors;
+use crate::game::ingame::line_of_sight::LineOfSight;
+use crate::game::ingame::terrain::{MovementCost, Terrain};
+
+#[derive(AssetCollection, Resource, Debug, Clone)]
+pub struct TerrainAssetCollection {
+    #[asset(path = "default.terrain.ron")]
+    pub terrain_registry: Handle<TerrainRegistryDefinition>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, TypePath, Clone, Debug, PartialEq, Asset)]
+pub struct TerrainRegistryDefinition {
+    pub terrains: HashMap<String, TerrainDefinition>,
+}
+
+impl TerrainRegistryDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.terrains.is_empty() {
+            return Err("The terrain registry defines no terrains".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct TerrainDefinition {
+    pub name: String,
+    pub movement_cost: MovementCost,
+    pub display: TerrainDisplay,
+    #[serde(default)]
+    pub attack_modifier: i32,
+    #[serde(default)]
+    pub defense_modifier: i32,
+    #[serde(default)]
+    pub line_of_sight: LineOfSight,
+    #[serde(default)]
+    pub stops_charges: bool,
+    #[serde(default)]
+    pub attack_disadvantage: bool,
+}
+
+impl TerrainDefinition {
+    pub fn get_terrain(&self) -> Terrain {
+        Terrain {
+            name: self.name.clone(),
+            movement_cost: self.movement_cost.clone(),
+            attack_modifier: self.attack_modifier,
+            defense_modifier: self.defense_modifier,
+            line_of_sight: self.line_of_sight,
+            stops_charges: self.stops_charges,
+            attack_disadvantage: self.attack_disadvantage,
+        }
+    }
+}
+
+/// How a hex of this terrain is drawn: either a plain rgb color or an image path relative to the assets folder
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub enum TerrainDisplay {
+    Color(f32, f32, f32),
+    Texture(String),
+}
+
+#[derive(Debug, Clone, Resource, Default)]
+pub struct TerrainAssetsResource {
+    pub(crate) terrains: HashMap<String, TerrainDefinition>,
+}
+
+impl TerrainAssetsResource {
+    pub fn get_terrain_definition(&self, terrain_key: &str) -> Option<&TerrainDefinition> {
+        self.terrains.get(terrain_key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TerrainDefinition)> {
+        self.terrains.iter()
+    }
+}
+
+pub(super) fn insert_terrain_assets_resource(
+    mut commands: Commands,
+    terrain_assets_collection: Res<TerrainAssetCollection>,
+    terrain_registry_assets: Res<Assets<TerrainRegistryDefinition>>,
+    mut asset_loading_errors: ResMut<AssetLoadingErrors>,
+) {
+    let handle = &terrain_assets_collection.terrain_registry;
+    let path = handle
+        .path()
+        .map_or_else(|| "default.terrain.ron".to_string(), ToString::to_string);
+    let terrains = match terrain_registry_assets.get(handle) {
+        Some(terrain_registry) => {
+            if let Err(error) = terrain_registry.validate() {
+                asset_loading_errors.0.push(format!("{path}: {error}"));
+            }
+            terrain_registry.terrains.clone()
+        }
+        None => {
+            asset_loading_errors
+                .0
+                .push(format!("The terrain registry {path} could not be loaded"));
+            HashMap::new()
+        }
+    };
+
+    commands.insert_resource(TerrainAssetsResource { terrains });
+
+    commands.remove_resource::<TerrainAssetCollection>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_default_terrain_registry_is_valid() {
+        let terrain_registry: TerrainRegistryDefinition =
+            ron::from_str(include_str!("../../../assets/default.terrain.ron")).unwrap();
+
+        assert_eq!(terrain_registry.validate(), Ok(()));
+    }
+
+    #[test]
+    fn a_terrain_registry_without_terrains_is_invalid() {
+        let terrain_registry = TerrainRegistryDefinition {
+            terrains: HashMap::new(),
+        };
+
+        assert!(terrain_registry.validate().is_err());
+    }
+}
@@ -1,5 +1,9 @@
 use bevy::prelude::{Component, Query};
 
+use crate::game::ingame::combat::Attack;
+use crate::game::ingame::stat_modifiers::{EffectiveStat, Stat, StatModifiers};
+use crate::game::ingame::status_effects::StatusEffects;
+
 #[derive(Component, Debug)]
 pub struct ActionPoints {
     max: usize,
@@ -35,11 +39,37 @@ impl ActionPoints {
     pub fn can_still_attack_this_turn(&self) -> bool {
         self.attacks_this_round < self.max_attacks && self.left >= self.attack_action_point_cost
     }
+
+    /// Attacks without an action point cost of their own cost the unit's attack action point cost
+    pub fn get_attack_cost(&self, attack: &Attack) -> usize {
+        attack
+            .action_point_cost
+            .unwrap_or(self.attack_action_point_cost)
+    }
+
+    /// The action points the unit starts its turn with
+    pub fn get_effective_max(
+        &self,
+        stat_modifiers: &StatModifiers,
+        status_effects: &StatusEffects,
+    ) -> EffectiveStat {
+        stat_modifiers.resolve(Stat::ActionPoints, self.max, status_effects)
+    }
+
+    pub fn can_still_attack_with(&self, attack: &Attack) -> bool {
+        self.attacks_this_round < self.max_attacks
+            && self.left >= self.get_attack_cost(attack)
+            && attack.has_ammo_left()
+    }
 }
 
-pub(super) fn reset_action_points(mut action_points_entities: Query<&mut ActionPoints>) {
-    for mut action_points in &mut action_points_entities {
-        action_points.left = action_points.max;
+pub(super) fn reset_action_points(
+    mut action_points_entities: Query<(&mut ActionPoints, &StatModifiers, &StatusEffects)>,
+) {
+    for (mut action_points, stat_modifiers, status_effects) in &mut action_points_entities {
+        action_points.left = action_points
+            .get_effective_max(stat_modifiers, status_effects)
+            .value;
         action_points.attacks_this_round = 0;
     }
 }
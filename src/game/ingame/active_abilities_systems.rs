@@ -4,8 +4,9 @@ use bevy::prelude::{
 };
 
 use crate::game::abilities::active_abilities::{
-    ActivatedAbilityMarker, ActiveAbility, ThrowJavelinInput,
+    ActivatedAbilityMarker, ActiveAbility, FirebombInput, HealInput, RallyInput,
 };
+use crate::game::abilities::defined_abilities::DefinedAbilityInput;
 use crate::game::ingame::hovered_hex::HoveredHex;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
 use crate::game::states::round_state::RoundState;
@@ -15,10 +16,10 @@ pub fn handle_activated_active_ability(
     buttons: Res<ButtonInput<MouseButton>>,
     hovered_hex: Res<HoveredHex>,
     mut round_state: ResMut<NextState<RoundState>>,
-    mut active_abilities: Query<(&mut ActiveAbility, &Parent), With<ActivatedAbilityMarker>>,
+    active_abilities: Query<(Entity, &ActiveAbility, &Parent), With<ActivatedAbilityMarker>>,
     mut commands: Commands,
 ) {
-    let Ok((mut ability, parent)) = active_abilities.get_single_mut() else {
+    let Ok((ability_entity, ability, parent)) = active_abilities.get_single() else {
         round_state.set(RoundState::Input);
         return;
     };
@@ -42,19 +43,46 @@ pub fn handle_activated_active_ability(
     }
 
     match *ability {
-        ActiveAbility::ThrowJavelin {
-            throw_javelin_system: system_id,
-            ref mut usages_left,
-        } => {
-            *usages_left -= 1;
-            commands.run_system_with_input(
-                system_id,
-                ThrowJavelinInput {
-                    attacker: **parent,
-                    target_hex: hex_cursor_position,
-                },
-            )
-        }
+        ActiveAbility::Firebomb {
+            firebomb_system: system_id,
+            ammo,
+        } => commands.run_system_with_input(
+            system_id,
+            FirebombInput {
+                attacker: **parent,
+                ability: ability_entity,
+                target_hex: hex_cursor_position,
+                ammo,
+            },
+        ),
+        ActiveAbility::Heal {
+            heal_system: system_id,
+        } => commands.run_system_with_input(
+            system_id,
+            HealInput {
+                healer: **parent,
+                target_hex: hex_cursor_position,
+            },
+        ),
+        ActiveAbility::Rally {
+            rally_system: system_id,
+            ..
+        } => commands.run_system_with_input(
+            system_id,
+            RallyInput {
+                rallier: **parent,
+                ability: ability_entity,
+                target_hex: hex_cursor_position,
+            },
+        ),
+        ActiveAbility::Defined { system_id, .. } => commands.run_system_with_input(
+            system_id,
+            DefinedAbilityInput {
+                user: **parent,
+                ability: ability_entity,
+                target_hex: hex_cursor_position,
+            },
+        ),
     };
 }
 
@@ -0,0 +1,294 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::{
+    debug, in_state, App, Component, Entity, Event, EventReader, EventWriter, IntoSystemConfigs,
+    OnEnter, Parent, Plugin, Query, Res, Update, With,
+};
+
+use crate::game::abilities::active_abilities::ActiveAbility;
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::combat::CombatConfig;
+use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::hex::HexComponent;
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::states::in_game_state::InGameState;
+use crate::game::states::round_state::{round_end_system, ActiveTeam, RoundState};
+
+pub const RESUPPLY_ACTION_POINT_COST: usize = 2;
+/// Allies within this range of a [`Supplier`] refill their ammo at the start of their turn
+const SUPPLY_RANGE: u32 = 1;
+
+pub struct AmmoPlugin;
+
+impl Plugin for AmmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ResupplyEvent>()
+            .add_systems(
+                Update,
+                handle_resupply_events.run_if(in_state(InGameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(RoundState::RoundEnd),
+                resupply_near_suppliers.after(round_end_system),
+            );
+    }
+}
+
+/// Limited uses of an attack or ability
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ammo {
+    max: usize,
+    pub left: usize,
+}
+
+impl Ammo {
+    pub fn new(max: usize) -> Self {
+        Self { max, left: max }
+    }
+
+    pub fn get_max(&self) -> usize {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.left >= self.max
+    }
+
+    /// Spends one use and returns how many are left
+    pub fn use_one(&mut self) -> usize {
+        self.left = self.left.saturating_sub(1);
+        self.left
+    }
+
+    pub fn refill(&mut self) {
+        self.left = self.max;
+    }
+}
+
+impl Display for Ammo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.left, self.max)
+    }
+}
+
+/// Units refill the ammo of adjacent allies at the start of their turn
+#[derive(Component, Debug)]
+pub struct Supplier;
+
+/// The unit spends [`RESUPPLY_ACTION_POINT_COST`] action points to refill all its ammo
+#[derive(Event, Debug, Clone)]
+pub struct ResupplyEvent {
+    pub unit: Entity,
+}
+
+type AbilitiesQuery<'world, 'state, 'a> =
+    Query<'world, 'state, (&'a Parent, &'a mut ActiveAbility)>;
+
+/// Refills the ammo of the unit's attacks and abilities and returns whether any of it was missing
+fn refill_ammo(
+    unit: Entity,
+    combat_config: &mut CombatConfig,
+    abilities: &mut AbilitiesQuery,
+) -> bool {
+    let mut refilled = false;
+    for ammo in combat_config.iter_ammo_mut().filter(|ammo| !ammo.is_full()) {
+        ammo.refill();
+        refilled = true;
+    }
+    for (_, mut ability) in abilities
+        .iter_mut()
+        .filter(|(parent, _)| parent.get() == unit)
+    {
+        if let Some(ammo) = ability.get_ammo_mut().filter(|ammo| !ammo.is_full()) {
+            ammo.refill();
+            refilled = true;
+        }
+    }
+    refilled
+}
+
+fn handle_resupply_events(
+    mut resupply_events: EventReader<ResupplyEvent>,
+    mut units: Query<(&UnitMarker, &mut CombatConfig, &mut ActionPoints)>,
+    mut abilities: AbilitiesQuery,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    for event in resupply_events.read() {
+        let Ok((unit_marker, mut combat_config, mut action_points)) = units.get_mut(event.unit)
+        else {
+            continue;
+        };
+        if action_points.left < RESUPPLY_ACTION_POINT_COST {
+            debug!("{} has not enough action points to resupply", unit_marker.0);
+            continue;
+        }
+
+        if refill_ammo(event.unit, &mut combat_config, &mut abilities) {
+            action_points.left -= RESUPPLY_ACTION_POINT_COST;
+            log_event.send(LogEvent {
+                message: format!("{} resupplies", unit_marker.0),
+            });
+        }
+    }
+}
+
+fn resupply_near_suppliers(
+    active_team: Res<ActiveTeam>,
+    mut units: Query<(Entity, &HexComponent, &Team, &UnitMarker, &mut CombatConfig), UnitFilter>,
+    suppliers: Query<(Entity, &HexComponent, &Team, &UnitMarker), With<Supplier>>,
+    mut abilities: AbilitiesQuery,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    for (entity, hex, _, unit_marker, mut combat_config) in units
+        .iter_mut()
+        .filter(|(_, _, team, _, _)| **team == active_team.0)
+    {
+        let Some((_, _, _, supplier_marker)) =
+            suppliers.iter().find(|(supplier, supplier_hex, team, _)| {
+                supplier != &entity
+                    && **team == active_team.0
+                    && supplier_hex.0.unsigned_distance_to(hex.0) <= SUPPLY_RANGE
+            })
+        else {
+            continue;
+        };
+
+        if refill_ammo(entity, &mut combat_config, &mut abilities) {
+            log_event.send(LogEvent {
+                message: format!("{} was resupplied by {}", unit_marker.0, supplier_marker.0),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{BuildWorldChildren, Children, FromWorld, NextState};
+    use hexx::Hex;
+
+    use crate::game::abilities::active_abilities::{ActiveAbilityRegistry, ActiveAbilityType};
+    use crate::generate_test_app;
+    use crate::tests::{spawn_unit, AppWrapper};
+
+    use super::*;
+
+    #[test]
+    fn resupplying_refills_the_ammo_of_attacks_and_abilities() {
+        let mut app = TestApp::build(4);
+
+        app.send_event(ResupplyEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_ammo_left(app.unit), vec![2, 1]);
+        assert_eq!(app.get_action_points_left(), 4 - RESUPPLY_ACTION_POINT_COST);
+    }
+
+    #[test]
+    fn resupplying_without_enough_action_points_does_nothing() {
+        let mut app = TestApp::build(RESUPPLY_ACTION_POINT_COST - 1);
+
+        app.send_event(ResupplyEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_ammo_left(app.unit), vec![0, 0]);
+        assert_eq!(app.get_action_points_left(), RESUPPLY_ACTION_POINT_COST - 1);
+    }
+
+    #[test]
+    fn resupplying_with_full_ammo_costs_no_action_points() {
+        let mut app = TestApp::build(4);
+        app.send_event(ResupplyEvent { unit: app.unit });
+        app.update();
+
+        app.send_event(ResupplyEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_action_points_left(), 4 - RESUPPLY_ACTION_POINT_COST);
+    }
+
+    #[test]
+    fn allies_next_to_a_supplier_are_resupplied_for_free() {
+        let mut app = TestApp::build(4);
+        let supplier = spawn_unit_without_ammo(&mut app.app, Team::Red, Hex::new(1, 0));
+        app.app.world.entity_mut(supplier).insert(Supplier);
+        let distant_ally = spawn_unit_without_ammo(&mut app.app, Team::Red, Hex::new(3, 0));
+        let adjacent_enemy = spawn_unit_without_ammo(&mut app.app, Team::Blue, Hex::new(-1, 0));
+
+        app.app
+            .world
+            .resource_mut::<NextState<RoundState>>()
+            .set(RoundState::RoundEnd);
+        app.update();
+
+        assert_eq!(app.get::<RoundState>(), &RoundState::RoundEnd);
+        assert_eq!(app.get_ammo_left(app.unit), vec![2, 1]);
+        assert_eq!(app.get_action_points_left(), 4);
+        assert_eq!(app.get_ammo_left(distant_ally), vec![0, 0]);
+        assert_eq!(app.get_ammo_left(adjacent_enemy), vec![0, 0]);
+    }
+
+    generate_test_app!(unit: Entity);
+
+    impl TestApp {
+        fn build(action_points: usize) -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.init_state::<InGameState>();
+            app.world
+                .resource_mut::<NextState<InGameState>>()
+                .set(InGameState::Playing);
+            app.add_event::<LogEvent>();
+            app.insert_resource(ActiveTeam(Team::Red));
+            app.add_plugins(AmmoPlugin);
+
+            let unit = spawn_unit_without_ammo(&mut app, Team::Red, Hex::ZERO);
+            app.world.get_mut::<ActionPoints>(unit).unwrap().left = action_points;
+
+            app.update();
+
+            TestApp { app, unit }
+        }
+
+        fn get_ammo_left(&self, unit: Entity) -> Vec<usize> {
+            let world = &self.app.world;
+            let default_attack_ammo = world.get::<CombatConfig>(unit).unwrap().ammo;
+            let ability_ammo = world
+                .get::<Children>(unit)
+                .unwrap()
+                .iter()
+                .filter_map(|child| world.get::<ActiveAbility>(*child))
+                .filter_map(|ability| ability.get_ammo().copied());
+            default_attack_ammo
+                .into_iter()
+                .chain(ability_ammo)
+                .map(|ammo| ammo.left)
+                .collect()
+        }
+
+        fn get_action_points_left(&self) -> usize {
+            self.app.world.get::<ActionPoints>(self.unit).unwrap().left
+        }
+    }
+
+    /// Spawns a unit that has used up the ammo of its default attack and its firebomb
+    fn spawn_unit_without_ammo(app: &mut App, team: Team, hex: Hex) -> Entity {
+        let mut firebomb = ActiveAbilityRegistry::from_world(&mut app.world)
+            .get_registered_ability(&ActiveAbilityType::Firebomb);
+        firebomb.get_ammo_mut().unwrap().left = 0;
+        let mut ammo = Ammo::new(2);
+        ammo.left = 0;
+
+        let unit = spawn_unit(app, team, hex);
+        app.world.get_mut::<CombatConfig>(unit).unwrap().ammo = Some(ammo);
+        app.world.entity_mut(unit).with_children(|parent| {
+            parent.spawn(firebomb);
+        });
+        unit
+    }
+}
@@ -0,0 +1,422 @@
+use std::collections::HashSet;
+
+use bevy::prelude::{
+    in_state, App, Entity, Event, EventReader, EventWriter, IntoSystemConfigs, Parent, Plugin,
+    Query, Res, ResMut, Resource, Update, With,
+};
+use hexx::Hex;
+
+use crate::game::abilities::active_abilities::{ActivatedAbilityMarker, ActiveAbility};
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::combat::{use_ammo, Attack, AttackOrDefault, CombatConfig, CombatEvent};
+use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::hex::HexComponent;
+use crate::game::ingame::hovered_hex::HoveredHex;
+use crate::game::ingame::selected_unit::SelectedUnitResource;
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::states::in_game_state::InGameState;
+
+pub struct AreaOfEffectPlugin;
+
+impl Plugin for AreaOfEffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<AreaAttackEvent>()
+            .init_resource::<AreaOfEffectPreviewResource>()
+            .add_systems(
+                Update,
+                (handle_area_attack_events, update_area_of_effect_preview)
+                    .run_if(in_state(InGameState::Playing)),
+            );
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AreaShape {
+    /// every hex within the radius around the target hex
+    Radius(u32),
+    /// hexes in a straight line from the attacker towards the target hex
+    Line(u32),
+    /// hexes up to the length in the 60° wedge from the attacker towards the target hex
+    Cone(u32),
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AreaOfEffect {
+    pub shape: AreaShape,
+    /// whether allies of the attacker in the area are hit as well
+    #[serde(default)]
+    pub friendly_fire: bool,
+}
+
+impl AreaShape {
+    pub fn get_affected_hexes(&self, origin: Hex, target: Hex) -> HashSet<Hex> {
+        match *self {
+            AreaShape::Radius(radius) => target.range(radius).collect(),
+            AreaShape::Line(length) => {
+                let far_end = origin + (target - origin) * length as i32;
+                origin
+                    .line_to(far_end)
+                    .skip(1)
+                    .take(length as usize)
+                    .collect()
+            }
+            AreaShape::Cone(length) => {
+                if origin == target {
+                    return HashSet::new();
+                }
+                let direction = origin.main_direction_to(target);
+                origin
+                    .range(length)
+                    .filter(|hex| hex != &origin && origin.main_direction_to(*hex) == direction)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Attacks every unit in the area of the attack, each with its own roll like any other attack.
+/// The attack costs action points and ammo once, however many units it hits.
+#[derive(Event, Debug, Clone)]
+pub struct AreaAttackEvent {
+    pub attacker: Entity,
+    pub attack: Attack,
+    pub target_hex: Hex,
+}
+
+/// The hexes that would be hit by the attack or ability targeting the hovered hex
+#[derive(Resource, Debug, Default)]
+pub struct AreaOfEffectPreviewResource(pub Option<HashSet<Hex>>);
+
+type AreaAttackUnitsQuery<'world, 'state, 'a> = Query<
+    'world,
+    'state,
+    (
+        Entity,
+        &'a HexComponent,
+        &'a Team,
+        &'a UnitMarker,
+        &'a mut CombatConfig,
+        &'a mut ActionPoints,
+    ),
+    UnitFilter,
+>;
+
+fn handle_area_attack_events(
+    mut area_attack_events: EventReader<AreaAttackEvent>,
+    mut units: AreaAttackUnitsQuery,
+    mut active_abilities: Query<&mut ActiveAbility>,
+    mut combat_event: EventWriter<CombatEvent>,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    for event in area_attack_events.read() {
+        let Some(area) = event.attack.area else {
+            continue;
+        };
+        let Ok((
+            _,
+            attacker_hex,
+            attacker_team,
+            attacker_unit,
+            mut attacker_config,
+            mut action_points,
+        )) = units.get_mut(event.attacker)
+        else {
+            continue;
+        };
+        if !action_points.can_still_attack_with(&event.attack) {
+            continue;
+        }
+        let attacker_hex = attacker_hex.0;
+        let attacker_team = *attacker_team;
+        let attacker_name = attacker_unit.0.clone();
+
+        action_points.left -= action_points.get_attack_cost(&event.attack);
+        action_points.attacks_this_round += 1;
+        use_ammo(&event.attack, &mut attacker_config, &mut active_abilities);
+
+        let affected_hexes = area
+            .shape
+            .get_affected_hexes(attacker_hex, event.target_hex);
+        log_event.send(LogEvent {
+            message: format!(
+                "{attacker_name} uses {} on {} hexes",
+                event.attack.name,
+                affected_hexes.len()
+            ),
+        });
+
+        for (defender, ..) in units.iter().filter(|(_, hex, team, ..)| {
+            hex.0 != attacker_hex
+                && affected_hexes.contains(&hex.0)
+                && (**team != attacker_team || area.friendly_fire)
+        }) {
+            combat_event.send(CombatEvent {
+                attacker: event.attacker,
+                attack: AttackOrDefault::Attack(event.attack.clone()),
+                defender,
+            });
+        }
+    }
+}
+
+fn update_area_of_effect_preview(
+    mut area_of_effect_preview: ResMut<AreaOfEffectPreviewResource>,
+    selected_unit_resource: Res<SelectedUnitResource>,
+    hovered_hex: Res<HoveredHex>,
+    units: Query<(&HexComponent, &Team, &CombatConfig), UnitFilter>,
+    activated_abilities: Query<(&ActiveAbility, &Parent), With<ActivatedAbilityMarker>>,
+) {
+    area_of_effect_preview.0 = None;
+
+    let (Some(selected_unit), Some(hovered_hex), Some(reachable_hexes)) = (
+        selected_unit_resource.selected_unit(),
+        hovered_hex.0,
+        selected_unit_resource.reachable_hexes(),
+    ) else {
+        return;
+    };
+    if !reachable_hexes.contains(&hovered_hex) {
+        return;
+    }
+    let Ok((selected_unit_hex, selected_unit_team, combat_config)) = units.get(selected_unit)
+    else {
+        return;
+    };
+
+    let area = match activated_abilities.get_single() {
+        Ok((ability, _)) => ability.get_area(),
+        Err(_) => {
+            let targets_enemy = units
+                .iter()
+                .any(|(hex, team, _)| hex.0 == hovered_hex && team != selected_unit_team);
+            combat_config
+                .get_attack(selected_unit_resource.selected_attack())
+                .and_then(|attack| attack.area)
+                .filter(|_| targets_enemy)
+        }
+    };
+
+    area_of_effect_preview.0 = area.map(|area| {
+        area.shape
+            .get_affected_hexes(selected_unit_hex.0, hovered_hex)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{NextState, States};
+
+    use crate::game::ingame::combat::{CombatPlugin, HealthPoints};
+    use crate::game::ingame::combat_ruleset::CombatRuleset;
+    use crate::game::ingame::damage_type::DamageType;
+    use crate::game::ingame::elevation::Elevation;
+    use crate::game::ingame::hex::HexMarker;
+    use crate::game::ingame::line_of_sight::LineOfSight;
+    use crate::game::ingame::morale::MoraleCheckEvent;
+    use crate::game::ingame::terrain::{MovementCost, Terrain};
+    use crate::game::states::round_state::RoundState;
+    use crate::game::util::dice::DiceRng;
+    use crate::game::util::dice_expression::DiceExpression;
+    use crate::generate_test_app;
+    use crate::tests::{spawn_unit, AppWrapper};
+
+    use super::*;
+
+    #[test]
+    fn radius_covers_all_hexes_around_the_target() {
+        let affected_hexes = AreaShape::Radius(1).get_affected_hexes(Hex::ZERO, Hex::new(3, 0));
+
+        assert_eq!(affected_hexes.len(), 7);
+        assert!(affected_hexes.contains(&Hex::new(3, 0)));
+        assert!(affected_hexes.contains(&Hex::new(4, 0)));
+        assert!(!affected_hexes.contains(&Hex::new(1, 0)));
+    }
+
+    #[test]
+    fn line_and_cone_start_at_the_attacker() {
+        let line = AreaShape::Line(3).get_affected_hexes(Hex::ZERO, Hex::new(1, 0));
+        assert_eq!(
+            line,
+            HashSet::from([Hex::new(1, 0), Hex::new(2, 0), Hex::new(3, 0)])
+        );
+
+        let cone = AreaShape::Cone(2).get_affected_hexes(Hex::ZERO, Hex::new(1, 0));
+        assert!(cone.contains(&Hex::new(1, 0)));
+        assert!(cone.contains(&Hex::new(2, 0)));
+        assert!(!cone.contains(&Hex::ZERO));
+        assert!(!cone.contains(&Hex::new(-1, 0)));
+        assert!(cone
+            .iter()
+            .all(|hex| hex.unsigned_distance_to(Hex::ZERO) <= 2));
+    }
+
+    #[test]
+    fn every_enemy_in_the_area_is_attacked_with_its_own_roll() {
+        let mut app = TestApp::build([15, 5]);
+        let first_enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(2, 0));
+        let second_enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(3, 0));
+
+        app.area_attack(AreaShape::Radius(1), false, Hex::new(2, 0));
+
+        let mut health_points_left = [
+            app.get_health_points_left(first_enemy),
+            app.get_health_points_left(second_enemy),
+        ];
+        health_points_left.sort();
+        assert_eq!(health_points_left, [4, 5]);
+        let action_points = app.app.world.get::<ActionPoints>(app.attacker).unwrap();
+        assert_eq!(action_points.left, 9);
+        assert_eq!(action_points.attacks_this_round, 1);
+    }
+
+    #[test]
+    fn allies_in_the_area_are_only_hit_with_friendly_fire() {
+        let mut app = TestApp::build([15]);
+        let enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(2, 0));
+        let ally = spawn_unit(&mut app.app, Team::Red, Hex::new(2, -1));
+
+        app.area_attack(AreaShape::Radius(1), false, Hex::new(2, 0));
+
+        assert_eq!(app.get_health_points_left(enemy), 4);
+        assert_eq!(app.get_health_points_left(ally), 5);
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+
+        app.app.insert_resource(DiceRng::scripted([15, 15]));
+        app.area_attack(AreaShape::Radius(1), true, Hex::new(2, 0));
+
+        assert_eq!(app.get_health_points_left(enemy), 3);
+        assert_eq!(app.get_health_points_left(ally), 4);
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+    }
+
+    #[test]
+    fn cone_attacks_hit_the_enemies_in_front_of_the_attacker() {
+        // the adjacent enemy strikes back
+        let mut app = TestApp::build([15, 15, 15]);
+        let first_enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(1, 0));
+        let second_enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(2, 0));
+        let enemy_behind = spawn_unit(&mut app.app, Team::Blue, Hex::new(-1, 0));
+
+        app.area_attack(AreaShape::Cone(2), false, Hex::new(1, 0));
+
+        assert_eq!(app.get_health_points_left(first_enemy), 4);
+        assert_eq!(app.get_health_points_left(second_enemy), 4);
+        assert_eq!(app.get_health_points_left(enemy_behind), 5);
+    }
+
+    #[test]
+    fn area_attacks_need_an_attack_left() {
+        let mut app = TestApp::build([15]);
+        let enemy = spawn_unit(&mut app.app, Team::Blue, Hex::new(2, 0));
+        app.app
+            .world
+            .get_mut::<ActionPoints>(app.attacker)
+            .unwrap()
+            .attacks_this_round = 2;
+
+        app.send_area_attack(AreaShape::Radius(1), false, Hex::new(2, 0));
+        app.update();
+        app.update();
+
+        assert_eq!(app.get_health_points_left(enemy), 5);
+        assert_eq!(
+            app.app
+                .world
+                .get::<ActionPoints>(app.attacker)
+                .unwrap()
+                .left,
+            10
+        );
+    }
+
+    generate_test_app!(attacker: Entity);
+
+    impl TestApp {
+        fn build(dice_rolls: impl IntoIterator<Item = u8>) -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.init_state::<InGameState>();
+            app.world
+                .resource_mut::<NextState<InGameState>>()
+                .set(InGameState::Playing);
+            app.add_event::<LogEvent>();
+            app.add_event::<MoraleCheckEvent>();
+            app.insert_resource(DiceRng::scripted(dice_rolls));
+            app.init_resource::<CombatRuleset>();
+            app.init_resource::<SelectedUnitResource>();
+            app.init_resource::<HoveredHex>();
+            app.add_plugins((CombatPlugin, AreaOfEffectPlugin));
+
+            let plains = Terrain {
+                name: "Plains".to_string(),
+                movement_cost: MovementCost::Passable(1),
+                attack_modifier: 0,
+                defense_modifier: 0,
+                line_of_sight: LineOfSight::Clear,
+                stops_charges: false,
+                attack_disadvantage: false,
+            };
+            Hex::ZERO.spiral_range(0..=4).for_each(|hex| {
+                app.world.spawn((
+                    HexMarker,
+                    HexComponent(hex),
+                    Elevation::default(),
+                    plains.clone(),
+                ));
+            });
+
+            let attacker = spawn_unit(&mut app, Team::Red, Hex::ZERO);
+            app.world
+                .entity_mut(attacker)
+                .insert(ActionPoints::new(10, 2, 1));
+
+            app.update();
+
+            TestApp { app, attacker }
+        }
+
+        fn area_attack(&mut self, shape: AreaShape, friendly_fire: bool, target_hex: Hex) {
+            self.send_area_attack(shape, friendly_fire, target_hex);
+            // the round state only leaves input once the queued attacks start to be resolved
+            self.update();
+            self.update_until(RoundState::Input);
+        }
+
+        fn send_area_attack(&mut self, shape: AreaShape, friendly_fire: bool, target_hex: Hex) {
+            self.send_event(AreaAttackEvent {
+                attacker: self.attacker,
+                attack: Attack {
+                    name: "Fire Arrows".to_string(),
+                    damage: DiceExpression::constant(1),
+                    damage_type: DamageType::Fire,
+                    range: 3,
+                    action_point_cost: None,
+                    ammo: None,
+                    ammo_source: None,
+                    area: Some(AreaOfEffect {
+                        shape,
+                        friendly_fire,
+                    }),
+                    passive_combat_abilities: vec![],
+                },
+                target_hex,
+            });
+        }
+
+        fn update_until<S: States>(&mut self, state: S) {
+            for _ in 0..20 {
+                self.update();
+                if self.get::<S>() == &state {
+                    return;
+                }
+            }
+            panic!("Did not reach {state:?}");
+        }
+
+        fn get_health_points_left(&self, unit: Entity) -> usize {
+            self.app.world.get::<HealthPoints>(unit).unwrap().left
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use std::fmt::{Display, Formatter};
+
+#[cfg(doc)]
+use crate::game::ingame::combat::CombatResource;
+use crate::game::ingame::combat::{CombatResult, HitTarget};
+use crate::game::ingame::combat_ruleset::CombatRuleset;
+use crate::game::util::dice::{Dice, DiceRng};
+
+/// A change to an attack by a passive ability, collected in [`CombatResource::roll_modifications`]
+/// before the attack is rolled
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollModification {
+    pub kind: RollModificationKind,
+    /// name of the ability or effect that changes the attack
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollModificationKind {
+    /// added to the D20 roll, natural 1s and 20s stay what they are
+    Roll(i32),
+    /// the D20 is rolled again if the attack misses and only the new roll counts
+    Reroll,
+    /// added to the rolled damage before damage resistances
+    Damage(i32),
+    /// see [`RollMode::Advantage`]
+    Advantage,
+    /// see [`RollMode::Disadvantage`]
+    Disadvantage,
+}
+
+impl Display for RollModification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            RollModificationKind::Roll(value) | RollModificationKind::Damage(value) => {
+                write!(f, "{} {value:+}", self.source)
+            }
+            RollModificationKind::Reroll => write!(f, "{} reroll", self.source),
+            RollModificationKind::Advantage => write!(f, "{}: advantage", self.source),
+            RollModificationKind::Disadvantage => write!(f, "{}: disadvantage", self.source),
+        }
+    }
+}
+
+/// Sum of all damage modifications
+pub fn get_damage_modifier(roll_modifications: &[RollModification]) -> i32 {
+    roll_modifications
+        .iter()
+        .filter_map(|modification| match modification.kind {
+            RollModificationKind::Damage(value) => Some(value),
+            _ => None,
+        })
+        .sum()
+}
+
+/// Sum of all modifications added to the D20 roll
+pub fn get_roll_modifier(roll_modifications: &[RollModification]) -> i32 {
+    roll_modifications
+        .iter()
+        .filter_map(|modification| match modification.kind {
+            RollModificationKind::Roll(value) => Some(value),
+            _ => None,
+        })
+        .sum()
+}
+
+/// How many D20 are rolled for an attack and which one counts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RollMode {
+    #[default]
+    Normal,
+    /// two D20 are rolled and the higher one counts
+    Advantage,
+    /// two D20 are rolled and the lower one counts
+    Disadvantage,
+}
+
+impl RollMode {
+    /// As in 5e, advantage and disadvantage cancel out, no matter how many sources there are of each
+    pub fn new(roll_modifications: &[RollModification]) -> Self {
+        let has = |kind| {
+            roll_modifications
+                .iter()
+                .any(|modification| modification.kind == kind)
+        };
+        match (
+            has(RollModificationKind::Advantage),
+            has(RollModificationKind::Disadvantage),
+        ) {
+            (true, false) => RollMode::Advantage,
+            (false, true) => RollMode::Disadvantage,
+            _ => RollMode::Normal,
+        }
+    }
+
+    /// The number of the equally likely outcomes of all rolled dice in which the D20 that counts shows the roll
+    pub fn get_outcomes(&self, dice_roll: u8) -> usize {
+        let dice_roll = dice_roll as usize;
+        match self {
+            RollMode::Normal => 1,
+            RollMode::Advantage => 2 * dice_roll - 1,
+            RollMode::Disadvantage => 41 - 2 * dice_roll,
+        }
+    }
+
+    pub fn get_all_outcomes(&self) -> usize {
+        match self {
+            RollMode::Normal => 20,
+            RollMode::Advantage | RollMode::Disadvantage => 400,
+        }
+    }
+
+    /// Returns the D20 that counts and the one that was discarded
+    fn roll(&self, dice_rng: &mut DiceRng) -> (u8, Option<u8>) {
+        let first_roll = Dice::<20>::roll(dice_rng);
+        let second_roll = match self {
+            RollMode::Normal => return (first_roll, None),
+            RollMode::Advantage | RollMode::Disadvantage => Dice::<20>::roll(dice_rng),
+        };
+        let (higher, lower) = match first_roll >= second_roll {
+            true => (first_roll, second_roll),
+            false => (second_roll, first_roll),
+        };
+        match self {
+            RollMode::Disadvantage => (lower, Some(higher)),
+            _ => (higher, Some(lower)),
+        }
+    }
+}
+
+impl Display for RollMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RollMode::Normal => write!(f, "normal"),
+            RollMode::Advantage => write!(f, "advantage"),
+            RollMode::Disadvantage => write!(f, "disadvantage"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reroll {
+    pub discarded_roll: u8,
+    pub source: String,
+}
+
+/// The D20 roll of an attack, made up of the die that counts, the dice that were rerolled and all modifiers applying to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttackRoll {
+    pub roll_mode: RollMode,
+    pub natural_roll: u8,
+    /// the second D20 rolled with advantage or disadvantage
+    pub discarded_roll: Option<u8>,
+    pub rerolls: Vec<Reroll>,
+    /// the roll modifications and sources of advantage and disadvantage,
+    /// damage modifications are part of the damage instead
+    pub modifiers: Vec<RollModification>,
+}
+
+impl AttackRoll {
+    /// Rolls the D20 and uses up rerolls as long as the attack misses, each with the same roll mode
+    pub fn roll(
+        roll_modifications: &[RollModification],
+        hit_target: &HitTarget,
+        combat_ruleset: &CombatRuleset,
+        dice_rng: &mut DiceRng,
+    ) -> (Self, CombatResult) {
+        let roll_mode = RollMode::new(roll_modifications);
+        let (natural_roll, discarded_roll) = roll_mode.roll(dice_rng);
+        let mut attack_roll = AttackRoll {
+            roll_mode,
+            natural_roll,
+            discarded_roll,
+            rerolls: vec![],
+            modifiers: roll_modifications
+                .iter()
+                .filter(|modification| {
+                    !matches!(
+                        modification.kind,
+                        RollModificationKind::Reroll | RollModificationKind::Damage(_)
+                    )
+                })
+                .cloned()
+                .collect(),
+        };
+        let mut combat_result = attack_roll.get_combat_result(hit_target, combat_ruleset);
+
+        for reroll in roll_modifications
+            .iter()
+            .filter(|modification| modification.kind == RollModificationKind::Reroll)
+        {
+            if !combat_result.is_miss() {
+                break;
+            }
+            attack_roll.rerolls.push(Reroll {
+                discarded_roll: attack_roll.natural_roll,
+                source: reroll.source.clone(),
+            });
+            (attack_roll.natural_roll, attack_roll.discarded_roll) = roll_mode.roll(dice_rng);
+            combat_result = attack_roll.get_combat_result(hit_target, combat_ruleset);
+        }
+
+        (attack_roll, combat_result)
+    }
+
+    pub fn get_total(&self) -> u8 {
+        let modifier_sum = get_roll_modifier(&self.modifiers);
+        (self.natural_roll as i32 + modifier_sum).clamp(0, u8::MAX as i32) as u8
+    }
+
+    fn get_combat_result(
+        &self,
+        hit_target: &HitTarget,
+        combat_ruleset: &CombatRuleset,
+    ) -> CombatResult {
+        combat_ruleset.get_modified_combat_result(self.natural_roll, self.get_total(), hit_target)
+    }
+}
+
+impl Display for AttackRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_total())?;
+        if self.rerolls.is_empty() && self.modifiers.is_empty() {
+            return Ok(());
+        }
+
+        let has_advantage_sources = self.modifiers.iter().any(|modifier| {
+            matches!(
+                modifier.kind,
+                RollModificationKind::Advantage | RollModificationKind::Disadvantage
+            )
+        });
+        let dice = match (self.discarded_roll, self.roll_mode) {
+            (Some(discarded_roll), roll_mode) => format!(
+                "2d20 kept {}, discarded {discarded_roll} ({roll_mode})",
+                self.natural_roll
+            ),
+            (None, RollMode::Normal) if has_advantage_sources => format!(
+                "d20 {} (advantage and disadvantage cancel out)",
+                self.natural_roll
+            ),
+            (None, _) => format!("d20 {}", self.natural_roll),
+        };
+        let mut breakdown = vec![dice];
+        breakdown.extend(
+            self.rerolls
+                .iter()
+                .map(|reroll| format!("rerolled {} ({})", reroll.discarded_roll, reroll.source)),
+        );
+        breakdown.extend(self.modifiers.iter().map(|modifier| modifier.to_string()));
+        write!(f, " [{}]", breakdown.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modification(kind: RollModificationKind) -> RollModification {
+        RollModification {
+            kind,
+            source: "test".to_string(),
+        }
+    }
+
+    fn hit_target(defense: usize) -> HitTarget {
+        HitTarget {
+            defense,
+            modifiers: vec![],
+        }
+    }
+
+    #[test]
+    fn modifiers_change_whether_the_roll_hits() {
+        let (attack_roll, combat_result) = AttackRoll::roll(
+            &[
+                modification(RollModificationKind::Roll(2)),
+                modification(RollModificationKind::Damage(1)),
+            ],
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([9]),
+        );
+
+        assert_eq!(combat_result, CombatResult::Hit);
+        assert_eq!(attack_roll.to_string(), "11 [d20 9, test +2]");
+    }
+
+    #[test]
+    fn misses_are_rerolled_once_per_reroll() {
+        let rerolls = [
+            modification(RollModificationKind::Reroll),
+            modification(RollModificationKind::Reroll),
+        ];
+
+        let (attack_roll, combat_result) = AttackRoll::roll(
+            &rerolls,
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([1, 12]),
+        );
+        assert_eq!(combat_result, CombatResult::Hit);
+        assert_eq!(attack_roll.to_string(), "12 [d20 12, rerolled 1 (test)]");
+
+        let (attack_roll, combat_result) = AttackRoll::roll(
+            &rerolls,
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([3, 4, 5]),
+        );
+        assert_eq!(combat_result, CombatResult::Miss);
+        assert_eq!(attack_roll.rerolls.len(), 2);
+        assert_eq!(attack_roll.get_total(), 5);
+    }
+
+    #[test]
+    fn advantage_keeps_the_higher_and_disadvantage_the_lower_die() {
+        let advantage = modification(RollModificationKind::Advantage);
+        let disadvantage = modification(RollModificationKind::Disadvantage);
+
+        let (attack_roll, combat_result) = AttackRoll::roll(
+            &[advantage.clone(), advantage.clone()],
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([4, 15]),
+        );
+        assert_eq!(combat_result, CombatResult::Hit);
+        assert_eq!(
+            attack_roll.to_string(),
+            "15 [2d20 kept 15, discarded 4 (advantage), test: advantage, test: advantage]"
+        );
+
+        let (attack_roll, combat_result) = AttackRoll::roll(
+            &[modification(RollModificationKind::Disadvantage)],
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([4, 15]),
+        );
+        assert_eq!(combat_result, CombatResult::Miss);
+        assert_eq!(attack_roll.natural_roll, 4);
+        assert_eq!(attack_roll.discarded_roll, Some(15));
+
+        let (attack_roll, _) = AttackRoll::roll(
+            &[advantage.clone(), advantage, disadvantage],
+            &hit_target(10),
+            &CombatRuleset::default(),
+            &mut DiceRng::scripted([4]),
+        );
+        assert_eq!(attack_roll.roll_mode, RollMode::Normal);
+        assert_eq!(attack_roll.discarded_roll, None);
+    }
+
+    #[test]
+    fn advantage_and_disadvantage_change_the_odds_of_every_roll() {
+        for roll_mode in [
+            RollMode::Normal,
+            RollMode::Advantage,
+            RollMode::Disadvantage,
+        ] {
+            let outcomes: usize = (1..=20).map(|roll| roll_mode.get_outcomes(roll)).sum();
+            assert_eq!(outcomes, roll_mode.get_all_outcomes());
+        }
+        assert_eq!(RollMode::Advantage.get_outcomes(20), 39);
+        assert_eq!(RollMode::Disadvantage.get_outcomes(20), 1);
+    }
+
+    #[test]
+    fn damage_modifiers_are_summed_up() {
+        let modifications = [
+            modification(RollModificationKind::Damage(2)),
+            modification(RollModificationKind::Roll(5)),
+            modification(RollModificationKind::Damage(-1)),
+        ];
+
+        assert_eq!(get_damage_modifier(&modifications), 1);
+    }
+}
@@ -0,0 +1,175 @@
+use bevy::prelude::{Component, Query};
+use hexx::Hex;
+
+#[cfg(doc)]
+use crate::game::abilities::passive_combat_abilities::PassiveCombatAbility;
+use crate::game::ingame::combat::Attack;
+use crate::game::ingame::elevation::Elevation;
+use crate::game::ingame::hex::TerrainHexesQuery;
+use crate::game::ingame::terrain::Terrain;
+
+/// A charge has to cover at least this many hexes
+pub const CHARGE_MIN_DISTANCE: usize = 3;
+/// Hit modifier for attacks at the end of a charge
+pub const CHARGE_MODIFIER: i32 = 3;
+
+/// The hexes a unit moved through this turn, starting with the hex it started from
+#[derive(Component, Debug, Default, Clone)]
+pub struct TravelledPath(Vec<Hex>);
+
+impl TravelledPath {
+    pub fn record_step(&mut self, from: Hex, to: Hex) {
+        if self.0.is_empty() {
+            self.0.push(from);
+        }
+        self.0.push(to);
+    }
+
+    pub fn get_hexes(&self) -> &[Hex] {
+        &self.0
+    }
+
+    /// A charge is a straight run of at least [`CHARGE_MIN_DISTANCE`] hexes ending in the attack.
+    /// Climbing or entering terrain that stops charges, like forest, breaks it.
+    pub fn is_charge<'a>(
+        &self,
+        attacker_hex: Hex,
+        defender_hex: Hex,
+        get_hex: impl Fn(&Hex) -> Option<(&'a Terrain, Elevation)>,
+    ) -> bool {
+        let [start, .., end] = self.0.as_slice() else {
+            return false;
+        };
+        let steps = self.0.len() - 1;
+        if *end != attacker_hex || steps < CHARGE_MIN_DISTANCE {
+            return false;
+        }
+
+        let attack_distance = attacker_hex.unsigned_distance_to(defender_hex) as usize;
+        let is_straight =
+            start.unsigned_distance_to(defender_hex) as usize == steps + attack_distance;
+        if !is_straight {
+            return false;
+        }
+
+        let charged_hexes = self
+            .0
+            .iter()
+            .chain([&defender_hex])
+            .map(get_hex)
+            .collect::<Option<Vec<_>>>();
+        let Some(charged_hexes) = charged_hexes else {
+            return false;
+        };
+        let enters_stopping_terrain = charged_hexes
+            .iter()
+            .skip(1)
+            .any(|(terrain, _)| terrain.stops_charges);
+        let goes_uphill = charged_hexes
+            .windows(2)
+            .any(|pair| pair[1].1 .0 > pair[0].1 .0);
+
+        !enters_stopping_terrain && !goes_uphill
+    }
+}
+
+/// Whether the attack has [`PassiveCombatAbility::Charge`] and the attacker charged the defender this turn
+pub fn is_charging(
+    attack: &Attack,
+    travelled_path: Option<&TravelledPath>,
+    hexes: &TerrainHexesQuery,
+    attacker_hex: Hex,
+    defender_hex: Hex,
+) -> bool {
+    let Some(travelled_path) = travelled_path else {
+        return false;
+    };
+    attack.is_charge()
+        && travelled_path.is_charge(attacker_hex, defender_hex, |hex| {
+            hexes
+                .iter()
+                .find(|(hex_component, _, _)| &hex_component.0 == hex)
+                .map(|(_, terrain, elevation)| (terrain, *elevation))
+        })
+}
+
+pub(super) fn clear_travelled_paths(mut travelled_paths: Query<&mut TravelledPath>) {
+    for mut travelled_path in &mut travelled_paths {
+        travelled_path.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use crate::game::ingame::line_of_sight::LineOfSight;
+    use crate::game::ingame::terrain::MovementCost;
+
+    use super::*;
+
+    fn terrain(stops_charges: bool) -> Terrain {
+        Terrain {
+            name: "test".to_string(),
+            movement_cost: MovementCost::Passable(1),
+            attack_modifier: 0,
+            defense_modifier: 0,
+            line_of_sight: LineOfSight::Clear,
+            stops_charges,
+            attack_disadvantage: false,
+        }
+    }
+
+    fn travelled_path(hexes: &[Hex]) -> TravelledPath {
+        let mut travelled_path = TravelledPath::default();
+        for step in hexes.windows(2) {
+            travelled_path.record_step(step[0], step[1]);
+        }
+        travelled_path
+    }
+
+    #[test]
+    fn only_long_straight_runs_are_charges() {
+        let plains = terrain(false);
+        let map: HashMap<_, _> = Hex::ZERO
+            .spiral_range(0..=5)
+            .map(|hex| (hex, (&plains, Elevation(0))))
+            .collect();
+        let get_hex = |hex: &Hex| map.get(hex).copied();
+
+        let straight = [Hex::ZERO, Hex::new(1, 0), Hex::new(2, 0), Hex::new(3, 0)];
+        assert!(travelled_path(&straight).is_charge(Hex::new(3, 0), Hex::new(4, 0), get_hex));
+        assert!(travelled_path(&straight).is_charge(Hex::new(3, 0), Hex::new(4, -1), get_hex));
+        assert!(!travelled_path(&straight).is_charge(Hex::new(3, 0), Hex::new(2, 1), get_hex));
+        assert!(!travelled_path(&straight[..3]).is_charge(Hex::new(2, 0), Hex::new(3, 0), get_hex));
+
+        let zigzag = [Hex::ZERO, Hex::new(1, 0), Hex::new(1, 1), Hex::new(2, 0)];
+        assert!(!travelled_path(&zigzag).is_charge(Hex::new(2, 0), Hex::new(3, 0), get_hex));
+    }
+
+    #[test]
+    fn charges_into_forest_or_uphill_are_broken() {
+        let plains = terrain(false);
+        let forest = terrain(true);
+        let defender_hex = Hex::new(4, 0);
+        let path = travelled_path(&[Hex::ZERO, Hex::new(1, 0), Hex::new(2, 0), Hex::new(3, 0)]);
+
+        let get_hex = |hex: &Hex| match hex == &defender_hex {
+            true => Some((&forest, Elevation(0))),
+            false => Some((&plains, Elevation(0))),
+        };
+        assert!(!path.is_charge(Hex::new(3, 0), defender_hex, get_hex));
+
+        let get_hex = |hex: &Hex| match hex == &defender_hex {
+            true => Some((&plains, Elevation(1))),
+            false => Some((&plains, Elevation(0))),
+        };
+        assert!(!path.is_charge(Hex::new(3, 0), defender_hex, get_hex));
+
+        let get_hex = |hex: &Hex| match hex == &Hex::ZERO {
+            true => Some((&plains, Elevation(1))),
+            false => Some((&plains, Elevation(0))),
+        };
+        assert!(path.is_charge(Hex::new(3, 0), defender_hex, get_hex));
+    }
+}
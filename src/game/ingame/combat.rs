@@ -1,32 +1,68 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+
 use bevy::app::PostUpdate;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::{
     debug, in_state, info, not, App, Changed, Commands, Component, Condition, DespawnRecursiveExt,
     Entity, Event, EventReader, EventWriter, IntoSystemConfigs, NextState, Plugin, Query, Res,
-    ResMut, Resource, Update,
+    ResMut, Resource, State, Update, With,
 };
+use hexx::Hex;
 
+use crate::game::abilities::active_abilities::ActiveAbility;
+use crate::game::abilities::defined_abilities::DefinedAbility;
 use crate::game::abilities::passive_combat_abilities::{
-    AbilityTrigger, CombatPhase, RegisteredPassiveCombatAbility,
+    run_triggered_abilities, AbilityTrigger, CombatPhase, PassiveAbility, PassiveCombatAbility,
+    PassiveCombatAbilityRegistry, RegisteredPassiveCombatAbility,
 };
+use crate::game::asset_loading::nation_assets::UnitStats;
 use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::ammo::Ammo;
+#[cfg(doc)]
+use crate::game::ingame::area_of_effect::AreaAttackEvent;
+use crate::game::ingame::area_of_effect::AreaOfEffect;
+#[cfg(doc)]
+use crate::game::ingame::attack_roll::RollMode;
+use crate::game::ingame::attack_roll::{
+    get_damage_modifier, AttackRoll, RollModification, RollModificationKind,
+};
+use crate::game::ingame::charge::{is_charging, TravelledPath, CHARGE_MODIFIER};
+use crate::game::ingame::combat_ruleset::{CombatRuleset, FumbleRule};
+use crate::game::ingame::damage_type::{DamageResistances, DamageType};
+use crate::game::ingame::elevation::{get_elevation_at, Elevation};
+use crate::game::ingame::flanking::{Flanking, FLANKING_MODIFIER};
 use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::line_of_sight::{
+    LineOfSight, LineOfSightMap, OBSCURED_LINE_OF_SIGHT_MODIFIER,
+};
+use crate::game::ingame::morale::{Morale, MoraleCheckEvent, MoraleCheckReason};
+use crate::game::ingame::stat_modifiers::{EffectiveStat, Stat, StatModifiers};
+use crate::game::ingame::status_effects::StatusEffects;
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::terrain::Terrain;
 use crate::game::ingame::unit::UnitMarker;
 use crate::game::ingame::unit_status::UnitStatus;
 use crate::game::states::in_game_state::InGameState;
 use crate::game::states::round_state::RoundState;
-use crate::game::util::dice::Dice;
+use crate::game::util::dice::DiceRng;
+use crate::game::util::dice_expression::DiceExpression;
 
 pub struct CombatPlugin;
 
 impl Plugin for CombatPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<CombatEvent>()
+            .init_resource::<CombatQueue>()
             .add_systems(
                 PostUpdate,
                 (
                     handle_combat_event,
                     despawn_dead_units.run_if(
                         not(in_state(RoundState::Combat))
+                            .and_then(not(in_state(RoundState::PreCounterAttack)))
+                            .and_then(not(in_state(RoundState::CounterAttack)))
                             .and_then(not(in_state(RoundState::PostCombat))),
                     ),
                 )
@@ -37,6 +73,8 @@ impl Plugin for CombatPlugin {
                 (
                     handle_pre_combat.run_if(in_state(RoundState::PreCombat)),
                     handle_combat.run_if(in_state(RoundState::Combat)),
+                    handle_pre_counter_attack.run_if(in_state(RoundState::PreCounterAttack)),
+                    handle_counter_attack.run_if(in_state(RoundState::CounterAttack)),
                     handle_post_combat.run_if(in_state(RoundState::PostCombat)),
                 )
                     .run_if(in_state(InGameState::Playing)),
@@ -44,40 +82,205 @@ impl Plugin for CombatPlugin {
     }
 }
 
+type CombatUnitsQuery<'world, 'state, 'a> = Query<
+    'world,
+    'state,
+    (
+        &'a CombatConfig,
+        &'a mut HealthPoints,
+        &'a UnitMarker,
+        &'a HexComponent,
+        &'a UnitStatus,
+        &'a Morale,
+        &'a StatusEffects,
+        &'a StatModifiers,
+    ),
+>;
+
 #[derive(Component, Debug)]
 pub struct HealthPoints {
     max: usize,
     pub left: usize,
+    /// the unit that dealt the last damage, it gets the kill if this unit dies
+    pub last_damaged_by: Option<Entity>,
 }
 
 impl HealthPoints {
     pub fn new(max: usize) -> Self {
-        Self { max, left: max }
+        Self {
+            max,
+            left: max,
+            last_damaged_by: None,
+        }
     }
 
     pub fn get_max(&self) -> usize {
         self.max
     }
+
+    /// Damage without a source, like poison, keeps the last attacker from getting the kill
+    pub fn take_damage(&mut self, damage: usize, source: Option<Entity>) {
+        if damage == 0 {
+            return;
+        }
+        self.left = self.left.saturating_sub(damage);
+        self.last_damaged_by = source;
+    }
+
+    /// Heals up to the max health points and returns how many health points were actually recovered
+    pub fn heal(&mut self, amount: usize) -> usize {
+        let healed = amount.min(self.max.saturating_sub(self.left));
+        self.left += healed;
+        healed
+    }
 }
 
 #[derive(Component, Debug)]
 pub struct CombatConfig {
     /// damage to health points if defense fails
-    pub damage: usize,
+    pub damage: DiceExpression,
+    pub damage_type: DamageType,
     /// Chance to defend in a D20 dice roll
     pub defense: usize,
+    /// reduce the damage taken once the defense failed
+    pub damage_resistances: DamageResistances,
     pub range: u32,
+    /// whether the unit strikes back when attacked in melee
+    pub can_counter: bool,
     pub passive_combat_abilities: Vec<RegisteredPassiveCombatAbility>,
+    /// named attacks the unit can choose instead of its default attack, e.g. a bow for a melee unit
+    pub attacks: Vec<Attack>,
+    /// ammo of the default attack, unlimited if not set
+    pub ammo: Option<Ammo>,
 }
 
+pub const DEFAULT_ATTACK_NAME: &str = "Default attack";
+
 impl CombatConfig {
+    /// the combat config of a freshly spawned unit, including the passive ones of its defined abilities
+    pub fn from_unit_stats(
+        stats: &UnitStats,
+        passive_combat_ability_registry: &PassiveCombatAbilityRegistry,
+        defined_abilities: &[&DefinedAbility],
+    ) -> Self {
+        Self {
+            damage: stats.damage.clone(),
+            damage_type: stats.damage_type,
+            defense: stats.defense,
+            damage_resistances: stats.get_damage_resistances(),
+            range: stats.range,
+            can_counter: stats.can_counter,
+            passive_combat_abilities: stats
+                .passive_combat_abilities
+                .iter()
+                .map(|ability| {
+                    passive_combat_ability_registry.get_registered_ability(ability.clone())
+                })
+                .chain(
+                    defined_abilities
+                        .iter()
+                        .filter_map(|ability| match ability {
+                            DefinedAbility::Passive(ability) => Some(ability.clone()),
+                            DefinedAbility::Active(_) => None,
+                        }),
+                )
+                .collect(),
+            attacks: stats
+                .attacks
+                .iter()
+                .map(|attack_profile| Attack {
+                    name: attack_profile.name.clone(),
+                    damage: attack_profile.damage.clone(),
+                    damage_type: attack_profile.damage_type,
+                    range: attack_profile.range,
+                    action_point_cost: attack_profile.action_point_cost,
+                    ammo: attack_profile.ammo.map(Ammo::new),
+                    ammo_source: None,
+                    area: attack_profile.area,
+                    passive_combat_abilities: attack_profile
+                        .passive_combat_abilities
+                        .iter()
+                        .map(|ability| {
+                            passive_combat_ability_registry.get_registered_ability(ability.clone())
+                        })
+                        .collect(),
+                })
+                .collect(),
+            ammo: stats.ammo.map(Ammo::new),
+        }
+    }
+
+    pub fn get_effective_defense(
+        &self,
+        stat_modifiers: &StatModifiers,
+        status_effects: &StatusEffects,
+    ) -> EffectiveStat {
+        stat_modifiers.resolve(Stat::Defense, self.defense, status_effects)
+    }
+
+    pub fn get_effective_damage_resistances(
+        &self,
+        stat_modifiers: &StatModifiers,
+        status_effects: &StatusEffects,
+    ) -> DamageResistances {
+        stat_modifiers.get_damage_resistances(&self.damage_resistances, status_effects)
+    }
+
     pub fn get_default_attack(&self) -> Attack {
         Attack {
-            damage: self.damage,
+            name: DEFAULT_ATTACK_NAME.to_string(),
+            damage: self.damage.clone(),
+            damage_type: self.damage_type,
             range: self.range,
+            action_point_cost: None,
+            ammo: self.ammo,
+            ammo_source: Some(AmmoSource::Attack(0)),
+            area: None,
             passive_combat_abilities: self.passive_combat_abilities.clone(),
         }
     }
+
+    /// All attacks the unit can choose from, starting with the default attack
+    pub fn get_attacks(&self) -> Vec<Attack> {
+        (0..=self.attacks.len())
+            .filter_map(|index| self.get_attack(index))
+            .collect()
+    }
+
+    /// The attack at the given index of [`CombatConfig::get_attacks`]
+    pub fn get_attack(&self, index: usize) -> Option<Attack> {
+        match index {
+            0 => Some(self.get_default_attack()),
+            _ => self.attacks.get(index - 1).map(|attack| Attack {
+                ammo_source: Some(AmmoSource::Attack(index)),
+                ..attack.clone()
+            }),
+        }
+    }
+
+    /// The ammo of the attack at the given index of [`CombatConfig::get_attacks`]
+    pub fn get_attack_ammo_mut(&mut self, index: usize) -> Option<&mut Ammo> {
+        match index {
+            0 => self.ammo.as_mut(),
+            _ => self
+                .attacks
+                .get_mut(index - 1)
+                .and_then(|attack| attack.ammo.as_mut()),
+        }
+    }
+
+    pub fn iter_ammo_mut(&mut self) -> impl Iterator<Item = &mut Ammo> {
+        self.ammo.iter_mut().chain(
+            self.attacks
+                .iter_mut()
+                .filter_map(|attack| attack.ammo.as_mut()),
+        )
+    }
+
+    /// Only melee units strike back, and only against adjacent attackers without [`PassiveCombatAbility::FirstStrike`]
+    pub fn can_counter_attack(&self, attack: &Attack, distance: u32) -> bool {
+        self.can_counter && self.range == 1 && distance <= self.range && !attack.is_first_strike()
+    }
 }
 
 #[derive(Event, Debug, Clone)]
@@ -95,31 +298,366 @@ pub enum AttackOrDefault {
 
 #[derive(Debug, Clone)]
 pub struct Attack {
-    pub damage: usize,
+    pub name: String,
+    pub damage: DiceExpression,
+    pub damage_type: DamageType,
     pub range: u32,
+    /// overrides the unit's attack action point cost
+    pub action_point_cost: Option<usize>,
+    /// unlimited if not set
+    pub ammo: Option<Ammo>,
+    /// where the ammo is used up, set by [`CombatConfig::get_attack`] for the unit's own attacks
+    pub ammo_source: Option<AmmoSource>,
+    /// hits every unit in the area instead of a single defender, see [`AreaAttackEvent`]
+    pub area: Option<AreaOfEffect>,
     pub passive_combat_abilities: Vec<RegisteredPassiveCombatAbility>,
 }
 
-#[derive(Resource, Debug)]
+/// Keeps track of the ammo an [`Attack`] uses up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmmoSource {
+    /// the attack at this index of [`CombatConfig::get_attacks`]
+    Attack(usize),
+    /// the [`ActiveAbility`] entity the attack was made with
+    Ability(Entity),
+}
+
+impl Attack {
+    pub fn has_ammo_left(&self) -> bool {
+        !self.ammo.is_some_and(|ammo| ammo.is_empty())
+    }
+
+    /// Attacks with [`PassiveCombatAbility::FirstStrike`] can not be countered
+    pub fn is_first_strike(&self) -> bool {
+        self.has_passive_combat_ability(&PassiveCombatAbility::FirstStrike)
+    }
+
+    /// Attacks with [`PassiveCombatAbility::Charge`] are easier to hit at the end of a charge,
+    /// see [`TravelledPath::is_charge`]
+    pub fn is_charge(&self) -> bool {
+        self.has_passive_combat_ability(&PassiveCombatAbility::Charge)
+    }
+
+    pub fn has_passive_combat_ability(
+        &self,
+        passive_combat_ability: &PassiveCombatAbility,
+    ) -> bool {
+        self.passive_combat_abilities
+            .iter()
+            .any(|registered_ability| {
+                matches!(&registered_ability.ability, PassiveAbility::BuiltIn(ability) if ability == passive_combat_ability)
+            })
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
 pub struct CombatResource {
     pub attacker: Entity,
     pub attack: Attack,
     pub defender: Entity,
     pub combat_result: CombatResult,
+    /// changes to the attack by passive abilities of both units, collected before the attack is rolled.
+    /// Advantages of the [`CombatSituation`] are added when rolling.
+    pub roll_modifications: Vec<RollModification>,
+    /// the breakdown of the attack roll, set once the attack was rolled
+    pub attack_roll: Option<AttackRoll>,
+}
+
+impl CombatResource {
+    pub fn new(attacker: Entity, attack: Attack, defender: Entity) -> Self {
+        Self {
+            attacker,
+            attack,
+            defender,
+            combat_result: CombatResult::None,
+            roll_modifications: vec![],
+            attack_roll: None,
+        }
+    }
+}
+
+/// Attacks waiting to be resolved one after another, e.g. the attacks of opportunity of all enemies
+/// a moving unit disengages from in the same step
+#[derive(Resource, Debug, Default)]
+pub struct CombatQueue {
+    pending: VecDeque<CombatResource>,
+    /// the round state to return to once all queued attacks are resolved, set while resolving
+    state_after_combat: Option<RoundState>,
+}
+
+impl CombatQueue {
+    pub fn is_resolving(&self) -> bool {
+        self.state_after_combat.is_some()
+    }
+
+    /// Skips attacks whose attacker or defender died while they were waiting
+    fn pop_next(&mut self, is_alive: impl Fn(Entity) -> bool) -> Option<CombatResource> {
+        while let Some(combat_resource) = self.pending.pop_front() {
+            if is_alive(combat_resource.attacker) && is_alive(combat_resource.defender) {
+                return Some(combat_resource);
+            }
+            debug!("Skipping queued combat {combat_resource:?} of a dead unit");
+        }
+        None
+    }
+}
+
+fn is_alive(health_points: &Query<&HealthPoints>, entity: Entity) -> bool {
+    health_points
+        .get(entity)
+        .is_ok_and(|health_points| health_points.left > 0)
+}
+
+/// The number a D20 roll has to reach to hit, made up of the defenders defense and all modifiers applying to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HitTarget {
+    pub defense: usize,
+    pub modifiers: Vec<HitModifier>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HitModifier {
+    pub source: HitModifierSource,
+    /// added to the target number, so positive values make hitting harder
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HitModifierSource {
+    AttackerTerrain(String),
+    DefenderTerrain(String),
+    DefenderUphill,
+    ObscuredLineOfSight,
+    Flanking,
+    Charge,
+}
+
+/// Everything besides the units themselves that influences the outcome of an attack
+#[derive(Debug, Default, Clone)]
+pub struct CombatSituation<'a> {
+    pub attacker_terrain: Option<&'a Terrain>,
+    pub attacker_elevation: Elevation,
+    pub defender_terrain: Option<&'a Terrain>,
+    pub defender_elevation: Elevation,
+    pub line_of_sight: LineOfSight,
+    pub flanking: Flanking,
+    pub attacker_shaken: bool,
+    pub attacker_charging: bool,
+}
+
+impl<'a> CombatSituation<'a> {
+    pub fn new(
+        hexes: &'a TerrainHexesQuery,
+        line_of_sight_map: &LineOfSightMap,
+        attacker_hex: Hex,
+        defender_hex: Hex,
+        flanking: Flanking,
+    ) -> Self {
+        let find_hex = |hex: Hex| {
+            hexes
+                .iter()
+                .find(|(hex_component, _, _)| hex_component.0 == hex)
+                .map(|(_, terrain, elevation)| (terrain, *elevation))
+        };
+        let attacker_terrain = find_hex(attacker_hex);
+        let defender_terrain = find_hex(defender_hex);
+
+        Self {
+            attacker_terrain: attacker_terrain.map(|(terrain, _)| terrain),
+            attacker_elevation: attacker_terrain
+                .map(|(_, elevation)| elevation)
+                .unwrap_or_default(),
+            defender_terrain: defender_terrain.map(|(terrain, _)| terrain),
+            defender_elevation: defender_terrain
+                .map(|(_, elevation)| elevation)
+                .unwrap_or_default(),
+            line_of_sight: line_of_sight_map.get_line_of_sight(attacker_hex, defender_hex),
+            flanking,
+            attacker_shaken: false,
+            attacker_charging: false,
+        }
+    }
+
+    /// Sources of advantage and disadvantage on the attack roll, see [`RollMode`]
+    pub fn get_advantages(&self) -> Vec<RollModification> {
+        let mut advantages = vec![];
+        if self.flanking.engaged_allies > 0 {
+            advantages.push(RollModification {
+                kind: RollModificationKind::Advantage,
+                source: "defender engaged elsewhere".to_string(),
+            });
+        }
+        if let Some(terrain) = self
+            .attacker_terrain
+            .filter(|terrain| terrain.attack_disadvantage)
+        {
+            advantages.push(RollModification {
+                kind: RollModificationKind::Disadvantage,
+                source: format!("attacker in {}", terrain.name),
+            });
+        }
+        if self.attacker_shaken {
+            advantages.push(RollModification {
+                kind: RollModificationKind::Disadvantage,
+                source: "attacker shaken".to_string(),
+            });
+        }
+        advantages
+    }
+}
+
+/// Whether an attack with the given range can reach the defender, taking elevation and line of sight into account
+pub fn is_in_attack_range(
+    hexes: &TerrainHexesQuery,
+    line_of_sight_map: &LineOfSightMap,
+    range: u32,
+    attacker_hex: Hex,
+    defender_hex: Hex,
+) -> bool {
+    let effective_range = get_elevation_at(hexes, attacker_hex)
+        .get_effective_range(range, &get_elevation_at(hexes, defender_hex));
+
+    attacker_hex.unsigned_distance_to(defender_hex) <= effective_range
+        && line_of_sight_map.can_see(attacker_hex, defender_hex)
+}
+
+impl HitTarget {
+    pub fn new(defense: usize, combat_situation: &CombatSituation) -> Self {
+        let mut modifiers = vec![];
+
+        if let Some(terrain) = combat_situation
+            .attacker_terrain
+            .filter(|terrain| terrain.attack_modifier != 0)
+        {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::AttackerTerrain(terrain.name.clone()),
+                value: -terrain.attack_modifier,
+            });
+        }
+        if let Some(terrain) = combat_situation
+            .defender_terrain
+            .filter(|terrain| terrain.defense_modifier != 0)
+        {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::DefenderTerrain(terrain.name.clone()),
+                value: terrain.defense_modifier,
+            });
+        }
+        let elevation_bonus = combat_situation
+            .defender_elevation
+            .get_defense_bonus(&combat_situation.attacker_elevation);
+        if elevation_bonus != 0 {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::DefenderUphill,
+                value: elevation_bonus,
+            });
+        }
+        if combat_situation.line_of_sight == LineOfSight::Obscured {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::ObscuredLineOfSight,
+                value: OBSCURED_LINE_OF_SIGHT_MODIFIER,
+            });
+        }
+        if combat_situation.flanking.from_opposite_side {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::Flanking,
+                value: -FLANKING_MODIFIER,
+            });
+        }
+        if combat_situation.attacker_charging {
+            modifiers.push(HitModifier {
+                source: HitModifierSource::Charge,
+                value: -CHARGE_MODIFIER,
+            });
+        }
+
+        Self { defense, modifiers }
+    }
+
+    pub fn get_target(&self) -> usize {
+        let modifier_sum: i32 = self.modifiers.iter().map(|modifier| modifier.value).sum();
+        (self.defense as i32 + modifier_sum).max(0) as usize
+    }
+
+    pub fn is_hit(&self, dice_roll: u8) -> bool {
+        dice_roll as usize >= self.get_target()
+    }
+}
+
+impl Display for HitTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.get_target())?;
+        if self.modifiers.is_empty() {
+            return Ok(());
+        }
+
+        let modifiers: Vec<_> = self
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.to_string())
+            .collect();
+        write!(f, " [defense {}, {}]", self.defense, modifiers.join(", "))
+    }
+}
+
+impl Display for HitModifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.source {
+            HitModifierSource::AttackerTerrain(terrain_name) => {
+                write!(f, "attacker in {terrain_name} {:+}", self.value)
+            }
+            HitModifierSource::DefenderTerrain(terrain_name) => {
+                write!(f, "defender in {terrain_name} {:+}", self.value)
+            }
+            HitModifierSource::DefenderUphill => write!(f, "defender uphill {:+}", self.value),
+            HitModifierSource::ObscuredLineOfSight => {
+                write!(f, "obscured line of sight {:+}", self.value)
+            }
+            HitModifierSource::Flanking => write!(f, "flanked {:+}", self.value),
+            HitModifierSource::Charge => write!(f, "charge {:+}", self.value),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CombatResult {
     Hit,
+    /// natural 20, see [`CombatRuleset`]
+    CriticalHit,
     Miss,
+    /// natural 1, see [`CombatRuleset`]
+    Fumble,
     None,
 }
 
-fn handle_combat_event(
+impl CombatResult {
+    pub fn is_hit(&self) -> bool {
+        matches!(self, CombatResult::Hit | CombatResult::CriticalHit)
+    }
+
+    pub fn is_miss(&self) -> bool {
+        matches!(self, CombatResult::Miss | CombatResult::Fumble)
+    }
+
+    /// Triggers of passive abilities of the attack that fire for this result besides `OnAttack`
+    fn get_ability_triggers(&self) -> Vec<AbilityTrigger> {
+        match self {
+            CombatResult::CriticalHit => vec![AbilityTrigger::OnCriticalHit],
+            CombatResult::Fumble => vec![AbilityTrigger::OnFumble],
+            _ => vec![],
+        }
+    }
+}
+
+/// Queues all attacks and starts resolving the first one, unless attacks are already being resolved
+pub(super) fn handle_combat_event(
+    current_round_state: Res<State<RoundState>>,
     mut round_state: ResMut<NextState<RoundState>>,
     mut commands: Commands,
     mut combat_events: EventReader<CombatEvent>,
+    mut combat_queue: ResMut<CombatQueue>,
     units: Query<&CombatConfig>,
+    health_points: Query<&HealthPoints>,
 ) {
     for combat_event in combat_events.read() {
         let attack = match &combat_event.attack {
@@ -129,21 +667,35 @@ fn handle_combat_event(
                 Err(_) => continue,
             },
         };
-        round_state.set(RoundState::PreCombat);
-        commands.insert_resource(CombatResource {
-            attacker: combat_event.attacker,
+        combat_queue.pending.push_back(CombatResource::new(
+            combat_event.attacker,
             attack,
-            defender: combat_event.defender,
-            combat_result: CombatResult::None,
-        });
+            combat_event.defender,
+        ));
+    }
+
+    if combat_queue.is_resolving() {
+        return;
     }
+    let Some(combat_resource) = combat_queue.pop_next(|entity| is_alive(&health_points, entity))
+    else {
+        return;
+    };
+    combat_queue.state_after_combat = Some(match current_round_state.get() {
+        RoundState::MovingUnit => RoundState::MovingUnit,
+        _ => RoundState::Input,
+    });
+    round_state.set(RoundState::PreCombat);
+    commands.insert_resource(combat_resource);
 }
 
 fn handle_pre_combat(
     mut commands: Commands,
-    mut units: Query<(&CombatConfig, &UnitMarker, &mut ActionPoints)>,
+    mut units: Query<(&mut CombatConfig, &UnitMarker, &mut ActionPoints)>,
+    mut active_abilities: Query<&mut ActiveAbility>,
     combat_resource: Res<CombatResource>,
     mut round_state: ResMut<NextState<RoundState>>,
+    mut log_event: EventWriter<LogEvent>,
 ) {
     debug!("Handling pre combat");
 
@@ -156,9 +708,27 @@ fn handle_pre_combat(
         );
     }
 
-    if let Ok((_, unit_marker, mut action_points)) = units.get_mut(combat_resource.attacker) {
-        action_points.left -= action_points.attack_action_point_cost();
-        action_points.attacks_this_round += 1;
+    if let Ok((mut attacker_config, unit_marker, mut action_points)) =
+        units.get_mut(combat_resource.attacker)
+    {
+        // area attacks are paid for once when they are used, not for every unit they hit
+        if combat_resource.attack.area.is_none() {
+            action_points.left -= action_points.get_attack_cost(&combat_resource.attack);
+            action_points.attacks_this_round += 1;
+            let ammo_left = use_ammo(
+                &combat_resource.attack,
+                &mut attacker_config,
+                &mut active_abilities,
+            );
+            if ammo_left == Some(0) {
+                log_event.send(LogEvent {
+                    message: format!(
+                        "{} is out of ammo for {}",
+                        unit_marker.0, combat_resource.attack.name
+                    ),
+                });
+            }
+        }
         filter_and_run_abilities(
             &mut commands,
             &combat_resource.attack.passive_combat_abilities,
@@ -170,6 +740,23 @@ fn handle_pre_combat(
     round_state.set(RoundState::Combat);
 }
 
+/// Spends one ammo of the attack, which is either one of the attacker's attacks or one of its abilities
+pub(super) fn use_ammo(
+    attack: &Attack,
+    attacker_config: &mut CombatConfig,
+    active_abilities: &mut Query<&mut ActiveAbility>,
+) -> Option<usize> {
+    let ammo_left = match attack.ammo_source? {
+        AmmoSource::Attack(index) => attacker_config.get_attack_ammo_mut(index)?.use_one(),
+        AmmoSource::Ability(ability) => active_abilities
+            .get_mut(ability)
+            .ok()?
+            .get_ammo_mut()?
+            .use_one(),
+    };
+    Some(ammo_left)
+}
+
 fn filter_and_run_abilities(
     commands: &mut Commands,
     passive_combat_abilities: &[RegisteredPassiveCombatAbility],
@@ -182,65 +769,479 @@ fn filter_and_run_abilities(
         .iter()
         .filter(|ability| ability.ability_trigger == ability_trigger)
         .for_each(|ability| {
-            debug!("{unit_name} trying {:?}", ability.ability);
-            commands.run_system(ability.system_id);
+            debug!("{unit_name} trying {}", ability.ability);
+            if let Some(system_id) = ability.system_id {
+                commands.run_system(system_id);
+            }
         });
 }
 
+struct ResolvedAttack {
+    combat_result: CombatResult,
+    attack_roll: AttackRoll,
+    damage: usize,
+    /// the dice rolls and what they were rolled against, for the log
+    roll_description: String,
+}
+
+impl ResolvedAttack {
+    fn describe(&self, attacker_name: &str, defender_name: &str) -> String {
+        let ResolvedAttack {
+            combat_result,
+            damage,
+            roll_description,
+            ..
+        } = self;
+        match combat_result {
+            CombatResult::Hit => {
+                format!("{attacker_name} caused {damage} damage to {defender_name} ({roll_description})")
+            }
+            CombatResult::CriticalHit => format!(
+                "{attacker_name} caused {damage} damage to {defender_name} with a critical hit ({roll_description})"
+            ),
+            CombatResult::Fumble => {
+                format!("{attacker_name} fumbled the attack on {defender_name} ({roll_description})")
+            }
+            CombatResult::Miss | CombatResult::None => format!(
+                "{attacker_name} has failed to cause significant damage to {defender_name} ({roll_description})"
+            ),
+        }
+    }
+}
+
+/// The defender of an attack as far as rolling the attack is concerned
+struct AttackedDefender<'a> {
+    hit_target: HitTarget,
+    health_points: &'a mut HealthPoints,
+    damage_resistances: DamageResistances,
+}
+
+/// Applies the roll modifications of passive abilities to the attack roll and the damage
+fn resolve_attack(
+    combat_resource: &CombatResource,
+    roll_modifications: &[RollModification],
+    defender: AttackedDefender,
+    combat_ruleset: &CombatRuleset,
+    dice_rng: &mut DiceRng,
+) -> ResolvedAttack {
+    let AttackedDefender {
+        hit_target,
+        health_points: defender_health_points,
+        damage_resistances: defender_damage_resistances,
+    } = defender;
+    let attack = &combat_resource.attack;
+    let (attack_roll, combat_result) =
+        AttackRoll::roll(roll_modifications, &hit_target, combat_ruleset, dice_rng);
+
+    let (damage_expression, damage_factor) = match combat_result {
+        CombatResult::Hit => (attack.damage.clone(), 1),
+        CombatResult::CriticalHit => combat_ruleset.get_critical_damage(&attack.damage),
+        _ => {
+            return ResolvedAttack {
+                combat_result,
+                damage: 0,
+                roll_description: format!("{attack_roll}/{hit_target}"),
+                attack_roll,
+            };
+        }
+    };
+
+    debug!("Successful combat dice roll: {attack_roll:?} against {hit_target:?}");
+    let damage_roll = damage_expression.roll(dice_rng);
+    let damage_modifier = get_damage_modifier(roll_modifications);
+    let damage = ((damage_roll.total * damage_factor) as i32 + damage_modifier).max(0) as usize;
+    let damage_taken = defender_damage_resistances.get_damage_taken(damage, &attack.damage_type);
+    defender_health_points.take_damage(damage_taken.damage, Some(combat_resource.attacker));
+
+    let damage_factor_description = match damage_factor {
+        1 => String::new(),
+        factor => format!(" x{factor}"),
+    };
+    let damage_modifications_description: String = roll_modifications
+        .iter()
+        .filter(|modification| matches!(modification.kind, RollModificationKind::Damage(_)))
+        .map(|modification| format!(", {modification}"))
+        .collect();
+    let damage_resistances_description: String = damage_taken
+        .modifications
+        .iter()
+        .map(|damage_modification| format!(", {damage_modification}"))
+        .collect();
+    ResolvedAttack {
+        combat_result,
+        damage: damage_taken.damage,
+        roll_description: format!(
+            "{attack_roll}/{hit_target}, {} damage {damage_roll}{damage_factor_description}{damage_modifications_description}{damage_resistances_description}",
+            attack.damage_type
+        ),
+        attack_roll,
+    }
+}
+
+/// Everything rolling an attack or a counterattack needs
+#[derive(SystemParam)]
+struct AttackRolls<'w, 's> {
+    units: CombatUnitsQuery<'w, 's, 'static>,
+    hexes: TerrainHexesQuery<'w, 's, 'static>,
+    action_points: Query<'w, 's, &'static mut ActionPoints>,
+    log_event: EventWriter<'w, LogEvent>,
+    dice_rng: ResMut<'w, DiceRng>,
+    combat_ruleset: Res<'w, CombatRuleset>,
+}
+
+impl AttackRolls<'_, '_> {
+    /// See [`FumbleRule::LoseAttacks`]
+    fn apply_fumble_rule(
+        &mut self,
+        combat_result: &CombatResult,
+        attacker: Entity,
+        attacker_name: &str,
+    ) {
+        if *combat_result != CombatResult::Fumble
+            || self.combat_ruleset.fumble != FumbleRule::LoseAttacks
+        {
+            return;
+        }
+        if let Ok(mut attacker_action_points) = self.action_points.get_mut(attacker) {
+            attacker_action_points.attacks_this_round = attacker_action_points.get_max_attacks();
+            self.log_event.send(LogEvent {
+                message: format!("{attacker_name} can not attack anymore this round"),
+            });
+        }
+    }
+}
+
 fn handle_combat(
-    mut units: Query<(&CombatConfig, &mut HealthPoints, &UnitMarker)>,
-    mut log_event: EventWriter<LogEvent>,
+    mut commands: Commands,
+    mut attack_rolls: AttackRolls,
     mut combat_resource: ResMut<CombatResource>,
     mut round_state: ResMut<NextState<RoundState>>,
+    mut morale_check_event: EventWriter<MoraleCheckEvent>,
+    travelled_paths: Query<&TravelledPath>,
 ) {
-    let Ok((_, _, attacker_unit)) = units.get_mut(combat_resource.attacker) else {
+    let AttackRolls {
+        units,
+        hexes,
+        log_event,
+        dice_rng,
+        combat_ruleset,
+        ..
+    } = &mut attack_rolls;
+    let Ok((_, _, attacker_unit, attacker_hex, _, attacker_morale, _, _)) =
+        units.get(combat_resource.attacker)
+    else {
         return;
     };
 
-    let damage = combat_resource.attack.damage;
     let attacker_name = attacker_unit.0.clone();
+    let attacker_hex = attacker_hex.0;
+    let attacker_shaken = attacker_morale.is_shaken();
+    let line_of_sight_map = LineOfSightMap::from_terrain(
+        hexes,
+        units
+            .iter()
+            .map(|(_, _, _, hex_component, ..)| hex_component.0),
+    );
+    let flanking = get_flanking(units, combat_resource.attacker, combat_resource.defender);
+    if flanking.from_opposite_side {
+        morale_check_event.send(MoraleCheckEvent {
+            unit: combat_resource.defender,
+            reason: MoraleCheckReason::Flanked,
+        });
+    }
 
-    let (defender_config, mut defender_health_points, defender_unit) =
-        units.get_mut(combat_resource.defender).unwrap();
-
-    let dice_roll = Dice::<20>::roll();
+    let (
+        defender_config,
+        mut defender_health_points,
+        defender_unit,
+        defender_hex,
+        _,
+        _,
+        defender_status_effects,
+        defender_stat_modifiers,
+    ) = units.get_mut(combat_resource.defender).unwrap();
 
-    let defender_name = &defender_unit.0;
+    let combat_situation = CombatSituation {
+        attacker_shaken,
+        attacker_charging: is_charging(
+            &combat_resource.attack,
+            travelled_paths.get(combat_resource.attacker).ok(),
+            hexes,
+            attacker_hex,
+            defender_hex.0,
+        ),
+        ..CombatSituation::new(
+            hexes,
+            &line_of_sight_map,
+            attacker_hex,
+            defender_hex.0,
+            flanking,
+        )
+    };
+    let roll_modifications: Vec<_> = combat_situation
+        .get_advantages()
+        .into_iter()
+        .chain(combat_resource.roll_modifications.iter().cloned())
+        .collect();
+    let resolved_attack = resolve_attack(
+        &combat_resource,
+        &roll_modifications,
+        AttackedDefender {
+            hit_target: HitTarget::new(
+                defender_config
+                    .get_effective_defense(defender_stat_modifiers, defender_status_effects)
+                    .value,
+                &combat_situation,
+            ),
+            health_points: &mut defender_health_points,
+            damage_resistances: defender_config
+                .get_effective_damage_resistances(defender_stat_modifiers, defender_status_effects),
+        },
+        combat_ruleset,
+        dice_rng,
+    );
 
-    let defense = defender_config.defense;
-    if (dice_roll as usize) >= defense {
-        combat_resource.combat_result = CombatResult::Hit;
-        debug!(
-            "Successful combat dice roll: {dice_roll} against {}",
-            defender_config.defense
+    log_event.send(LogEvent {
+        message: resolved_attack.describe(&attacker_name, &defender_unit.0),
+    });
+    if resolved_attack.damage > 0 && defender_health_points.left > 0 {
+        run_triggered_abilities(
+            &mut commands,
+            combat_resource.defender,
+            Some(combat_resource.attacker),
+            defender_config,
+            AbilityTrigger::OnDamaged,
         );
-        defender_health_points.left = if damage >= defender_health_points.left {
-            0
-        } else {
-            defender_health_points.left - damage
-        };
+    }
 
-        log_event.send(LogEvent {
-            message: format!(
-                "{attacker_name} caused {damage} damage to {defender_name} ({dice_roll}/{defense})",
-            ),
-        });
-    } else {
-        combat_resource.combat_result = CombatResult::Miss;
-        log_event.send(LogEvent {
-            message: format!(
-                "{attacker_name} has failed to cause significant damage to {defender_name} ({dice_roll}/{defense})",
-            ),
-        });
+    attack_rolls.apply_fumble_rule(
+        &resolved_attack.combat_result,
+        combat_resource.attacker,
+        &attacker_name,
+    );
+
+    combat_resource.combat_result = resolved_attack.combat_result;
+    combat_resource.attack_roll = Some(resolved_attack.attack_roll);
+
+    round_state.set(RoundState::PreCounterAttack);
+}
+
+fn get_flanking(units: &CombatUnitsQuery, attacker: Entity, defender: Entity) -> Flanking {
+    let (Ok((_, _, _, attacker_hex, ..)), Ok((_, _, _, defender_hex, defender_status, ..))) =
+        (units.get(attacker), units.get(defender))
+    else {
+        return Flanking::default();
+    };
+
+    Flanking::new(
+        attacker,
+        attacker_hex.0,
+        defender_hex.0,
+        defender_status,
+        |entity| units.get(*entity).ok().map(|(_, _, _, hex, ..)| hex.0),
+    )
+}
+
+/// The attack that is being countered, which is the [`CombatResource`] again once the counterattack is resolved
+#[derive(Resource, Debug, Clone)]
+struct CounteredAttack {
+    combat_resource: CombatResource,
+    is_free_counter_attack: bool,
+}
+
+/// Surviving melee defenders strike back at their attacker with their default attack.
+/// The counterattack is the current [`CombatResource`] until it is resolved, so the `OnAttack` passive abilities
+/// of the defender and the `OnDefense` passive abilities of the attacker can change its roll like for any other attack.
+fn handle_pre_counter_attack(
+    mut commands: Commands,
+    units: CombatUnitsQuery,
+    combat_resource: Res<CombatResource>,
+    mut round_state: ResMut<NextState<RoundState>>,
+    combat_ruleset: Res<CombatRuleset>,
+) {
+    let (
+        Ok((attacker_config, attacker_health_points, attacker_unit, attacker_hex, ..)),
+        Ok((defender_config, defender_health_points, defender_unit, defender_hex, ..)),
+    ) = (
+        units.get(combat_resource.attacker),
+        units.get(combat_resource.defender),
+    )
+    else {
+        round_state.set(RoundState::PostCombat);
+        return;
+    };
+
+    let distance = attacker_hex.0.unsigned_distance_to(defender_hex.0);
+    let is_free_counter_attack = combat_resource.combat_result == CombatResult::Fumble
+        && combat_ruleset.fumble == FumbleRule::FreeCounterAttack
+        && distance <= defender_config.range;
+    if defender_health_points.left == 0
+        || attacker_health_points.left == 0
+        || !(is_free_counter_attack
+            || defender_config.can_counter_attack(&combat_resource.attack, distance))
+    {
+        round_state.set(RoundState::PostCombat);
+        return;
     }
 
+    let counter_attack = defender_config.get_default_attack();
+    commands.insert_resource(CounteredAttack {
+        combat_resource: combat_resource.clone(),
+        is_free_counter_attack,
+    });
+    commands.insert_resource(CombatResource::new(
+        combat_resource.defender,
+        counter_attack.clone(),
+        combat_resource.attacker,
+    ));
+    filter_and_run_abilities(
+        &mut commands,
+        &attacker_config.passive_combat_abilities,
+        attacker_unit,
+        AbilityTrigger::OnDefense(CombatPhase::PreCombat),
+    );
+    filter_and_run_abilities(
+        &mut commands,
+        &counter_attack.passive_combat_abilities,
+        defender_unit,
+        AbilityTrigger::OnAttack(CombatPhase::PreCombat),
+    );
+
+    round_state.set(RoundState::CounterAttack);
+}
+
+/// Rolls the counterattack, which is the current [`CombatResource`], and restores the countered attack afterwards
+fn handle_counter_attack(
+    mut commands: Commands,
+    mut attack_rolls: AttackRolls,
+    combat_resource: Res<CombatResource>,
+    countered_attack: Res<CounteredAttack>,
+    mut round_state: ResMut<NextState<RoundState>>,
+) {
     round_state.set(RoundState::PostCombat);
+    commands.remove_resource::<CounteredAttack>();
+
+    let AttackRolls {
+        units,
+        hexes,
+        log_event,
+        dice_rng,
+        combat_ruleset,
+        ..
+    } = &mut attack_rolls;
+    let line_of_sight_map = LineOfSightMap::from_terrain(
+        hexes,
+        units
+            .iter()
+            .map(|(_, _, _, hex_component, ..)| hex_component.0),
+    );
+    let flanking = get_flanking(units, combat_resource.attacker, combat_resource.defender);
+
+    let Ok(
+        [(_, _, attacker_unit, attacker_hex, _, attacker_morale, _, _), (
+            defender_config,
+            mut defender_health_points,
+            defender_unit,
+            defender_hex,
+            _,
+            _,
+            defender_status_effects,
+            defender_stat_modifiers,
+        )],
+    ) = units.get_many_mut([combat_resource.attacker, combat_resource.defender])
+    else {
+        commands.insert_resource(countered_attack.combat_resource.clone());
+        return;
+    };
+
+    let combat_situation = CombatSituation {
+        attacker_shaken: attacker_morale.is_shaken(),
+        ..CombatSituation::new(
+            hexes,
+            &line_of_sight_map,
+            attacker_hex.0,
+            defender_hex.0,
+            flanking,
+        )
+    };
+    let roll_modifications: Vec<_> = combat_situation
+        .get_advantages()
+        .into_iter()
+        .chain(combat_resource.roll_modifications.iter().cloned())
+        .collect();
+    let resolved_attack = resolve_attack(
+        &combat_resource,
+        &roll_modifications,
+        AttackedDefender {
+            hit_target: HitTarget::new(
+                defender_config
+                    .get_effective_defense(defender_stat_modifiers, defender_status_effects)
+                    .value,
+                &combat_situation,
+            ),
+            health_points: &mut defender_health_points,
+            damage_resistances: defender_config
+                .get_effective_damage_resistances(defender_stat_modifiers, defender_status_effects),
+        },
+        combat_ruleset,
+        dice_rng,
+    );
+
+    let counter_attack_description = match countered_attack.is_free_counter_attack {
+        true => "gets a free counterattack",
+        false => "strikes back",
+    };
+    log_event.send(LogEvent {
+        message: format!(
+            "{} {counter_attack_description}: {}",
+            attacker_unit.0,
+            resolved_attack.describe(&attacker_unit.0, &defender_unit.0)
+        ),
+    });
+    if resolved_attack.damage > 0 && defender_health_points.left > 0 {
+        run_triggered_abilities(
+            &mut commands,
+            combat_resource.defender,
+            Some(combat_resource.attacker),
+            defender_config,
+            AbilityTrigger::OnDamaged,
+        );
+    }
+
+    let ability_triggers = [AbilityTrigger::OnAttack(CombatPhase::PostCombat)]
+        .into_iter()
+        .chain(resolved_attack.combat_result.get_ability_triggers());
+
+    commands.insert_resource(CombatResource {
+        combat_result: resolved_attack.combat_result.clone(),
+        attack_roll: Some(resolved_attack.attack_roll),
+        ..combat_resource.clone()
+    });
+    for ability_trigger in ability_triggers {
+        filter_and_run_abilities(
+            &mut commands,
+            &combat_resource.attack.passive_combat_abilities,
+            attacker_unit,
+            ability_trigger,
+        );
+    }
+    let counter_attacker_name = attacker_unit.0.clone();
+    attack_rolls.apply_fumble_rule(
+        &resolved_attack.combat_result,
+        combat_resource.attacker,
+        &counter_attacker_name,
+    );
+    commands.insert_resource(countered_attack.combat_resource.clone());
 }
 
+/// Continues with the next queued attack or returns to the round state before the attacks,
+/// which lets an interrupted movement carry on
 fn handle_post_combat(
     mut commands: Commands,
     units: Query<(&CombatConfig, &UnitMarker)>,
+    health_points: Query<&HealthPoints>,
     combat_resource: Res<CombatResource>,
+    mut combat_queue: ResMut<CombatQueue>,
     mut round_state: ResMut<NextState<RoundState>>,
 ) {
     debug!("Handling post combat");
@@ -255,31 +1256,90 @@ fn handle_post_combat(
     }
 
     if let Ok((_, unit_marker)) = units.get(combat_resource.attacker) {
-        filter_and_run_abilities(
-            &mut commands,
-            &combat_resource.attack.passive_combat_abilities,
-            unit_marker,
-            AbilityTrigger::OnAttack(CombatPhase::PostCombat),
-        );
+        let ability_triggers = [AbilityTrigger::OnAttack(CombatPhase::PostCombat)]
+            .into_iter()
+            .chain(combat_resource.combat_result.get_ability_triggers());
+        for ability_trigger in ability_triggers {
+            filter_and_run_abilities(
+                &mut commands,
+                &combat_resource.attack.passive_combat_abilities,
+                unit_marker,
+                ability_trigger,
+            );
+        }
     }
 
-    round_state.set(RoundState::Input);
+    match combat_queue.pop_next(|entity| is_alive(&health_points, entity)) {
+        Some(next_combat_resource) => {
+            commands.insert_resource(next_combat_resource);
+            round_state.set(RoundState::PreCombat);
+        }
+        None => round_state.set(
+            combat_queue
+                .state_after_combat
+                .take()
+                .unwrap_or(RoundState::Input),
+        ),
+    }
 }
 
+/// Also triggers the `OnKill` and `OnAllyKilled` passive abilities
 pub(super) fn despawn_dead_units(
     mut commands: Commands,
     mut units: Query<(Entity, &HealthPoints, &mut UnitStatus), Changed<HealthPoints>>,
+    unit_positions: Query<(Entity, &HexComponent, &Team), With<Morale>>,
+    combat_configs: Query<&CombatConfig>,
+    mut morale_check_event: EventWriter<MoraleCheckEvent>,
 ) {
     let despawned_entities: Vec<_> = units
         .iter()
         .filter(|(_, health_points, _)| health_points.left == 0)
-        .map(|(entity, _, _)| {
+        .map(|(entity, health_points, _)| {
             info!("Despawning {entity:?}, because health points are 0");
+            if let Some(killer) = health_points.last_damaged_by {
+                if let Ok(combat_config) = combat_configs.get(killer) {
+                    run_triggered_abilities(
+                        &mut commands,
+                        killer,
+                        Some(entity),
+                        combat_config,
+                        AbilityTrigger::OnKill,
+                    );
+                }
+            }
             commands.entity(entity).despawn_recursive();
             entity
         })
         .collect();
 
+    for (despawned_entity, despawned_hex, despawned_team) in despawned_entities
+        .iter()
+        .filter_map(|entity| unit_positions.get(*entity).ok())
+    {
+        unit_positions
+            .iter()
+            .filter(|(entity, hex, team)| {
+                team == &despawned_team
+                    && hex.0.unsigned_distance_to(despawned_hex.0) == 1
+                    && !despawned_entities.contains(entity)
+            })
+            .for_each(|(ally, _, _)| {
+                morale_check_event.send(MoraleCheckEvent {
+                    unit: ally,
+                    reason: MoraleCheckReason::AllyDied,
+                });
+                if let Ok(combat_config) = combat_configs.get(ally) {
+                    run_triggered_abilities(
+                        &mut commands,
+                        ally,
+                        Some(despawned_entity),
+                        combat_config,
+                        AbilityTrigger::OnAllyKilled,
+                    );
+                }
+            });
+    }
+
     for (_, _, mut unit_status) in &mut units {
         for despawned_entity in &despawned_entities {
             if unit_status.is_engaged_with(despawned_entity) {
@@ -288,3 +1348,981 @@ pub(super) fn despawn_dead_units(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, FromWorld, OnEnter, States};
+
+    use crate::game::abilities::defined_abilities::DefinedAbilityInput;
+    use crate::game::abilities::passive_combat_abilities::PassiveCombatAbilityRegistry;
+    use crate::game::ingame::attack_roll::RollMode;
+    use crate::game::ingame::hex::HexMarker;
+    use crate::game::ingame::selected_unit::SelectedUnitResource;
+    use crate::game::ingame::status_effects::{StatusEffect, StatusEffectType};
+    use crate::game::ingame::terrain::MovementCost;
+    use crate::game::states::round_state::{round_end_system, ActiveTeam};
+    use crate::generate_test_app;
+    use crate::tests::{add_active_ability, add_passive_ability, spawn_unit, AppWrapper};
+
+    use super::*;
+
+    fn terrain(name: &str, attack_modifier: i32, defense_modifier: i32) -> Terrain {
+        Terrain {
+            name: name.to_string(),
+            movement_cost: MovementCost::Passable(1),
+            attack_modifier,
+            defense_modifier,
+            line_of_sight: LineOfSight::Clear,
+            stops_charges: false,
+            attack_disadvantage: false,
+        }
+    }
+
+    #[test]
+    fn hit_target_without_terrain_is_the_defense() {
+        let plains = terrain("Plains", 0, 0);
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                defender_terrain: Some(&plains),
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 10);
+        assert_eq!(hit_target.modifiers, vec![]);
+        assert_eq!(hit_target.to_string(), "10");
+    }
+
+    #[test]
+    fn hit_target_includes_terrain_modifiers() {
+        let hills = terrain("Hills", 1, 1);
+        let forest = terrain("Forest", 0, 2);
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                attacker_terrain: Some(&hills),
+                defender_terrain: Some(&forest),
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 11);
+        assert!(hit_target.is_hit(11));
+        assert!(!hit_target.is_hit(10));
+        assert_eq!(
+            hit_target.to_string(),
+            "11 [defense 10, attacker in Hills -1, defender in Forest +2]"
+        );
+    }
+
+    #[test]
+    fn hit_target_does_not_drop_below_zero() {
+        let water = terrain("Water", 0, -2);
+        let hit_target = HitTarget::new(
+            1,
+            &CombatSituation {
+                defender_terrain: Some(&water),
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 0);
+    }
+
+    #[test]
+    fn obscured_line_of_sight_makes_hitting_harder() {
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                line_of_sight: LineOfSight::Obscured,
+                ..default()
+            },
+        );
+
+        assert_eq!(
+            hit_target.get_target(),
+            10 + OBSCURED_LINE_OF_SIGHT_MODIFIER as usize
+        );
+        assert_eq!(
+            hit_target.to_string(),
+            "12 [defense 10, obscured line of sight +2]"
+        );
+    }
+
+    #[test]
+    fn defender_uphill_is_harder_to_hit() {
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                attacker_elevation: Elevation(0),
+                defender_elevation: Elevation(2),
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 12);
+        assert_eq!(
+            hit_target.to_string(),
+            "12 [defense 10, defender uphill +2]"
+        );
+
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                attacker_elevation: Elevation(2),
+                defender_elevation: Elevation(0),
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 10);
+    }
+
+    #[test]
+    fn flanking_makes_hitting_easier() {
+        let hit_target = HitTarget::new(
+            10,
+            &CombatSituation {
+                flanking: Flanking {
+                    engaged_allies: 2,
+                    from_opposite_side: true,
+                },
+                ..default()
+            },
+        );
+
+        assert_eq!(hit_target.get_target(), 8);
+        assert_eq!(hit_target.to_string(), "8 [defense 10, flanked -2]");
+    }
+
+    #[test]
+    fn combat_situation_grants_advantage_and_disadvantage() {
+        let forest = Terrain {
+            attack_disadvantage: true,
+            ..terrain("Forest", 0, 2)
+        };
+        let combat_situation = CombatSituation {
+            attacker_terrain: Some(&forest),
+            flanking: Flanking {
+                engaged_allies: 1,
+                from_opposite_side: false,
+            },
+            attacker_shaken: true,
+            ..default()
+        };
+
+        let advantages: Vec<_> = combat_situation
+            .get_advantages()
+            .iter()
+            .map(|advantage| advantage.to_string())
+            .collect();
+
+        assert_eq!(
+            advantages,
+            vec![
+                "defender engaged elsewhere: advantage",
+                "attacker in Forest: disadvantage",
+                "attacker shaken: disadvantage",
+            ]
+        );
+        assert_eq!(
+            RollMode::new(&combat_situation.get_advantages()),
+            RollMode::Normal
+        );
+    }
+
+    #[test]
+    fn melee_defender_strikes_back() {
+        let mut app = TestApp::build([15, 15], melee_config(), melee_config());
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 4);
+        assert_eq!(app.get_health_points_left(app.attacker), 4);
+    }
+
+    #[test]
+    fn defender_that_can_not_counter_does_not_strike_back() {
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15], melee_config(), defender_config);
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 4);
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+    }
+
+    #[test]
+    fn attacks_with_first_strike_can_not_be_countered() {
+        let mut app = TestApp::build([5], melee_config(), melee_config());
+        let first_strike = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::FirstStrike);
+        app.app
+            .world
+            .entity_mut(app.attacker)
+            .get_mut::<CombatConfig>()
+            .unwrap()
+            .passive_combat_abilities
+            .push(first_strike);
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 5);
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+    }
+
+    #[test]
+    fn hits_with_poisoned_weapons_poison_the_defender() {
+        let mut app = TestApp::build([15, 5], melee_config(), melee_config());
+        let poisoned_weapons = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::PoisonedWeapons);
+        app.app
+            .world
+            .entity_mut(app.attacker)
+            .get_mut::<CombatConfig>()
+            .unwrap()
+            .passive_combat_abilities
+            .push(poisoned_weapons);
+
+        app.attack();
+
+        let status_effects = app
+            .app
+            .world
+            .entity(app.defender)
+            .get::<StatusEffects>()
+            .unwrap();
+        assert_eq!(status_effects.get_poison_damage(), 1);
+    }
+
+    #[test]
+    fn misses_with_armor_break_from_the_ability_file_break_the_armor_of_the_defender() {
+        let mut app = TestApp::build([5, 5], melee_config(), melee_config());
+        add_passive_ability(
+            &mut app.app.world,
+            app.attacker,
+            include_str!("../../../assets/abilities/armor_break.ability.ron"),
+        );
+
+        app.attack();
+
+        let status_effects = app
+            .app
+            .world
+            .entity(app.defender)
+            .get::<StatusEffects>()
+            .unwrap();
+        assert_eq!(
+            status_effects.get_effects(),
+            &[StatusEffect {
+                effect_type: StatusEffectType::ArmorBroken,
+                source: "Armor Break".to_string(),
+                stacks: 1,
+                remaining_rounds: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn hits_with_hit_and_run_from_the_ability_file_disengage_the_attacker() {
+        let mut app = TestApp::build([15, 5], melee_config(), melee_config());
+        add_passive_ability(
+            &mut app.app.world,
+            app.attacker,
+            include_str!("../../../assets/abilities/hit_and_run.ability.ron"),
+        );
+        let defender = app.defender;
+        app.app
+            .world
+            .entity_mut(app.attacker)
+            .get_mut::<UnitStatus>()
+            .unwrap()
+            .engage_with(defender);
+
+        app.attack();
+
+        let unit_status = app
+            .app
+            .world
+            .entity(app.attacker)
+            .get::<UnitStatus>()
+            .unwrap();
+        assert!(!unit_status.is_engaged_with(&defender));
+    }
+
+    #[test]
+    fn throwing_a_javelin_from_the_ability_file_uses_it_up() {
+        let mut app = TestApp::build([15], melee_config(), melee_config());
+        app.set_hex(app.defender, Hex::new(2, 0));
+        let javelin = add_active_ability(
+            &mut app.app.world,
+            app.attacker,
+            include_str!("../../../assets/abilities/throw_javelin.ability.ron"),
+        );
+
+        app.use_active_ability(javelin, Hex::new(2, 0));
+
+        assert_eq!(app.get_health_points_left(app.defender), 4);
+        let ability = app.app.world.get::<ActiveAbility>(javelin).unwrap();
+        assert_eq!(ability.get_ammo().map(|ammo| ammo.left), Some(0));
+        let action_points = app.app.world.get::<ActionPoints>(app.attacker).unwrap();
+        assert_eq!(action_points.left, 9);
+        assert!(!ability.can_be_used(action_points));
+    }
+
+    #[test]
+    fn active_abilities_without_damage_cost_the_attack_action_points() {
+        let mut app = TestApp::build([], melee_config(), melee_config());
+        let shove = add_active_ability(
+            &mut app.app.world,
+            app.attacker,
+            r#"(
+                name: "Shove",
+                trigger: Active(range: 1, targets: Enemy),
+                target: OtherUnit,
+                effects: [MoveUnit(distance: 2)],
+            )"#,
+        );
+
+        app.use_active_ability(shove, Hex::new(1, 0));
+
+        assert_eq!(
+            app.app.world.get::<HexComponent>(app.defender).unwrap().0,
+            Hex::new(3, 0)
+        );
+        let action_points = app.app.world.get::<ActionPoints>(app.attacker).unwrap();
+        assert_eq!(action_points.left, 9);
+    }
+
+    #[test]
+    fn units_are_not_moved_onto_other_units() {
+        let mut app = TestApp::build([], melee_config(), melee_config());
+        spawn_unit_with(&mut app.app, Team::Blue, Hex::new(3, 0), melee_config());
+        let shove = add_active_ability(
+            &mut app.app.world,
+            app.attacker,
+            r#"(
+                name: "Shove",
+                trigger: Active(range: 1, targets: Enemy),
+                target: OtherUnit,
+                effects: [MoveUnit(distance: 3)],
+            )"#,
+        );
+
+        app.use_active_ability(shove, Hex::new(1, 0));
+
+        assert_eq!(
+            app.app.world.get::<HexComponent>(app.defender).unwrap().0,
+            Hex::new(2, 0)
+        );
+    }
+
+    #[test]
+    fn adjacent_allies_get_the_stat_modifiers_of_an_active_ability() {
+        let mut app = TestApp::build([], melee_config(), melee_config());
+        let adjacent_ally =
+            spawn_unit_with(&mut app.app, Team::Red, Hex::new(-1, 0), melee_config());
+        let distant_ally =
+            spawn_unit_with(&mut app.app, Team::Red, Hex::new(-3, 0), melee_config());
+        let battle_cry = add_active_ability(
+            &mut app.app.world,
+            app.attacker,
+            r#"(
+                name: "Battle Cry",
+                trigger: Active(range: 1, targets: Enemy),
+                target: AdjacentAllies,
+                effects: [ModifyStat(stat: Defense, value: Add(2), rounds: Some(1))],
+            )"#,
+        );
+
+        app.use_active_ability(battle_cry, Hex::new(1, 0));
+
+        let get_defense = |unit: Entity| {
+            let world = &app.app.world;
+            world.get::<StatModifiers>(unit).unwrap().resolve(
+                Stat::Defense,
+                10,
+                world.get::<StatusEffects>(unit).unwrap(),
+            )
+        };
+        assert_eq!(get_defense(adjacent_ally).value, 12);
+        assert_eq!(get_defense(distant_ally).value, 10);
+        assert_eq!(get_defense(app.attacker).value, 10);
+        assert_eq!(get_defense(app.defender).value, 10);
+    }
+
+    #[test]
+    fn adjacent_enemies_get_the_status_effects_of_an_active_ability() {
+        let mut app = TestApp::build([], melee_config(), melee_config());
+        let adjacent_enemy =
+            spawn_unit_with(&mut app.app, Team::Blue, Hex::new(0, 1), melee_config());
+        let distant_enemy =
+            spawn_unit_with(&mut app.app, Team::Blue, Hex::new(3, 0), melee_config());
+        let adjacent_ally =
+            spawn_unit_with(&mut app.app, Team::Red, Hex::new(-1, 0), melee_config());
+        let sweep = add_active_ability(
+            &mut app.app.world,
+            app.attacker,
+            r#"(
+                name: "Sweep",
+                trigger: Active(range: 1, targets: Enemy),
+                target: AdjacentEnemies,
+                effects: [ApplyStatus(effect: ArmorBroken, rounds: 1)],
+            )"#,
+        );
+
+        app.use_active_ability(sweep, Hex::new(1, 0));
+
+        let is_armor_broken = |unit: Entity| {
+            !app.app
+                .world
+                .get::<StatusEffects>(unit)
+                .unwrap()
+                .get_effects()
+                .is_empty()
+        };
+        assert!(is_armor_broken(app.defender));
+        assert!(is_armor_broken(adjacent_enemy));
+        assert!(!is_armor_broken(distant_enemy));
+        assert!(!is_armor_broken(adjacent_ally));
+        assert!(!is_armor_broken(app.attacker));
+    }
+
+    #[test]
+    fn broken_armor_does_not_reduce_the_damage_taken() {
+        let attacker_config = CombatConfig {
+            damage: DiceExpression::constant(3),
+            ..melee_config()
+        };
+        let defender_config = CombatConfig {
+            damage_resistances: DamageResistances {
+                armor: 1,
+                ..default()
+            },
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15, 5], attacker_config, defender_config);
+        app.app
+            .world
+            .entity_mut(app.defender)
+            .get_mut::<StatusEffects>()
+            .unwrap()
+            .add(StatusEffect {
+                effect_type: StatusEffectType::ArmorBroken,
+                source: "test".to_string(),
+                stacks: 1,
+                remaining_rounds: 1,
+            });
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 2);
+    }
+
+    #[test]
+    fn armor_reduces_the_damage_taken() {
+        let attacker_config = CombatConfig {
+            damage: DiceExpression::constant(3),
+            ..melee_config()
+        };
+        let defender_config = CombatConfig {
+            damage_resistances: DamageResistances {
+                armor: 1,
+                ..default()
+            },
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15, 5], attacker_config, defender_config);
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 3);
+    }
+
+    #[test]
+    fn natural_20_deals_double_damage() {
+        let mut app = TestApp::build([20, 5], melee_config(), melee_config());
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.defender), 3);
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+    }
+
+    #[test]
+    fn natural_1_loses_remaining_attacks() {
+        let mut app = TestApp::build([1, 5], melee_config(), melee_config());
+
+        app.attack();
+
+        let action_points = app
+            .app
+            .world
+            .entity(app.attacker)
+            .get::<ActionPoints>()
+            .unwrap();
+        assert!(!action_points.can_still_attack_this_turn());
+        assert_eq!(app.get_health_points_left(app.defender), 5);
+    }
+
+    #[test]
+    fn a_fumbled_counter_attack_loses_the_remaining_attacks_of_the_defender() {
+        let mut app = TestApp::build([5, 1], melee_config(), melee_config());
+
+        app.attack();
+
+        let action_points = app
+            .app
+            .world
+            .entity(app.defender)
+            .get::<ActionPoints>()
+            .unwrap();
+        assert!(!action_points.can_still_attack_this_turn());
+        assert_eq!(app.get_health_points_left(app.attacker), 5);
+    }
+
+    #[test]
+    fn natural_1_can_give_a_free_counter_attack() {
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([1, 15], melee_config(), defender_config);
+        app.app.insert_resource(CombatRuleset {
+            fumble: FumbleRule::FreeCounterAttack,
+            ..default()
+        });
+
+        app.attack();
+
+        assert_eq!(app.get_health_points_left(app.attacker), 4);
+    }
+
+    generate_test_app!(attacker: Entity, defender: Entity);
+
+    #[test]
+    fn attacks_use_the_damage_and_action_point_cost_of_the_chosen_profile() {
+        let attacker_config = CombatConfig {
+            attacks: vec![Attack {
+                name: "Crossbow".to_string(),
+                damage: DiceExpression::constant(3),
+                damage_type: DamageType::Piercing,
+                range: 3,
+                action_point_cost: Some(4),
+                ammo: None,
+                ammo_source: None,
+                area: None,
+                passive_combat_abilities: vec![],
+            }],
+            ..melee_config()
+        };
+        let crossbow = attacker_config.get_attack(1).unwrap();
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15], attacker_config, defender_config);
+
+        app.send_attack(AttackOrDefault::Attack(crossbow));
+
+        let action_points = app
+            .app
+            .world
+            .entity(app.attacker)
+            .get::<ActionPoints>()
+            .unwrap();
+        assert_eq!(action_points.left, 6);
+        assert_eq!(app.get_health_points_left(app.defender), 2);
+    }
+
+    #[test]
+    fn simultaneous_attacks_are_resolved_one_after_another() {
+        let mut app = TestApp::build([15, 5, 15, 5], melee_config(), melee_config());
+        let second_attacker =
+            spawn_unit_with(&mut app.app, Team::Red, Hex::new(1, -1), melee_config());
+
+        for attacker in [app.attacker, second_attacker] {
+            app.send_event(CombatEvent {
+                attacker,
+                attack: AttackOrDefault::Default,
+                defender: app.defender,
+            });
+        }
+        app.update_until(RoundState::Input);
+
+        assert_eq!(app.get_health_points_left(app.defender), 3);
+        assert!(!app.app.world.resource::<CombatQueue>().is_resolving());
+    }
+
+    #[test]
+    fn passive_abilities_modify_the_counter_attack_roll() {
+        // the attack misses, the counterattack misses with 11 - 2 and hits with the rerolled 13 - 2
+        let mut app = TestApp::build([5, 11, 13], melee_config(), melee_config());
+        let registry = PassiveCombatAbilityRegistry::from_world(&mut app.app.world);
+        for (unit, ability) in [
+            (app.attacker, PassiveCombatAbility::Evasive),
+            (app.defender, PassiveCombatAbility::Lucky),
+            (app.defender, PassiveCombatAbility::Brutal),
+        ] {
+            app.app
+                .world
+                .entity_mut(unit)
+                .get_mut::<CombatConfig>()
+                .unwrap()
+                .passive_combat_abilities
+                .push(registry.get_registered_ability(ability));
+        }
+
+        app.send_attack(AttackOrDefault::Default);
+
+        assert_eq!(app.get_health_points_left(app.defender), 5);
+        assert_eq!(app.get_health_points_left(app.attacker), 3);
+        let combat_resource = app.app.world.resource::<CombatResource>();
+        assert_eq!(combat_resource.attacker, app.attacker);
+        assert_eq!(combat_resource.combat_result, CombatResult::Miss);
+    }
+
+    #[test]
+    fn passive_abilities_modify_the_attack_roll() {
+        let mut app = TestApp::build([11, 4, 5], melee_config(), melee_config());
+        let registry = PassiveCombatAbilityRegistry::from_world(&mut app.app.world);
+        for (unit, ability) in [
+            (app.attacker, PassiveCombatAbility::Lucky),
+            (app.defender, PassiveCombatAbility::Evasive),
+        ] {
+            app.app
+                .world
+                .entity_mut(unit)
+                .get_mut::<CombatConfig>()
+                .unwrap()
+                .passive_combat_abilities
+                .push(registry.get_registered_ability(ability));
+        }
+
+        app.send_attack(AttackOrDefault::Default);
+
+        let attack_roll = app
+            .app
+            .world
+            .resource::<CombatResource>()
+            .attack_roll
+            .clone();
+        assert_eq!(
+            attack_roll.unwrap().to_string(),
+            "2 [d20 4, rerolled 11 (Lucky), Evasive -2]"
+        );
+        assert_eq!(app.get_health_points_left(app.defender), 5);
+    }
+
+    #[test]
+    fn killing_a_unit_triggers_vengeance_of_adjacent_allies() {
+        let mut app = TestApp::build([15], melee_config(), melee_config());
+        let vengeance = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::Vengeance);
+        let ally_config = CombatConfig {
+            passive_combat_abilities: vec![vengeance],
+            ..melee_config()
+        };
+        let ally = spawn_unit_with(&mut app.app, Team::Blue, Hex::new(1, -1), ally_config);
+        app.app
+            .world
+            .entity_mut(app.defender)
+            .get_mut::<HealthPoints>()
+            .unwrap()
+            .left = 1;
+
+        app.attack();
+        app.update();
+
+        assert!(app.app.world.get_entity(app.defender).is_none());
+        let ally = app.app.world.entity(ally);
+        let defense = ally.get::<CombatConfig>().unwrap().get_effective_defense(
+            ally.get::<StatModifiers>().unwrap(),
+            ally.get::<StatusEffects>().unwrap(),
+        );
+        assert_eq!(defense.value, 12);
+    }
+
+    const BLOODLUST: &str = r#"(
+        name: "Bloodlust",
+        trigger: Passive(OnKill),
+        target: Owner,
+        effects: [ModifyStat(stat: Defense, value: Add(1))],
+    )"#;
+
+    #[test]
+    fn killing_a_unit_triggers_on_kill_abilities() {
+        let mut app = TestApp::build([15], melee_config(), melee_config());
+        add_passive_ability(&mut app.app.world, app.attacker, BLOODLUST);
+        app.set_health_points_left(app.defender, 1);
+
+        app.attack();
+        app.update();
+
+        assert!(app.app.world.get_entity(app.defender).is_none());
+        assert_eq!(app.get_defense(app.attacker), 11);
+    }
+
+    #[test]
+    fn units_dying_of_poison_are_not_killed_by_their_last_attacker() {
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15], melee_config(), defender_config);
+        add_passive_ability(&mut app.app.world, app.attacker, BLOODLUST);
+        app.attack();
+        app.set_health_points_left(app.defender, 1);
+        app.app
+            .world
+            .get_mut::<StatusEffects>(app.defender)
+            .unwrap()
+            .add(StatusEffect {
+                effect_type: StatusEffectType::Poisoned,
+                source: "test".to_string(),
+                stacks: 1,
+                remaining_rounds: 2,
+            });
+
+        app.app.insert_resource(ActiveTeam(Team::Blue));
+        app.app.init_resource::<SelectedUnitResource>();
+        app.app
+            .add_systems(OnEnter(RoundState::RoundEnd), round_end_system);
+        app.app
+            .world
+            .resource_mut::<NextState<RoundState>>()
+            .set(RoundState::RoundEnd);
+        app.update();
+        app.update();
+
+        assert!(app.app.world.get_entity(app.defender).is_none());
+        assert_eq!(app.get_defense(app.attacker), 10);
+    }
+
+    #[test]
+    fn surviving_damage_triggers_on_damaged_abilities() {
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15, 5], melee_config(), defender_config);
+        add_passive_ability(
+            &mut app.app.world,
+            app.defender,
+            r#"(
+                name: "Thick Skin",
+                trigger: Passive(OnDamaged),
+                target: Owner,
+                effects: [ModifyStat(stat: Defense, value: Add(1))],
+            )"#,
+        );
+
+        app.attack();
+        assert_eq!(app.get_defense(app.defender), 11);
+
+        app.attack();
+        assert_eq!(app.get_defense(app.defender), 11);
+    }
+
+    #[test]
+    fn attacks_use_up_their_ammo() {
+        let attacker_config = CombatConfig {
+            ammo: Some(Ammo::new(1)),
+            ..melee_config()
+        };
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15], attacker_config, defender_config);
+
+        app.attack();
+
+        let attacker = app.app.world.entity(app.attacker);
+        let attack = attacker.get::<CombatConfig>().unwrap().get_default_attack();
+        assert_eq!(attack.ammo.unwrap().left, 0);
+        assert!(!attacker
+            .get::<ActionPoints>()
+            .unwrap()
+            .can_still_attack_with(&attack));
+    }
+
+    #[test]
+    fn attacks_sharing_a_name_use_up_only_their_own_ammo() {
+        let javelin = Attack {
+            name: "Javelin".to_string(),
+            damage: DiceExpression::constant(1),
+            damage_type: DamageType::Piercing,
+            range: 2,
+            action_point_cost: None,
+            ammo: Some(Ammo::new(1)),
+            ammo_source: None,
+            area: None,
+            passive_combat_abilities: vec![],
+        };
+        let attacker_config = CombatConfig {
+            attacks: vec![javelin.clone(), javelin],
+            ..melee_config()
+        };
+        let second_javelin = attacker_config.get_attack(2).unwrap();
+        let defender_config = CombatConfig {
+            can_counter: false,
+            ..melee_config()
+        };
+        let mut app = TestApp::build([15], attacker_config, defender_config);
+
+        app.send_attack(AttackOrDefault::Attack(second_javelin));
+
+        let attacker_config = app.app.world.get::<CombatConfig>(app.attacker).unwrap();
+        let ammo_left: Vec<_> = attacker_config
+            .get_attacks()
+            .iter()
+            .filter_map(|attack| attack.ammo.map(|ammo| ammo.left))
+            .collect();
+        assert_eq!(ammo_left, vec![1, 0]);
+    }
+
+    fn melee_config() -> CombatConfig {
+        CombatConfig {
+            damage: DiceExpression::constant(1),
+            damage_type: DamageType::Slashing,
+            defense: 10,
+            damage_resistances: DamageResistances::default(),
+            range: 1,
+            can_counter: true,
+            passive_combat_abilities: vec![],
+            attacks: vec![],
+            ammo: None,
+        }
+    }
+
+    impl TestApp {
+        fn build(
+            dice_rolls: impl IntoIterator<Item = u8>,
+            attacker_config: CombatConfig,
+            defender_config: CombatConfig,
+        ) -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.init_state::<InGameState>();
+            app.world
+                .resource_mut::<NextState<InGameState>>()
+                .set(InGameState::Playing);
+            app.add_event::<LogEvent>();
+            app.add_event::<MoraleCheckEvent>();
+            app.insert_resource(DiceRng::scripted(dice_rolls));
+            app.init_resource::<CombatRuleset>();
+            app.add_plugins(CombatPlugin);
+
+            let plains = terrain("Plains", 0, 0);
+            Hex::ZERO.spiral_range(0..=3).for_each(|hex| {
+                app.world.spawn((
+                    HexMarker,
+                    HexComponent(hex),
+                    Elevation::default(),
+                    plains.clone(),
+                ));
+            });
+
+            let attacker = spawn_unit_with(&mut app, Team::Red, Hex::ZERO, attacker_config);
+            let defender = spawn_unit_with(&mut app, Team::Blue, Hex::new(1, 0), defender_config);
+
+            app.update();
+
+            TestApp {
+                app,
+                attacker,
+                defender,
+            }
+        }
+
+        fn attack(&mut self) {
+            self.send_attack(AttackOrDefault::Default);
+        }
+
+        fn send_attack(&mut self, attack: AttackOrDefault) {
+            self.send_event(CombatEvent {
+                attacker: self.attacker,
+                attack,
+                defender: self.defender,
+            });
+            self.update_until(RoundState::Input);
+        }
+
+        fn update_until<S: States>(&mut self, state: S) {
+            for _ in 0..20 {
+                self.update();
+                if self.get::<S>() == &state {
+                    return;
+                }
+            }
+            panic!("Did not reach {state:?}");
+        }
+
+        fn use_active_ability(&mut self, ability: Entity, target_hex: Hex) {
+            let ActiveAbility::Defined { system_id, .. } =
+                self.app.world.get::<ActiveAbility>(ability).unwrap()
+            else {
+                panic!("{ability:?} is not a defined ability");
+            };
+            let system_id = *system_id;
+            self.app
+                .world
+                .run_system_with_input(
+                    system_id,
+                    DefinedAbilityInput {
+                        user: self.attacker,
+                        ability,
+                        target_hex,
+                    },
+                )
+                .unwrap();
+            // the ability resets the round state before any attack it sends is resolved
+            self.update();
+            self.update_until(RoundState::Input);
+        }
+
+        fn set_health_points_left(&mut self, unit: Entity, left: usize) {
+            self.app.world.get_mut::<HealthPoints>(unit).unwrap().left = left;
+        }
+
+        fn get_defense(&self, unit: Entity) -> usize {
+            let unit = self.app.world.entity(unit);
+            unit.get::<CombatConfig>()
+                .unwrap()
+                .get_effective_defense(
+                    unit.get::<StatModifiers>().unwrap(),
+                    unit.get::<StatusEffects>().unwrap(),
+                )
+                .value
+        }
+
+        fn set_hex(&mut self, unit: Entity, hex: Hex) {
+            self.app.world.get_mut::<HexComponent>(unit).unwrap().0 = hex;
+        }
+
+        fn get_health_points_left(&self, entity: Entity) -> usize {
+            self.app
+                .world
+                .entity(entity)
+                .get::<HealthPoints>()
+                .unwrap()
+                .left
+        }
+    }
+
+    fn spawn_unit_with(app: &mut App, team: Team, hex: Hex, combat_config: CombatConfig) -> Entity {
+        let unit = spawn_unit(app, team, hex);
+        app.world.entity_mut(unit).insert((
+            combat_config,
+            ActionPoints::new(10, 2, 1),
+            Morale::new(20),
+        ));
+        unit
+    }
+}
@@ -0,0 +1,516 @@
+use bevy::prelude::{Query, Res, ResMut, Resource};
+use bevy_egui::egui::{show_tooltip_at_pointer, Id, Ui};
+use bevy_egui::EguiContexts;
+
+use crate::game::abilities::passive_combat_abilities::{
+    AbilityTrigger, CombatPhase, PassiveAbility, RegisteredPassiveCombatAbility,
+};
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::attack_roll::{get_damage_modifier, RollMode, RollModification};
+use crate::game::ingame::charge::{is_charging, TravelledPath};
+use crate::game::ingame::combat::{
+    is_in_attack_range, Attack, CombatConfig, CombatResult, CombatSituation, HealthPoints,
+    HitTarget,
+};
+use crate::game::ingame::combat_ruleset::CombatRuleset;
+use crate::game::ingame::damage_type::{DamageResistances, DamageType};
+use crate::game::ingame::flanking::Flanking;
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::hovered_hex::HoveredUnitResource;
+use crate::game::ingame::line_of_sight::LineOfSightMap;
+use crate::game::ingame::morale::Morale;
+use crate::game::ingame::selected_unit::SelectedUnitResource;
+use crate::game::ingame::stat_modifiers::StatModifiers;
+use crate::game::ingame::status_effects::StatusEffects;
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::unit::UnitFilter;
+use crate::game::ingame::unit_status::UnitStatus;
+use crate::game::util::dice_expression::DiceExpression;
+
+/// The odds of the selected unit attacking the hovered enemy
+#[derive(Resource, Debug, Default)]
+pub struct CombatPreviewResource(pub Option<CombatPreview>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombatPreview {
+    pub hit_target: HitTarget,
+    /// sources of advantage and disadvantage, see [`RollMode`], and the roll modifications of passive abilities
+    pub roll_modifications: Vec<RollModification>,
+    pub roll_mode: RollMode,
+    pub hit_chance: f32,
+    pub critical_hit_chance: f32,
+    pub fumble_chance: f32,
+    pub damage: DiceExpression,
+    pub damage_type: DamageType,
+    pub health_points_left: usize,
+    pub expected_health_points_left: f32,
+    pub kill_chance: f32,
+    /// Passive abilities of both units that may take effect, with the chance that they do
+    pub passive_abilities: Vec<(PassiveAbility, f32)>,
+    /// whether the defender strikes back if it survives
+    pub counter_attack: bool,
+}
+
+/// The defender of a previewed attack
+pub struct PreviewedDefender<'a> {
+    pub health_points: &'a HealthPoints,
+    pub damage_resistances: &'a DamageResistances,
+    pub passive_abilities: &'a [RegisteredPassiveCombatAbility],
+    /// whether the defender strikes back if it survives
+    pub counter_attack: bool,
+}
+
+impl CombatPreview {
+    pub fn new(
+        hit_target: HitTarget,
+        roll_modifications: Vec<RollModification>,
+        attack: &Attack,
+        defender: PreviewedDefender,
+        combat_ruleset: &CombatRuleset,
+    ) -> Self {
+        let PreviewedDefender {
+            health_points: defender_health_points,
+            damage_resistances: defender_damage_resistances,
+            passive_abilities: defender_passive_abilities,
+            counter_attack,
+        } = defender;
+        let roll_mode = RollMode::new(&roll_modifications);
+        let combat_result_chances =
+            combat_ruleset.get_combat_result_chances(&hit_target, &roll_modifications);
+        let damage_modifier = get_damage_modifier(&roll_modifications);
+        let health_points_left = defender_health_points.left;
+
+        let mut expected_health_points_left = 0.;
+        let mut kill_chance = 0.;
+        for (combat_result, result_chance) in &combat_result_chances {
+            let (damage, damage_factor) = match combat_result {
+                CombatResult::Hit => (attack.damage.clone(), 1),
+                CombatResult::CriticalHit => combat_ruleset.get_critical_damage(&attack.damage),
+                _ => (DiceExpression::constant(0), 0),
+            };
+            for (damage, damage_chance) in damage.get_distribution() {
+                let damage = ((damage * damage_factor) as i32 + damage_modifier).max(0) as usize;
+                let damage = defender_damage_resistances
+                    .get_damage_taken(damage, &attack.damage_type)
+                    .damage;
+                let chance = result_chance * damage_chance;
+                expected_health_points_left +=
+                    health_points_left.saturating_sub(damage) as f32 * chance;
+                if damage >= health_points_left {
+                    kill_chance += chance;
+                }
+            }
+        }
+
+        let get_chance = |filter: &dyn Fn(&CombatResult) -> bool| -> f32 {
+            combat_result_chances
+                .iter()
+                .filter(|(combat_result, _)| filter(combat_result))
+                .map(|(_, chance)| chance)
+                .sum()
+        };
+
+        let attacker_passive_abilities = attack
+            .passive_combat_abilities
+            .iter()
+            .filter(|ability| !matches!(ability.ability_trigger, AbilityTrigger::OnDefense(_)));
+        let defender_passive_abilities = defender_passive_abilities
+            .iter()
+            .filter(|ability| matches!(ability.ability_trigger, AbilityTrigger::OnDefense(_)));
+        let passive_abilities = attacker_passive_abilities
+            .chain(defender_passive_abilities)
+            .map(|registered_ability| {
+                let chance = get_chance(&|combat_result| {
+                    let is_triggered = match registered_ability.ability_trigger {
+                        AbilityTrigger::OnCriticalHit => {
+                            combat_result == &CombatResult::CriticalHit
+                        }
+                        AbilityTrigger::OnFumble => combat_result == &CombatResult::Fumble,
+                        AbilityTrigger::OnAttack(_) | AbilityTrigger::OnDefense(_) => true,
+                        AbilityTrigger::OnRoundStart
+                        | AbilityTrigger::OnRoundEnd
+                        | AbilityTrigger::OnMoveStep
+                        | AbilityTrigger::OnEnterHex
+                        | AbilityTrigger::OnEngaged
+                        | AbilityTrigger::OnKill
+                        | AbilityTrigger::OnAllyKilled
+                        | AbilityTrigger::OnDamaged => false,
+                    };
+                    is_triggered && registered_ability.ability.takes_effect_on(combat_result)
+                });
+                (registered_ability.ability.clone(), chance)
+            })
+            .filter(|(_, chance)| *chance > 0.)
+            .collect();
+
+        Self {
+            hit_target,
+            roll_modifications,
+            roll_mode,
+            hit_chance: get_chance(&CombatResult::is_hit),
+            critical_hit_chance: get_chance(&|combat_result| {
+                combat_result == &CombatResult::CriticalHit
+            }),
+            fumble_chance: get_chance(&|combat_result| combat_result == &CombatResult::Fumble),
+            damage: attack.damage.clone(),
+            damage_type: attack.damage_type,
+            health_points_left,
+            expected_health_points_left,
+            kill_chance,
+            passive_abilities,
+            counter_attack,
+        }
+    }
+
+    pub fn show(&self, ui: &mut Ui) {
+        ui.label(format!(
+            "Hit chance: {:.0}% (needs {})",
+            self.hit_chance * 100.,
+            self.hit_target
+        ));
+        if !self.roll_modifications.is_empty() {
+            let roll_modifications: Vec<_> = self
+                .roll_modifications
+                .iter()
+                .map(|roll_modification| roll_modification.to_string())
+                .collect();
+            ui.label(format!(
+                "Rolls with {} ({})",
+                self.roll_mode,
+                roll_modifications.join(", ")
+            ));
+        }
+        if self.critical_hit_chance > 0. || self.fumble_chance > 0. {
+            ui.label(format!(
+                "Critical hit: {:.0}%, fumble: {:.0}%",
+                self.critical_hit_chance * 100.,
+                self.fumble_chance * 100.
+            ));
+        }
+        ui.label(format!(
+            "Damage: {} {} ({}-{})",
+            self.damage,
+            self.damage_type,
+            self.damage.get_min(),
+            self.damage.get_max()
+        ));
+        ui.label(format!(
+            "Expected health points left: {:.1}/{}",
+            self.expected_health_points_left, self.health_points_left
+        ));
+        ui.label(format!("Kill chance: {:.0}%", self.kill_chance * 100.));
+        for (ability, chance) in &self.passive_abilities {
+            ui.label(format!("{ability}: {:.0}%", chance * 100.));
+        }
+        if self.counter_attack {
+            ui.label("Defender strikes back");
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub(super) fn update_combat_preview(
+    mut combat_preview: ResMut<CombatPreviewResource>,
+    selected_unit_resource: Res<SelectedUnitResource>,
+    hovered_unit_resource: Res<HoveredUnitResource>,
+    units: Query<
+        (
+            &HexComponent,
+            &Team,
+            &ActionPoints,
+            &CombatConfig,
+            &HealthPoints,
+            &UnitStatus,
+            &Morale,
+            &StatusEffects,
+            &StatModifiers,
+        ),
+        UnitFilter,
+    >,
+    hexes: TerrainHexesQuery,
+    combat_ruleset: Res<CombatRuleset>,
+    travelled_paths: Query<&TravelledPath>,
+) {
+    combat_preview.0 = None;
+
+    let (Some(attacker), Some(defender)) = (
+        selected_unit_resource.selected_unit(),
+        hovered_unit_resource.0,
+    ) else {
+        return;
+    };
+    let (
+        Ok((
+            attacker_hex,
+            attacker_team,
+            action_points,
+            attacker_config,
+            _,
+            _,
+            attacker_morale,
+            ..,
+        )),
+        Ok((
+            defender_hex,
+            defender_team,
+            _,
+            defender_config,
+            defender_health_points,
+            defender_status,
+            _,
+            defender_status_effects,
+            defender_stat_modifiers,
+        )),
+    ) = (units.get(attacker), units.get(defender))
+    else {
+        return;
+    };
+    let Some(attack) = attacker_config.get_attack(selected_unit_resource.selected_attack()) else {
+        return;
+    };
+    if attacker_team == defender_team || !action_points.can_still_attack_with(&attack) {
+        return;
+    }
+
+    let line_of_sight_map = LineOfSightMap::from_terrain(
+        &hexes,
+        units.iter().map(|(hex_component, ..)| hex_component.0),
+    );
+    if !is_in_attack_range(
+        &hexes,
+        &line_of_sight_map,
+        attack.range,
+        attacker_hex.0,
+        defender_hex.0,
+    ) {
+        return;
+    }
+
+    let flanking = Flanking::new(
+        attacker,
+        attacker_hex.0,
+        defender_hex.0,
+        defender_status,
+        |entity| units.get(*entity).ok().map(|(hex, ..)| hex.0),
+    );
+    let combat_situation = CombatSituation {
+        attacker_shaken: attacker_morale.is_shaken(),
+        attacker_charging: is_charging(
+            &attack,
+            travelled_paths.get(attacker).ok(),
+            &hexes,
+            attacker_hex.0,
+            defender_hex.0,
+        ),
+        ..CombatSituation::new(
+            &hexes,
+            &line_of_sight_map,
+            attacker_hex.0,
+            defender_hex.0,
+            flanking,
+        )
+    };
+    let hit_target = HitTarget::new(
+        defender_config
+            .get_effective_defense(defender_stat_modifiers, defender_status_effects)
+            .value,
+        &combat_situation,
+    );
+
+    let distance = attacker_hex.0.unsigned_distance_to(defender_hex.0);
+    let counter_attack = defender_config.can_counter_attack(&attack, distance);
+
+    // the same abilities that change the roll in the pre combat phase
+    let attacker_abilities = attack.passive_combat_abilities.iter().filter(|ability| {
+        ability.ability_trigger == AbilityTrigger::OnAttack(CombatPhase::PreCombat)
+    });
+    let defender_abilities = defender_config
+        .passive_combat_abilities
+        .iter()
+        .filter(|ability| {
+            ability.ability_trigger == AbilityTrigger::OnDefense(CombatPhase::PreCombat)
+        });
+    let roll_modifications = combat_situation
+        .get_advantages()
+        .into_iter()
+        .chain(
+            attacker_abilities
+                .chain(defender_abilities)
+                .filter_map(|ability| ability.ability.get_roll_modification()),
+        )
+        .collect();
+
+    combat_preview.0 = Some(CombatPreview::new(
+        hit_target,
+        roll_modifications,
+        &attack,
+        PreviewedDefender {
+            health_points: defender_health_points,
+            damage_resistances: &defender_config
+                .get_effective_damage_resistances(defender_stat_modifiers, defender_status_effects),
+            passive_abilities: &defender_config.passive_combat_abilities,
+            counter_attack,
+        },
+        &combat_ruleset,
+    ));
+}
+
+pub(super) fn show_combat_preview_tooltip(
+    mut contexts: EguiContexts,
+    combat_preview: Res<CombatPreviewResource>,
+) {
+    let Some(combat_preview) = &combat_preview.0 else {
+        return;
+    };
+
+    show_tooltip_at_pointer(contexts.ctx_mut(), Id::new("combat_preview"), |ui| {
+        combat_preview.show(ui);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+
+    use crate::game::abilities::passive_combat_abilities::PassiveCombatAbility;
+    use crate::game::ingame::combat_ruleset::{CriticalHitRule, FumbleRule};
+    use crate::game::ingame::line_of_sight::LineOfSight;
+
+    use super::*;
+
+    fn attack(damage: &str) -> Attack {
+        Attack {
+            name: "test attack".to_string(),
+            damage: damage.parse().unwrap(),
+            damage_type: DamageType::Slashing,
+            range: 1,
+            action_point_cost: None,
+            ammo: None,
+            ammo_source: None,
+            area: None,
+            passive_combat_abilities: vec![],
+        }
+    }
+
+    fn defender<'a>(
+        health_points: &'a HealthPoints,
+        damage_resistances: &'a DamageResistances,
+    ) -> PreviewedDefender<'a> {
+        PreviewedDefender {
+            health_points,
+            damage_resistances,
+            passive_abilities: &[],
+            counter_attack: false,
+        }
+    }
+
+    fn without_natural_rolls() -> CombatRuleset {
+        CombatRuleset {
+            critical_hit: CriticalHitRule::Disabled,
+            fumble: FumbleRule::Disabled,
+        }
+    }
+
+    #[test]
+    fn preview_combines_hit_chance_and_damage() {
+        let hit_target = HitTarget::new(11, &CombatSituation::default());
+        let mut defender_health_points = HealthPoints::new(3);
+        defender_health_points.left = 2;
+
+        let preview = CombatPreview::new(
+            hit_target,
+            vec![],
+            &attack("1d2"),
+            defender(&defender_health_points, &DamageResistances::default()),
+            &without_natural_rolls(),
+        );
+
+        assert_eq!(preview.hit_chance, 0.5);
+        assert_eq!(preview.health_points_left, 2);
+        assert_eq!(preview.expected_health_points_left, 0.5 * 0.5 + 0.5 * 2.);
+        assert_eq!(preview.kill_chance, 0.25);
+        assert_eq!(preview.passive_abilities, vec![]);
+    }
+
+    #[test]
+    fn preview_uses_the_modifiers_of_the_combat_situation() {
+        let hit_target = HitTarget::new(
+            11,
+            &CombatSituation {
+                line_of_sight: LineOfSight::Obscured,
+                ..default()
+            },
+        );
+
+        let preview = CombatPreview::new(
+            hit_target,
+            vec![],
+            &attack("1"),
+            defender(&HealthPoints::new(1), &DamageResistances::default()),
+            &without_natural_rolls(),
+        );
+
+        assert_eq!(preview.hit_chance, 0.4);
+        assert_eq!(preview.kill_chance, 0.4);
+    }
+
+    #[test]
+    fn preview_includes_the_roll_modifications_of_passive_abilities() {
+        let hit_target = HitTarget::new(11, &CombatSituation::default());
+        let roll_modifications = [PassiveCombatAbility::Evasive, PassiveCombatAbility::Brutal]
+            .iter()
+            .filter_map(PassiveCombatAbility::get_roll_modification)
+            .collect();
+
+        let preview = CombatPreview::new(
+            hit_target,
+            roll_modifications,
+            &attack("1"),
+            defender(&HealthPoints::new(2), &DamageResistances::default()),
+            &without_natural_rolls(),
+        );
+
+        assert_eq!(preview.hit_chance, 0.4);
+        assert_eq!(preview.kill_chance, 0.4);
+        assert_eq!(preview.roll_modifications.len(), 2);
+    }
+
+    #[test]
+    fn preview_includes_critical_hits_and_fumbles() {
+        let hit_target = HitTarget::new(21, &CombatSituation::default());
+
+        let preview = CombatPreview::new(
+            hit_target,
+            vec![],
+            &attack("1"),
+            defender(&HealthPoints::new(2), &DamageResistances::default()),
+            &CombatRuleset::default(),
+        );
+
+        assert_eq!(preview.hit_chance, 0.05);
+        assert_eq!(preview.critical_hit_chance, 0.05);
+        assert_eq!(preview.fumble_chance, 0.05);
+        assert_eq!(preview.kill_chance, 0.05);
+    }
+
+    #[test]
+    fn preview_applies_armor_to_the_damage() {
+        let hit_target = HitTarget::new(11, &CombatSituation::default());
+        let damage_resistances = DamageResistances {
+            armor: 1,
+            ..default()
+        };
+
+        let preview = CombatPreview::new(
+            hit_target,
+            vec![],
+            &attack("1d2"),
+            defender(&HealthPoints::new(1), &damage_resistances),
+            &without_natural_rolls(),
+        );
+
+        assert_eq!(preview.hit_chance, 0.5);
+        assert_eq!(preview.kill_chance, 0.25);
+        assert_eq!(preview.expected_health_points_left, 0.75);
+    }
+}
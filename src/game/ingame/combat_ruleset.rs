@@ -0,0 +1,250 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::Resource;
+use enum_iterator::Sequence;
+
+#[cfg(doc)]
+use crate::game::ingame::attack_roll::AttackRoll;
+use crate::game::ingame::attack_roll::{
+    get_roll_modifier, RollMode, RollModification, RollModificationKind,
+};
+use crate::game::ingame::combat::{CombatResult, HitTarget};
+use crate::game::util::dice_expression::DiceExpression;
+
+/// Optional rules for natural 20s and natural 1s on the attack roll
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct CombatRuleset {
+    pub critical_hit: CriticalHitRule,
+    pub fumble: FumbleRule,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum CriticalHitRule {
+    /// A natural 20 is an ordinary roll
+    Disabled,
+    /// A natural 20 always hits and deals twice the rolled damage
+    #[default]
+    DoubleDamage,
+    /// A natural 20 always hits and rolls the damage dice twice
+    ExtraDamageDice,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Sequence)]
+pub enum FumbleRule {
+    /// A natural 1 is an ordinary roll
+    Disabled,
+    /// A natural 1 always misses and the attacker can not attack anymore this round
+    #[default]
+    LoseAttacks,
+    /// A natural 1 always misses and the defender strikes back, even if it usually can not
+    FreeCounterAttack,
+}
+
+impl CombatRuleset {
+    pub fn get_combat_result(&self, dice_roll: u8, hit_target: &HitTarget) -> CombatResult {
+        self.get_modified_combat_result(dice_roll, dice_roll, hit_target)
+    }
+
+    /// Natural 20s and 1s are checked on the die itself, hits on the roll after all modifiers
+    pub fn get_modified_combat_result(
+        &self,
+        natural_roll: u8,
+        modified_roll: u8,
+        hit_target: &HitTarget,
+    ) -> CombatResult {
+        match natural_roll {
+            20 if self.critical_hit != CriticalHitRule::Disabled => CombatResult::CriticalHit,
+            1 if self.fumble != FumbleRule::Disabled => CombatResult::Fumble,
+            _ if hit_target.is_hit(modified_roll) => CombatResult::Hit,
+            _ => CombatResult::Miss,
+        }
+    }
+
+    /// The chance of every combat result of an attack roll with the roll modifications against the hit target,
+    /// misses are rolled again once per reroll like in [`AttackRoll::roll`]
+    pub fn get_combat_result_chances(
+        &self,
+        hit_target: &HitTarget,
+        roll_modifications: &[RollModification],
+    ) -> Vec<(CombatResult, f32)> {
+        let roll_mode = RollMode::new(roll_modifications);
+        let roll_modifier = get_roll_modifier(roll_modifications);
+        let mut roll_counts: Vec<(CombatResult, usize)> = vec![];
+        for dice_roll in 1..=20 {
+            let modified_roll = (dice_roll as i32 + roll_modifier).clamp(0, u8::MAX as i32) as u8;
+            let combat_result =
+                self.get_modified_combat_result(dice_roll, modified_roll, hit_target);
+            let outcomes = roll_mode.get_outcomes(dice_roll);
+            match roll_counts
+                .iter_mut()
+                .find(|(result, _)| result == &combat_result)
+            {
+                Some((_, count)) => *count += outcomes,
+                None => roll_counts.push((combat_result, outcomes)),
+            }
+        }
+        let all_outcomes = roll_mode.get_all_outcomes() as f32;
+
+        let rerolls = roll_modifications
+            .iter()
+            .filter(|modification| modification.kind == RollModificationKind::Reroll)
+            .count() as i32;
+        let miss_chance: f32 = roll_counts
+            .iter()
+            .filter(|(combat_result, _)| combat_result.is_miss())
+            .map(|(_, count)| *count as f32 / all_outcomes)
+            .sum();
+        // a result other than a miss ends the rerolls, so it can come from any of the rolls
+        let chance_to_reach_roll = |roll: i32| miss_chance.powi(roll);
+        let non_miss_factor: f32 = (0..=rerolls).map(chance_to_reach_roll).sum();
+        roll_counts
+            .into_iter()
+            .map(|(combat_result, count)| {
+                let factor = match combat_result.is_miss() {
+                    true => chance_to_reach_roll(rerolls),
+                    false => non_miss_factor,
+                };
+                (combat_result, count as f32 / all_outcomes * factor)
+            })
+            .collect()
+    }
+
+    /// The damage dice rolled on a critical hit and the factor the rolled damage is multiplied with
+    pub fn get_critical_damage(&self, damage: &DiceExpression) -> (DiceExpression, usize) {
+        match self.critical_hit {
+            CriticalHitRule::Disabled => (damage.clone(), 1),
+            CriticalHitRule::DoubleDamage => (damage.clone(), 2),
+            CriticalHitRule::ExtraDamageDice => (damage.with_doubled_dice(), 1),
+        }
+    }
+}
+
+impl Display for CriticalHitRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CriticalHitRule::Disabled => write!(f, "No critical hits"),
+            CriticalHitRule::DoubleDamage => write!(f, "Double damage"),
+            CriticalHitRule::ExtraDamageDice => write!(f, "Extra damage dice"),
+        }
+    }
+}
+
+impl Display for FumbleRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FumbleRule::Disabled => write!(f, "No fumbles"),
+            FumbleRule::LoseAttacks => write!(f, "Lose remaining attacks"),
+            FumbleRule::FreeCounterAttack => write!(f, "Free counterattack"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hit_target(defense: usize) -> HitTarget {
+        HitTarget {
+            defense,
+            modifiers: vec![],
+        }
+    }
+
+    fn modification(kind: RollModificationKind) -> RollModification {
+        RollModification {
+            kind,
+            source: "test".to_string(),
+        }
+    }
+
+    #[test]
+    fn natural_20_is_a_critical_hit_even_against_high_defense() {
+        let ruleset = CombatRuleset::default();
+
+        assert_eq!(
+            ruleset.get_combat_result(20, &hit_target(25)),
+            CombatResult::CriticalHit
+        );
+        assert_eq!(
+            ruleset.get_combat_result(1, &hit_target(0)),
+            CombatResult::Fumble
+        );
+        assert_eq!(
+            ruleset.get_combat_result(10, &hit_target(10)),
+            CombatResult::Hit
+        );
+    }
+
+    #[test]
+    fn disabled_rules_treat_natural_rolls_as_ordinary_rolls() {
+        let ruleset = CombatRuleset {
+            critical_hit: CriticalHitRule::Disabled,
+            fumble: FumbleRule::Disabled,
+        };
+
+        assert_eq!(
+            ruleset.get_combat_result(20, &hit_target(25)),
+            CombatResult::Miss
+        );
+        assert_eq!(
+            ruleset.get_combat_result(1, &hit_target(0)),
+            CombatResult::Hit
+        );
+    }
+
+    #[test]
+    fn combat_result_chances_add_up() {
+        let chances = CombatRuleset::default().get_combat_result_chances(&hit_target(11), &[]);
+
+        assert_eq!(
+            chances,
+            vec![
+                (CombatResult::Fumble, 0.05),
+                (CombatResult::Miss, 0.45),
+                (CombatResult::Hit, 0.45),
+                (CombatResult::CriticalHit, 0.05),
+            ]
+        );
+
+        let chances = CombatRuleset::default().get_combat_result_chances(
+            &hit_target(11),
+            &[modification(RollModificationKind::Advantage)],
+        );
+
+        assert_eq!(
+            chances,
+            vec![
+                (CombatResult::Fumble, 0.0025),
+                (CombatResult::Miss, 0.2475),
+                (CombatResult::Hit, 0.6525),
+                (CombatResult::CriticalHit, 0.0975),
+            ]
+        );
+    }
+
+    #[test]
+    fn combat_result_chances_include_roll_modifiers_and_rerolls() {
+        let ruleset = CombatRuleset {
+            critical_hit: CriticalHitRule::Disabled,
+            fumble: FumbleRule::Disabled,
+        };
+
+        let chances = ruleset.get_combat_result_chances(
+            &hit_target(11),
+            &[modification(RollModificationKind::Roll(-2))],
+        );
+        assert_eq!(
+            chances,
+            vec![(CombatResult::Miss, 0.6), (CombatResult::Hit, 0.4)]
+        );
+
+        let chances = ruleset.get_combat_result_chances(
+            &hit_target(11),
+            &[modification(RollModificationKind::Reroll)],
+        );
+        assert_eq!(
+            chances,
+            vec![(CombatResult::Miss, 0.25), (CombatResult::Hit, 0.75)]
+        );
+    }
+}
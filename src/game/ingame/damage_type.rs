@@ -0,0 +1,178 @@
+use std::fmt::{Display, Formatter};
+
+use enum_iterator::Sequence;
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Default, Clone, Copy, Hash, PartialEq, Eq, Sequence,
+)]
+pub enum DamageType {
+    #[default]
+    Slashing,
+    Piercing,
+    Blunt,
+    Fire,
+}
+
+impl DamageType {
+    /// Armor only protects against physical damage
+    pub fn is_physical(&self) -> bool {
+        match self {
+            DamageType::Slashing | DamageType::Piercing | DamageType::Blunt => true,
+            DamageType::Fire => false,
+        }
+    }
+}
+
+impl Display for DamageType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DamageType::Slashing => write!(f, "slashing"),
+            DamageType::Piercing => write!(f, "piercing"),
+            DamageType::Blunt => write!(f, "blunt"),
+            DamageType::Fire => write!(f, "fire"),
+        }
+    }
+}
+
+/// How a unit reduces the damage of attacks that hit it
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DamageResistances {
+    /// subtracted from physical damage after resistances and vulnerabilities
+    pub armor: usize,
+    /// damage of these types is halved, rounded down
+    pub resistances: Vec<DamageType>,
+    /// damage of these types is doubled
+    pub vulnerabilities: Vec<DamageType>,
+}
+
+/// The damage a unit takes after its [`DamageResistances`] were applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DamageTaken {
+    pub damage: usize,
+    pub modifications: Vec<DamageModification>,
+}
+
+/// A change to the damage taken, resistances and armor lower it while vulnerabilities raise it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DamageModification {
+    Resistance,
+    Vulnerability,
+    Armor(usize),
+}
+
+impl DamageResistances {
+    pub fn get_damage_taken(&self, damage: usize, damage_type: &DamageType) -> DamageTaken {
+        let mut damage = damage;
+        let mut modifications = vec![];
+
+        let is_resistant = self.resistances.contains(damage_type);
+        let is_vulnerable = self.vulnerabilities.contains(damage_type);
+        if is_resistant && !is_vulnerable {
+            damage /= 2;
+            modifications.push(DamageModification::Resistance);
+        }
+        if is_vulnerable && !is_resistant {
+            damage *= 2;
+            modifications.push(DamageModification::Vulnerability);
+        }
+
+        if damage_type.is_physical() && self.armor > 0 && damage > 0 {
+            let absorbed = self.armor.min(damage);
+            damage -= absorbed;
+            modifications.push(DamageModification::Armor(absorbed));
+        }
+
+        DamageTaken {
+            damage,
+            modifications,
+        }
+    }
+}
+
+impl Display for DamageModification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DamageModification::Resistance => write!(f, "resistant /2"),
+            DamageModification::Vulnerability => write!(f, "vulnerable x2"),
+            DamageModification::Armor(absorbed) => write!(f, "armor -{absorbed}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn armor_reduces_only_physical_damage() {
+        let damage_resistances = DamageResistances {
+            armor: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            damage_resistances.get_damage_taken(3, &DamageType::Piercing),
+            DamageTaken {
+                damage: 2,
+                modifications: vec![DamageModification::Armor(1)],
+            }
+        );
+        assert_eq!(
+            damage_resistances
+                .get_damage_taken(3, &DamageType::Fire)
+                .damage,
+            3
+        );
+        assert_eq!(
+            damage_resistances
+                .get_damage_taken(0, &DamageType::Blunt)
+                .damage,
+            0
+        );
+    }
+
+    #[test]
+    fn resistances_and_vulnerabilities_apply_before_armor() {
+        let damage_resistances = DamageResistances {
+            armor: 1,
+            resistances: vec![DamageType::Piercing],
+            vulnerabilities: vec![DamageType::Blunt],
+        };
+
+        assert_eq!(
+            damage_resistances
+                .get_damage_taken(5, &DamageType::Piercing)
+                .damage,
+            1
+        );
+        assert_eq!(
+            damage_resistances
+                .get_damage_taken(2, &DamageType::Blunt)
+                .damage,
+            3
+        );
+        assert_eq!(
+            damage_resistances
+                .get_damage_taken(2, &DamageType::Slashing)
+                .damage,
+            1
+        );
+    }
+
+    #[test]
+    fn resistance_and_vulnerability_cancel_out() {
+        let damage_resistances = DamageResistances {
+            armor: 0,
+            resistances: vec![DamageType::Fire],
+            vulnerabilities: vec![DamageType::Fire],
+        };
+
+        assert_eq!(
+            damage_resistances.get_damage_taken(3, &DamageType::Fire),
+            DamageTaken {
+                damage: 3,
+                modifications: vec![],
+            }
+        );
+    }
+}
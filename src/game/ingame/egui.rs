@@ -4,15 +4,23 @@ use bevy::prelude::{
     warn, Children, Commands, Entity, Event, EventReader, EventWriter, FromWorld, In, Local,
     NextState, Query, Res, ResMut, With, World,
 };
-use bevy_egui::egui::{Ui, Window};
+use bevy_egui::egui::{Color32, RichText, Ui, Window};
 use bevy_egui::EguiContexts;
 
 use crate::game::abilities::active_abilities::{ActivatedAbilityMarker, ActiveAbility};
 use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::ammo::{ResupplyEvent, RESUPPLY_ACTION_POINT_COST};
+use crate::game::ingame::charge::TravelledPath;
 use crate::game::ingame::combat::{CombatConfig, HealthPoints};
+use crate::game::ingame::combat_preview::CombatPreviewResource;
+use crate::game::ingame::elevation::Elevation;
+use crate::game::ingame::healing::{can_rest, RestEvent, REST_HEALING};
 use crate::game::ingame::hex::HexComponent;
 use crate::game::ingame::hovered_hex::{HoveredHex, HoveredUnitResource};
+use crate::game::ingame::morale::Morale;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
+use crate::game::ingame::stat_modifiers::{Stat, StatModifiers};
+use crate::game::ingame::status_effects::StatusEffects;
 use crate::game::ingame::team_setup::Team;
 use crate::game::ingame::terrain::Terrain;
 use crate::game::ingame::unit::UnitMarker;
@@ -28,7 +36,11 @@ type UnitQuery<'world, 'state, 'a> = Query<
         &'a HealthPoints,
         &'a Team,
         &'a UnitStatus,
+        &'a Morale,
+        &'a StatusEffects,
+        &'a StatModifiers,
         &'a CombatConfig,
+        &'a TravelledPath,
         &'a Children,
     ),
 >;
@@ -37,6 +49,10 @@ type UnitQuery<'world, 'state, 'a> = Query<
 pub enum UiEvent {
     EndRound,
     ActivateAbility(Entity),
+    /// index into [`CombatConfig::get_attacks`] of the selected unit
+    SelectAttack(usize),
+    Resupply(Entity),
+    Rest(Entity),
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -49,7 +65,8 @@ pub(super) fn ui_system(
     units: UnitQuery,
     active_abilities: Query<(Entity, &ActiveAbility)>,
     hovered_hex: Res<HoveredHex>,
-    terrain_hexes: Query<(&Terrain, &HexComponent)>,
+    terrain_hexes: Query<(&Terrain, &Elevation, &HexComponent)>,
+    combat_preview: Res<CombatPreviewResource>,
 ) {
     Window::new("Round").show(contexts.ctx_mut(), |ui| {
         ui.heading(format!("Round of {}", active_team.0));
@@ -68,6 +85,12 @@ pub(super) fn ui_system(
 
         ui.separator();
 
+        if let Some(combat_preview) = &combat_preview.0 {
+            ui.heading("Attack:");
+            combat_preview.show(ui);
+            ui.separator();
+        }
+
         display_terrain(hovered_hex, terrain_hexes, ui);
 
         ui.separator();
@@ -98,8 +121,19 @@ fn display_selected_unit(
         return;
     };
 
-    let Ok((unit_marker, action_points, health_points, team, unit_status, combat_config, children)) =
-        units.get(selected_unit)
+    let Ok((
+        unit_marker,
+        action_points,
+        health_points,
+        team,
+        unit_status,
+        morale,
+        status_effects,
+        stat_modifiers,
+        combat_config,
+        travelled_path,
+        children,
+    )) = units.get(selected_unit)
     else {
         ui.label("-");
         return;
@@ -107,11 +141,12 @@ fn display_selected_unit(
 
     ui.label(format!("Unit: {}", unit_marker.0));
     ui.label(format!("Owner: {team}"));
+    let max_action_points = action_points.get_effective_max(stat_modifiers, status_effects);
     ui.label(format!(
         "Action points: {}/{}",
-        action_points.left,
-        action_points.get_max()
-    ));
+        action_points.left, max_action_points.value
+    ))
+    .on_hover_text(max_action_points.to_string());
     ui.label(format!(
         "Attacks this turn: {}/{}",
         action_points.attacks_this_round,
@@ -122,21 +157,100 @@ fn display_selected_unit(
         health_points.left,
         health_points.get_max()
     ));
+    ui.label(format!("Morale: {} ({})", morale.state, morale.get_value()));
     ui.label(format!("Status: {unit_status:#?}"));
-    ui.label(format!("Combat Stats: {combat_config:#?}"));
+    if !status_effects.get_effects().is_empty() {
+        ui.horizontal_wrapped(|ui| {
+            for status_effect in status_effects.get_effects() {
+                let [r, g, b, _] = status_effect.effect_type.get_badge_color().as_rgba_u8();
+                ui.label(
+                    RichText::new(status_effect.to_string())
+                        .background_color(Color32::from_rgb(r, g, b))
+                        .color(Color32::BLACK),
+                );
+            }
+        });
+    }
+    ui.label("Combat Stats:");
+    let effective_stats = [
+        combat_config.get_effective_defense(stat_modifiers, status_effects),
+        stat_modifiers.resolve(
+            Stat::Armor,
+            combat_config.damage_resistances.armor,
+            status_effects,
+        ),
+        stat_modifiers.resolve(Stat::StepCost, 0, status_effects),
+    ];
+    for effective_stat in effective_stats
+        .iter()
+        .filter(|effective_stat| effective_stat.base > 0 || effective_stat.value > 0)
+    {
+        ui.label(effective_stat.to_string());
+    }
+    ui.collapsing("Base stats", |ui| {
+        ui.label(format!("{combat_config:#?}"));
+    });
+
+    let belongs_to_active_team = &active_team.0 == team;
+    if belongs_to_active_team && selected_unit_resource.selected_unit() == Some(selected_unit) {
+        display_attack_selection(
+            combat_config,
+            action_points,
+            selected_unit_resource.selected_attack(),
+            ui_event,
+            ui,
+        );
+    }
+
+    let rest_button = ui.add_enabled(
+        belongs_to_active_team && can_rest(action_points, travelled_path),
+        bevy_egui::egui::Button::new(format!("Rest (+{REST_HEALING} HP, ends turn)")),
+    );
+    if rest_button.clicked() {
+        ui_event.send(UiEvent::Rest(selected_unit));
+    }
 
     let abilities = children
         .into_iter()
         .filter_map(|child| active_abilities.get(*child).ok())
         .collect::<Vec<_>>();
 
+    let ammo = combat_config
+        .get_attacks()
+        .into_iter()
+        .filter_map(|attack| attack.ammo.map(|ammo| (attack.name, ammo)))
+        .chain(abilities.iter().filter_map(|(_, ability)| {
+            ability
+                .get_ammo()
+                .map(|ammo| (ability.get_display_name(), *ammo))
+        }))
+        .collect::<Vec<_>>();
+    if !ammo.is_empty() {
+        ui.label(format!(
+            "Ammo: {}",
+            ammo.iter()
+                .map(|(name, ammo)| format!("{name} {ammo}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+        let can_resupply = belongs_to_active_team
+            && action_points.left >= RESUPPLY_ACTION_POINT_COST
+            && ammo.iter().any(|(_, ammo)| !ammo.is_full());
+        let resupply_button = ui.add_enabled(
+            can_resupply,
+            bevy_egui::egui::Button::new(format!("Resupply ({RESUPPLY_ACTION_POINT_COST} AP)")),
+        );
+        if resupply_button.clicked() {
+            ui_event.send(UiEvent::Resupply(selected_unit));
+        }
+    }
+
     if abilities.is_empty() {
         return;
     }
     ui.separator();
     ui.label("Abilities:".to_string());
     for (ability_entity, active_ability) in abilities {
-        let belongs_to_active_team = &active_team.0 == team;
         let is_enabled = belongs_to_active_team && active_ability.can_be_used(action_points);
 
         let ability_button = ui.add_enabled(
@@ -149,9 +263,43 @@ fn display_selected_unit(
     }
 }
 
+fn display_attack_selection(
+    combat_config: &CombatConfig,
+    action_points: &ActionPoints,
+    selected_attack: usize,
+    ui_event: &mut EventWriter<UiEvent>,
+    ui: &mut Ui,
+) {
+    let attacks = combat_config.get_attacks();
+    if attacks.len() < 2 {
+        return;
+    }
+    ui.separator();
+    ui.label("Attacks:".to_string());
+    for (index, attack) in attacks.iter().enumerate() {
+        let mut text = format!(
+            "{}: {} {}, range {}, {} AP",
+            attack.name,
+            attack.damage,
+            attack.damage_type,
+            attack.range,
+            action_points.get_attack_cost(attack)
+        );
+        if let Some(ammo) = attack.ammo {
+            text.push_str(&format!(", ammo {ammo}"));
+        }
+        if ui
+            .selectable_label(index == selected_attack, text)
+            .clicked()
+        {
+            ui_event.send(UiEvent::SelectAttack(index));
+        }
+    }
+}
+
 fn display_terrain(
     hovered_hex: Res<HoveredHex>,
-    terrain_hexes: Query<(&Terrain, &HexComponent)>,
+    terrain_hexes: Query<(&Terrain, &Elevation, &HexComponent)>,
     ui: &mut Ui,
 ) {
     ui.heading("Terrain:");
@@ -160,9 +308,9 @@ fn display_terrain(
         return;
     };
 
-    let Some((terrain, hex_component)) = terrain_hexes
+    let Some((terrain, elevation, hex_component)) = terrain_hexes
         .iter()
-        .find(|(_, hex_component)| hex_component.0 == hovered_hex)
+        .find(|(_, _, hex_component)| hex_component.0 == hovered_hex)
     else {
         warn!("Did not find terrain for hex {hovered_hex:?}");
         ui.label("-");
@@ -174,7 +322,11 @@ fn display_terrain(
         hex_component.0.x, hex_component.0.y
     ));
     ui.label(&terrain.name);
+    ui.label(format!("Elevation: {}", elevation.0));
     ui.label(format!("Movement cost: {}", terrain.movement_cost));
+    ui.label(format!("Attack modifier: {:+}", terrain.attack_modifier));
+    ui.label(format!("Defense modifier: {:+}", terrain.defense_modifier));
+    ui.label(format!("Line of sight: {:?}", terrain.line_of_sight));
 }
 
 pub(super) struct ActivateAbilityCallback(SystemId<Entity>);
@@ -192,6 +344,9 @@ pub(super) fn handle_ui_event(
     mut commands: Commands,
     mut round_state: ResMut<NextState<RoundState>>,
     activate_ability_callback: Local<ActivateAbilityCallback>,
+    mut selected_unit_resource: ResMut<SelectedUnitResource>,
+    mut resupply_event: EventWriter<ResupplyEvent>,
+    mut rest_event: EventWriter<RestEvent>,
 ) {
     for event in events.read() {
         match event {
@@ -199,6 +354,13 @@ pub(super) fn handle_ui_event(
             UiEvent::ActivateAbility(ability_entity) => {
                 commands.run_system_with_input(activate_ability_callback.0, *ability_entity)
             }
+            UiEvent::SelectAttack(index) => selected_unit_resource.set_selected_attack(*index),
+            UiEvent::Resupply(unit) => {
+                resupply_event.send(ResupplyEvent { unit: *unit });
+            }
+            UiEvent::Rest(unit) => {
+                rest_event.send(RestEvent { unit: *unit });
+            }
         }
     }
 }
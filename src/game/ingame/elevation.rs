@@ -0,0 +1,135 @@
+use bevy::prelude::Component;
+use bevy::utils::HashMap;
+use hexx::Hex;
+
+use crate::game::ingame::hex::TerrainHexesQuery;
+use crate::game::ingame::terrain::MovementCost;
+
+/// Height level of a hex, 0 being the ground level
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Elevation(pub u32);
+
+impl Elevation {
+    /// Every level climbed costs one additional action point, going down is free
+    pub fn get_climbing_cost(&self, to: &Elevation) -> u32 {
+        to.0.saturating_sub(self.0)
+    }
+
+    /// Ranged attacks reach one hex further per level they shoot downhill
+    pub fn get_effective_range(&self, range: u32, target: &Elevation) -> u32 {
+        if range <= 1 {
+            return range;
+        }
+        range + self.0.saturating_sub(target.0)
+    }
+
+    /// The maximum range a ranged attack from this elevation can reach, useful to narrow down targets
+    pub fn get_max_effective_range(&self, range: u32) -> u32 {
+        self.get_effective_range(range, &Elevation(0))
+    }
+
+    /// Defenders get one defense point per level they stand above their attacker
+    pub fn get_defense_bonus(&self, attacker: &Elevation) -> i32 {
+        self.0.saturating_sub(attacker.0) as i32
+    }
+}
+
+pub fn get_elevation_at(hexes: &TerrainHexesQuery, hex: Hex) -> Elevation {
+    hexes
+        .iter()
+        .find(|(hex_component, _, _)| hex_component.0 == hex)
+        .map(|(_, _, elevation)| *elevation)
+        .unwrap_or_default()
+}
+
+/// Everything the path finding needs to know about the cost of stepping from one hex to another
+#[derive(Debug, Default, Clone)]
+pub struct MovementCostMap {
+    movement_costs: HashMap<Hex, MovementCost>,
+    elevations: HashMap<Hex, Elevation>,
+    /// added to the cost of every step, e.g. for slowed units
+    extra_step_cost: u32,
+}
+
+impl MovementCostMap {
+    pub fn new(
+        movement_costs: HashMap<Hex, MovementCost>,
+        elevations: HashMap<Hex, Elevation>,
+    ) -> Self {
+        Self {
+            movement_costs,
+            elevations,
+            extra_step_cost: 0,
+        }
+    }
+
+    pub fn with_extra_step_cost(self, extra_step_cost: u32) -> Self {
+        Self {
+            extra_step_cost,
+            ..self
+        }
+    }
+
+    /// Same as [`MovementCost::get_modified_algorithm_cost`] plus the cost to climb from `from` to `to` and the extra step cost
+    pub fn get_modified_algorithm_cost(&self, from: Hex, to: Hex) -> Option<u32> {
+        let terrain_cost = self
+            .movement_costs
+            .get(&to)?
+            .get_modified_algorithm_cost()?;
+
+        let from_elevation = self.elevations.get(&from).copied().unwrap_or_default();
+        let to_elevation = self.elevations.get(&to).copied().unwrap_or_default();
+
+        Some(terrain_cost + from_elevation.get_climbing_cost(&to_elevation) + self.extra_step_cost)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn climbing_costs_one_per_level() {
+        assert_eq!(Elevation(0).get_climbing_cost(&Elevation(2)), 2);
+        assert_eq!(Elevation(2).get_climbing_cost(&Elevation(0)), 0);
+        assert_eq!(Elevation(1).get_climbing_cost(&Elevation(1)), 0);
+    }
+
+    #[test]
+    fn shooting_downhill_increases_range_of_ranged_attacks_only() {
+        assert_eq!(Elevation(2).get_effective_range(3, &Elevation(0)), 5);
+        assert_eq!(Elevation(0).get_effective_range(3, &Elevation(2)), 3);
+        assert_eq!(Elevation(2).get_effective_range(1, &Elevation(0)), 1);
+    }
+
+    #[test]
+    fn defenders_above_attacker_get_defense_bonus() {
+        assert_eq!(Elevation(2).get_defense_bonus(&Elevation(1)), 1);
+        assert_eq!(Elevation(0).get_defense_bonus(&Elevation(1)), 0);
+    }
+
+    #[test]
+    fn step_cost_includes_terrain_and_climbing() {
+        let movement_cost_map = MovementCostMap::new(
+            HashMap::from([
+                (Hex::ZERO, MovementCost::Passable(1)),
+                (Hex::new(1, 0), MovementCost::Passable(2)),
+                (Hex::new(2, 0), MovementCost::Impassable),
+            ]),
+            HashMap::from([(Hex::new(1, 0), Elevation(1))]),
+        );
+
+        assert_eq!(
+            movement_cost_map.get_modified_algorithm_cost(Hex::ZERO, Hex::new(1, 0)),
+            Some(2)
+        );
+        assert_eq!(
+            movement_cost_map.get_modified_algorithm_cost(Hex::new(1, 0), Hex::ZERO),
+            Some(0)
+        );
+        assert_eq!(
+            movement_cost_map.get_modified_algorithm_cost(Hex::new(1, 0), Hex::new(2, 0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::Entity;
+use hexx::Hex;
+
+use crate::game::ingame::unit_status::UnitStatus;
+
+/// Hit modifier if an ally is engaged with the defender from the side opposite of the attacker
+pub const FLANKING_MODIFIER: i32 = 2;
+
+/// How the allies of the attacker surround the defender
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Flanking {
+    /// allies besides the attacker that are engaged with the defender, which gives the attacker advantage
+    pub engaged_allies: usize,
+    /// an engaged ally stands right behind the defender, seen from the attacker
+    pub from_opposite_side: bool,
+}
+
+impl Flanking {
+    pub fn new(
+        attacker: Entity,
+        attacker_hex: Hex,
+        defender_hex: Hex,
+        defender_status: &UnitStatus,
+        get_unit_hex: impl Fn(&Entity) -> Option<Hex>,
+    ) -> Self {
+        let engaged_ally_hexes: Vec<_> = defender_status
+            .get_engaged_with_units()
+            .iter()
+            .filter(|engaged_unit| *engaged_unit != &attacker)
+            .filter_map(get_unit_hex)
+            .collect();
+
+        let opposite_hex = defender_hex + (defender_hex - attacker_hex);
+        let is_adjacent = attacker_hex.unsigned_distance_to(defender_hex) == 1;
+
+        Self {
+            engaged_allies: engaged_ally_hexes.len(),
+            from_opposite_side: is_adjacent && engaged_ally_hexes.contains(&opposite_hex),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::utils::HashMap;
+
+    use super::*;
+
+    const ATTACKER: Entity = Entity::from_raw(0);
+    const OPPOSITE_ALLY: Entity = Entity::from_raw(1);
+    const SIDE_ALLY: Entity = Entity::from_raw(2);
+
+    fn defender_engaged_with(units: &[Entity]) -> UnitStatus {
+        let mut unit_status = UnitStatus::default();
+        units.iter().for_each(|unit| unit_status.engage_with(*unit));
+        unit_status
+    }
+
+    fn get_flanking(defender_status: &UnitStatus) -> Flanking {
+        let unit_hexes = HashMap::from([
+            (ATTACKER, Hex::new(-1, 0)),
+            (OPPOSITE_ALLY, Hex::new(1, 0)),
+            (SIDE_ALLY, Hex::new(0, 1)),
+        ]);
+
+        Flanking::new(
+            ATTACKER,
+            Hex::new(-1, 0),
+            Hex::ZERO,
+            defender_status,
+            |entity| unit_hexes.get(entity).copied(),
+        )
+    }
+
+    #[test]
+    fn attacker_alone_does_not_flank() {
+        let flanking = get_flanking(&defender_engaged_with(&[ATTACKER]));
+
+        assert_eq!(flanking, Flanking::default());
+    }
+
+    #[test]
+    fn allies_engaged_with_the_defender_gang_up() {
+        let flanking = get_flanking(&defender_engaged_with(&[ATTACKER, SIDE_ALLY]));
+
+        assert_eq!(
+            flanking,
+            Flanking {
+                engaged_allies: 1,
+                from_opposite_side: false,
+            }
+        );
+    }
+
+    #[test]
+    fn ally_on_the_opposite_side_flanks() {
+        let flanking = get_flanking(&defender_engaged_with(&[
+            ATTACKER,
+            SIDE_ALLY,
+            OPPOSITE_ALLY,
+        ]));
+
+        assert_eq!(
+            flanking,
+            Flanking {
+                engaged_allies: 2,
+                from_opposite_side: true,
+            }
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use bevy::prelude::{debug, DetectChanges, Event, EventReader, Res, ResMut, Resource};
+use bevy::prelude::{debug, DetectChanges, Event, EventReader, EventWriter, Res, ResMut, Resource};
 use bevy_egui::egui::{Align, ScrollArea, Window};
 use bevy_egui::EguiContexts;
 
+use crate::game::util::dice::DiceRng;
+
 #[derive(Event, Debug, Clone)]
 pub struct LogEvent {
     pub message: String,
@@ -21,6 +23,13 @@ pub(super) fn handle_log_events(
     }
 }
 
+/// Logging the seed allows to reproduce the dice rolls of a game
+pub(super) fn log_dice_seed(dice_rng: Res<DiceRng>, mut log_event: EventWriter<LogEvent>) {
+    log_event.send(LogEvent {
+        message: format!("Dice seed: {}", dice_rng.get_seed()),
+    });
+}
+
 pub(super) fn display_log_events(mut contexts: EguiContexts, log_record: Res<LogRecord>) {
     Window::new("Logs")
         .default_size((250., 150.))
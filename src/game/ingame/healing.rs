@@ -0,0 +1,295 @@
+use bevy::prelude::{
+    debug, in_state, App, Entity, Event, EventReader, EventWriter, IntoSystemConfigs, Plugin,
+    Query, Update,
+};
+
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::charge::TravelledPath;
+use crate::game::ingame::combat::HealthPoints;
+use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::states::in_game_state::InGameState;
+
+/// Health points a unit recovers by resting
+pub const REST_HEALING: usize = 1;
+
+pub struct HealingPlugin;
+
+impl Plugin for HealingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<HealEvent>()
+            .add_event::<RestEvent>()
+            .add_systems(
+                Update,
+                (handle_rest_events, handle_heal_events)
+                    .chain()
+                    .run_if(in_state(InGameState::Playing)),
+            );
+    }
+}
+
+/// Heals the unit by the amount, but never above its max health points
+#[derive(Event, Debug, Clone)]
+pub struct HealEvent {
+    pub unit: Entity,
+    pub amount: usize,
+    /// name of the ability or action that heals the unit
+    pub source: String,
+}
+
+/// The unit skips the rest of its turn to recover [`REST_HEALING`] health points
+#[derive(Event, Debug, Clone)]
+pub struct RestEvent {
+    pub unit: Entity,
+}
+
+/// Units can only rest if they have neither moved nor attacked this turn
+pub fn can_rest(action_points: &ActionPoints, travelled_path: &TravelledPath) -> bool {
+    action_points.left > 0
+        && action_points.attacks_this_round == 0
+        && travelled_path.get_hexes().is_empty()
+}
+
+fn handle_heal_events(
+    mut heal_events: EventReader<HealEvent>,
+    mut units: Query<(&UnitMarker, &mut HealthPoints), UnitFilter>,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    for event in heal_events.read() {
+        let Ok((unit_marker, mut health_points)) = units.get_mut(event.unit) else {
+            continue;
+        };
+        if health_points.left == 0 {
+            debug!("{} is dead and can not be healed", unit_marker.0);
+            continue;
+        }
+
+        let healed = health_points.heal(event.amount);
+        let message = match healed {
+            0 => format!("{} is already at full health", unit_marker.0),
+            _ => format!(
+                "{} recovers {healed} health points from {}",
+                unit_marker.0, event.source
+            ),
+        };
+        log_event.send(LogEvent { message });
+    }
+}
+
+fn handle_rest_events(
+    mut rest_events: EventReader<RestEvent>,
+    mut units: Query<(&UnitMarker, &mut ActionPoints, &TravelledPath), UnitFilter>,
+    mut heal_event: EventWriter<HealEvent>,
+) {
+    for event in rest_events.read() {
+        let Ok((unit_marker, mut action_points, travelled_path)) = units.get_mut(event.unit) else {
+            continue;
+        };
+        if !can_rest(&action_points, travelled_path) {
+            debug!("{} can not rest after acting this turn", unit_marker.0);
+            continue;
+        }
+
+        action_points.left = 0;
+        heal_event.send(HealEvent {
+            unit: event.unit,
+            amount: REST_HEALING,
+            source: "resting".to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{FromWorld, NextState, OnEnter};
+    use hexx::Hex;
+
+    use crate::game::abilities::active_abilities::{
+        ActiveAbility, ActiveAbilityRegistry, ActiveAbilityType, HealInput, HEAL_ACTION_POINT_COST,
+    };
+    use crate::game::abilities::passive_combat_abilities::{
+        PassiveCombatAbility, PassiveCombatAbilityRegistry,
+    };
+    use crate::game::ingame::combat::{CombatConfig, CombatEvent, HealthPoints};
+    use crate::game::ingame::selected_unit::SelectedUnitResource;
+    use crate::game::ingame::team_setup::Team;
+    use crate::game::states::round_state::{round_end_system, ActiveTeam, RoundState};
+    use crate::generate_test_app;
+    use crate::tests::{spawn_unit, AppWrapper};
+
+    use super::*;
+
+    const MAX_ACTION_POINTS: usize = 5;
+
+    #[test]
+    fn healing_is_capped_at_the_max_health_points() {
+        let mut health_points = HealthPoints::new(5);
+        health_points.left = 2;
+
+        assert_eq!(health_points.heal(2), 2);
+        assert_eq!(health_points.heal(3), 1);
+        assert_eq!(health_points.left, 5);
+        assert_eq!(health_points.heal(1), 0);
+    }
+
+    #[test]
+    fn resting_heals_the_unit_and_ends_its_turn() {
+        let mut app = TestApp::build();
+        app.set_health_points_left(app.unit, 2);
+
+        app.send_event(RestEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_health_points_left(app.unit), 2 + REST_HEALING);
+        assert_eq!(app.get_action_points(app.unit).left, 0);
+    }
+
+    #[test]
+    fn units_that_attacked_this_turn_can_not_rest() {
+        let mut app = TestApp::build();
+        app.set_health_points_left(app.unit, 2);
+        app.app
+            .world
+            .get_mut::<ActionPoints>(app.unit)
+            .unwrap()
+            .attacks_this_round = 1;
+
+        app.send_event(RestEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_health_points_left(app.unit), 2);
+        assert_eq!(app.get_action_points(app.unit).left, MAX_ACTION_POINTS);
+    }
+
+    #[test]
+    fn units_that_moved_this_turn_can_not_rest() {
+        let mut app = TestApp::build();
+        app.set_health_points_left(app.unit, 2);
+        app.app
+            .world
+            .get_mut::<TravelledPath>(app.unit)
+            .unwrap()
+            .record_step(Hex::ZERO, Hex::new(1, 0));
+
+        app.send_event(RestEvent { unit: app.unit });
+        app.update();
+
+        assert_eq!(app.get_health_points_left(app.unit), 2);
+        assert_eq!(app.get_action_points(app.unit).left, MAX_ACTION_POINTS);
+    }
+
+    #[test]
+    fn regenerating_units_heal_at_the_end_of_their_turn() {
+        let mut app = TestApp::build();
+        let regeneration = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::Regeneration);
+        for unit in [app.unit, app.ally] {
+            app.app
+                .world
+                .get_mut::<CombatConfig>(unit)
+                .unwrap()
+                .passive_combat_abilities
+                .push(regeneration.clone());
+            app.set_health_points_left(unit, 2);
+        }
+        *app.app.world.get_mut::<Team>(app.ally).unwrap() = Team::Blue;
+
+        app.app
+            .world
+            .resource_mut::<NextState<RoundState>>()
+            .set(RoundState::RoundEnd);
+        app.update();
+        app.update();
+
+        assert_eq!(app.get_health_points_left(app.unit), 3);
+        assert_eq!(app.get_health_points_left(app.ally), 2);
+    }
+
+    #[test]
+    fn healing_an_ally_costs_action_points() {
+        let mut app = TestApp::build();
+        app.set_health_points_left(app.ally, 1);
+
+        app.heal(Hex::new(1, 0));
+
+        assert_eq!(app.get_health_points_left(app.ally), 3);
+        assert_eq!(
+            app.get_action_points(app.unit).left,
+            MAX_ACTION_POINTS - HEAL_ACTION_POINT_COST
+        );
+    }
+
+    #[test]
+    fn allies_at_full_health_are_not_healed_for_action_points() {
+        let mut app = TestApp::build();
+
+        app.heal(Hex::new(1, 0));
+
+        assert_eq!(app.get_health_points_left(app.ally), 5);
+        assert_eq!(app.get_action_points(app.unit).left, MAX_ACTION_POINTS);
+    }
+
+    generate_test_app!(unit: Entity, ally: Entity);
+
+    impl TestApp {
+        fn build() -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.init_state::<InGameState>();
+            app.world
+                .resource_mut::<NextState<InGameState>>()
+                .set(InGameState::Playing);
+            app.add_event::<LogEvent>();
+            app.add_event::<CombatEvent>();
+            app.init_resource::<ActiveTeam>();
+            app.init_resource::<SelectedUnitResource>();
+            app.add_plugins(HealingPlugin);
+            app.add_systems(OnEnter(RoundState::RoundEnd), round_end_system);
+
+            let [unit, ally] = [Hex::ZERO, Hex::new(1, 0)].map(|hex| {
+                let unit = spawn_unit(&mut app, Team::Red, hex);
+                app.world
+                    .entity_mut(unit)
+                    .insert(ActionPoints::new(MAX_ACTION_POINTS, 1, 1));
+                unit
+            });
+
+            app.update();
+
+            TestApp { app, unit, ally }
+        }
+
+        fn heal(&mut self, target_hex: Hex) {
+            let ActiveAbility::Heal { heal_system } =
+                ActiveAbilityRegistry::from_world(&mut self.app.world)
+                    .get_registered_ability(&ActiveAbilityType::Heal)
+            else {
+                panic!("Heal is not a heal ability");
+            };
+            self.app
+                .world
+                .run_system_with_input(
+                    heal_system,
+                    HealInput {
+                        healer: self.unit,
+                        target_hex,
+                    },
+                )
+                .unwrap();
+            self.update();
+        }
+
+        fn set_health_points_left(&mut self, unit: Entity, left: usize) {
+            self.app.world.get_mut::<HealthPoints>(unit).unwrap().left = left;
+        }
+
+        fn get_health_points_left(&self, unit: Entity) -> usize {
+            self.app.world.get::<HealthPoints>(unit).unwrap().left
+        }
+
+        fn get_action_points(&self, unit: Entity) -> &ActionPoints {
+            self.app.world.get::<ActionPoints>(unit).unwrap()
+        }
+    }
+}
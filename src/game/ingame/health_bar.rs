@@ -1,17 +1,23 @@
 use bevy::prelude::{
     debug, default, info, warn, Added, Assets, BuildChildren, Changed, Children, Color,
     ColorMaterial, ColorMesh2dBundle, Commands, Component, DespawnRecursiveExt, Entity, FromWorld,
-    Handle, Mesh, Query, Rectangle, Res, ResMut, Resource, SpriteBundle, Transform, Vec3, With,
-    Without, World,
+    Handle, Mesh, Query, Rectangle, Res, ResMut, Resource, SpriteBundle, Time, Timer, TimerMode,
+    Transform, Vec3, With, Without, World,
 };
 use bevy::sprite::Mesh2dHandle;
+use bevy::utils::HashMap;
+use enum_iterator::all;
 
 use crate::game::ingame::combat::HealthPoints;
 use crate::game::ingame::hex::HEX_RADIUS;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
+use crate::game::ingame::status_effects::{StatusEffectType, StatusEffects};
 use crate::game::ingame::z_ordering::ZOrdering;
 
 const MAX_Y_SCALE: f32 = 0.9;
+/// relative to the health bar
+const STATUS_EFFECT_BADGE_SIZE: f32 = 0.15;
+const HEALTH_BAR_ANIMATION_SECONDS: f32 = 0.6;
 
 #[derive(Resource, Debug)]
 pub struct HealthBarResources {
@@ -19,6 +25,9 @@ pub struct HealthBarResources {
     pub background_color: Handle<ColorMaterial>,
     pub green_color: Handle<ColorMaterial>,
     pub red_color: Handle<ColorMaterial>,
+    /// shown on the health indicator while it grows after healing
+    pub heal_color: Handle<ColorMaterial>,
+    pub status_effect_colors: HashMap<StatusEffectType, Handle<ColorMaterial>>,
 }
 
 #[derive(Component, Debug)]
@@ -29,6 +38,17 @@ pub struct HealthBarForEntity {
 #[derive(Component)]
 pub struct HealthIndicatorBarMarker;
 
+#[derive(Component)]
+pub struct StatusEffectBadgeMarker;
+
+/// Grows or shrinks the health indicator to the new health points over time
+#[derive(Component, Debug)]
+pub struct HealthBarAnimation {
+    from_scale: f32,
+    to_scale: f32,
+    timer: Timer,
+}
+
 impl FromWorld for HealthBarResources {
     fn from_world(world: &mut World) -> Self {
         let mut meshes = world.resource_mut::<Assets<Mesh>>();
@@ -37,12 +57,21 @@ impl FromWorld for HealthBarResources {
         let background_color = color_materials.add(ColorMaterial::from(Color::BLACK));
         let green_color = color_materials.add(ColorMaterial::from(Color::GREEN));
         let red_color = color_materials.add(ColorMaterial::from(Color::RED));
+        let heal_color = color_materials.add(ColorMaterial::from(Color::rgb(0.6, 1., 0.6)));
+        let status_effect_colors = all::<StatusEffectType>()
+            .map(|effect_type| {
+                let color = ColorMaterial::from(effect_type.get_badge_color());
+                (effect_type, color_materials.add(color))
+            })
+            .collect();
 
         Self {
             quad_mesh,
             background_color,
             green_color,
             red_color,
+            heal_color,
+            status_effect_colors,
         }
     }
 }
@@ -128,12 +157,14 @@ pub(super) fn update_health_bar_positions(
 }
 
 pub(super) fn update_health_bar_size(
+    mut commands: Commands,
     entities_with_health: Query<
         &HealthPoints,
         (Without<HealthBarForEntity>, Changed<HealthPoints>),
     >,
     health_bar_entities: Query<(&HealthBarForEntity, &Children)>,
-    mut health_indicator_transforms: Query<&mut Transform, With<HealthIndicatorBarMarker>>,
+    health_indicator_transforms: Query<&Transform, With<HealthIndicatorBarMarker>>,
+    health_bar_resources: Res<HealthBarResources>,
 ) {
     if entities_with_health.is_empty() {
         return;
@@ -154,11 +185,84 @@ pub(super) fn update_health_bar_size(
             continue;
         };
 
-        let mut transform = health_indicator_transforms
-            .get_mut(*health_indicator_child)
+        let transform = health_indicator_transforms
+            .get(*health_indicator_child)
             .unwrap();
 
+        let from_scale = transform.scale.x;
+        let to_scale = MAX_Y_SCALE * health_points.left as f32 / health_points.get_max() as f32;
+        let mut health_indicator = commands.entity(*health_indicator_child);
+        if to_scale > from_scale {
+            health_indicator.insert(health_bar_resources.heal_color.clone());
+        }
+        health_indicator.insert(HealthBarAnimation {
+            from_scale,
+            to_scale,
+            timer: Timer::from_seconds(HEALTH_BAR_ANIMATION_SECONDS, TimerMode::Once),
+        });
+    }
+}
+
+pub(super) fn animate_health_bars(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut health_indicators: Query<(Entity, &mut Transform, &mut HealthBarAnimation)>,
+    health_bar_resources: Res<HealthBarResources>,
+) {
+    for (entity, mut transform, mut animation) in &mut health_indicators {
+        animation.timer.tick(time.delta());
+        let progress = animation.timer.fraction();
         transform.scale.x =
-            MAX_Y_SCALE * health_points.left as f32 / health_points.get_max() as f32;
+            animation.from_scale + (animation.to_scale - animation.from_scale) * progress;
+
+        if animation.timer.finished() {
+            commands
+                .entity(entity)
+                .remove::<HealthBarAnimation>()
+                .insert(health_bar_resources.green_color.clone());
+        }
+    }
+}
+
+/// Shows one colored badge per status effect above the health bar
+pub(super) fn update_status_effect_badges(
+    mut commands: Commands,
+    units: Query<&StatusEffects, Changed<StatusEffects>>,
+    health_bar_entities: Query<(Entity, &HealthBarForEntity, Option<&Children>)>,
+    badges: Query<(), With<StatusEffectBadgeMarker>>,
+    health_bar_resources: Res<HealthBarResources>,
+) {
+    for (health_bar_entity, for_entity, children) in &health_bar_entities {
+        let Ok(status_effects) = units.get(for_entity.entity) else {
+            continue;
+        };
+
+        children
+            .into_iter()
+            .flatten()
+            .filter(|child| badges.contains(**child))
+            .for_each(|badge| commands.entity(*badge).despawn_recursive());
+
+        commands
+            .entity(health_bar_entity)
+            .with_children(|child_builder| {
+                for (index, status_effect) in status_effects.get_effects().iter().enumerate() {
+                    let x = -0.5 + STATUS_EFFECT_BADGE_SIZE * (index as f32 + 0.5);
+                    child_builder
+                        .spawn(ColorMesh2dBundle {
+                            mesh: health_bar_resources.quad_mesh.clone(),
+                            material: health_bar_resources.status_effect_colors
+                                [&status_effect.effect_type]
+                                .clone(),
+                            transform: Transform::from_xyz(x, 1., 3.).with_scale(Vec3::new(
+                                STATUS_EFFECT_BADGE_SIZE * 0.8,
+                                0.8,
+                                1.,
+                            )),
+                            ..default()
+                        })
+                        .insert(StatusEffectBadgeMarker);
+                }
+            });
     }
 }
@@ -1,15 +1,19 @@
 use bevy::prelude::{
-    default, Assets, Color, ColorMaterial, ColorMesh2dBundle, Commands, Component, Handle, Mesh,
-    ResMut, Resource, Transform, Vec2, Vec3,
+    default, error, warn, AssetServer, Assets, Color, ColorMaterial, ColorMesh2dBundle, Commands,
+    Component, Handle, Mesh, Query, Res, ResMut, Resource, Transform, Vec2, Vec3, With,
 };
 use bevy::render::mesh::{Indices, PrimitiveTopology};
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::utils::HashMap;
 use hexx::{Hex, HexLayout, PlaneMeshBuilder};
 
+use crate::game::asset_loading::map_assets::MapAssetsResource;
+use crate::game::asset_loading::terrain_assets::{TerrainAssetsResource, TerrainDisplay};
+use crate::game::ingame::elevation::Elevation;
 use crate::game::ingame::selected_unit::SelectedUnitHexMarker;
-use crate::game::ingame::terrain::{MovementCost, Terrain};
+use crate::game::ingame::terrain::Terrain;
 use crate::game::ingame::z_ordering::ZOrdering;
+use crate::game::states::in_game_state::PickedMapResource;
 
 pub const HEX_RADIUS: f32 = 50.;
 
@@ -22,16 +26,25 @@ pub struct HexOverlayMarker;
 #[derive(Component)]
 pub struct HexComponent(pub Hex);
 
+pub type TerrainHexesQuery<'world, 'state, 'a> =
+    Query<'world, 'state, (&'a HexComponent, &'a Terrain, &'a Elevation), With<HexMarker>>;
+
 #[derive(Resource)]
 pub struct HexResources {
     pub hex_layout: HexLayout,
     pub not_reachable_overlay_color: Handle<ColorMaterial>,
+    /// marks the hexes an area of effect attack would hit
+    pub area_of_effect_overlay_color: Handle<ColorMaterial>,
 }
 
 pub fn setup_hex_grid(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>,
+    map_assets_resource: Res<MapAssetsResource>,
+    terrain_assets_resource: Res<TerrainAssetsResource>,
+    picked_map: Res<PickedMapResource>,
 ) {
     let hex_layout = HexLayout {
         hex_size: Vec2::splat(HEX_RADIUS),
@@ -41,47 +54,58 @@ pub fn setup_hex_grid(
     let mesh = meshes.add(hexagonal_plane(&hex_layout));
 
     let not_reachable_overlay_color = materials.add(ColorMaterial::from(Color::BLACK.with_a(0.7)));
+    let area_of_effect_overlay_color =
+        materials.add(ColorMaterial::from(Color::ORANGE_RED.with_a(0.5)));
+
+    let terrain_map = build_terrain_map(&terrain_assets_resource, &mut materials, &asset_server);
+
+    // without the map the hex resources are still needed by the other systems
+    let (map_name, map_hexes) = match map_assets_resource.get_map(&picked_map.0) {
+        Some(map) => (map.name.as_str(), map.hexes.as_slice()),
+        None => {
+            error!("The picked map {:?} does not exist", picked_map.0);
+            ("", [].as_slice())
+        }
+    };
 
-    let terrain_map = build_terrain_map(&mut materials);
-
-    Hex::ZERO
-        .spiral_range(0..5)
-        .enumerate()
-        .for_each(|(i, hex_coord)| {
-            let terrain_key = match i % 4 {
-                3 => "forest",
-                2 => "water",
-                _ => "plains",
-            };
-            let (terrain, color) = &terrain_map[terrain_key];
-
-            let world_coord = hex_layout.hex_to_world_pos(hex_coord);
-            commands
-                .spawn(ColorMesh2dBundle {
-                    mesh: mesh.clone().into(),
-                    material: color.clone(),
-                    transform: Transform::from_xyz(world_coord.x, world_coord.y, ZOrdering::HEX)
-                        .with_scale(Vec3::splat(0.9)),
-                    ..default()
-                })
-                .insert(HexComponent(hex_coord))
-                .insert(terrain.clone())
-                .insert(HexMarker);
-            commands
-                .spawn(HexOverlayMarker)
-                .insert(ColorMesh2dBundle {
-                    mesh: mesh.clone().into(),
-                    material: not_reachable_overlay_color.clone(),
-                    transform: Transform::from_xyz(
-                        world_coord.x,
-                        world_coord.y,
-                        ZOrdering::HEX_OVERLAY,
-                    )
+    for map_hex in map_hexes {
+        let Some((terrain, color)) = terrain_map.get(&map_hex.terrain) else {
+            warn!(
+                "Unknown terrain '{}' in map '{}', skipping hex ({},{})",
+                map_hex.terrain, map_name, map_hex.x, map_hex.y
+            );
+            continue;
+        };
+
+        let hex_coord = map_hex.get_hex();
+        let world_coord = hex_layout.hex_to_world_pos(hex_coord);
+        commands
+            .spawn(ColorMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: color.clone(),
+                transform: Transform::from_xyz(world_coord.x, world_coord.y, ZOrdering::HEX)
                     .with_scale(Vec3::splat(0.9)),
-                    ..default()
-                })
-                .insert(HexComponent(hex_coord));
-        });
+                ..default()
+            })
+            .insert(HexComponent(hex_coord))
+            .insert(terrain.clone())
+            .insert(Elevation(map_hex.elevation))
+            .insert(HexMarker);
+        commands
+            .spawn(HexOverlayMarker)
+            .insert(ColorMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: not_reachable_overlay_color.clone(),
+                transform: Transform::from_xyz(
+                    world_coord.x,
+                    world_coord.y,
+                    ZOrdering::HEX_OVERLAY,
+                )
+                .with_scale(Vec3::splat(0.9)),
+                ..default()
+            })
+            .insert(HexComponent(hex_coord));
+    }
 
     commands
         .spawn(ColorMesh2dBundle {
@@ -99,44 +123,34 @@ pub fn setup_hex_grid(
     commands.insert_resource(HexResources {
         hex_layout,
         not_reachable_overlay_color,
+        area_of_effect_overlay_color,
     });
 }
 
-fn build_terrain_map<'a>(
+fn build_terrain_map(
+    terrain_assets_resource: &TerrainAssetsResource,
     materials: &mut Assets<ColorMaterial>,
-) -> HashMap<&'a str, (Terrain, Handle<ColorMaterial>)> {
-    HashMap::from([
-        (
-            "plains",
-            (
-                Terrain {
-                    name: "Plains".to_string(),
-                    movement_cost: MovementCost::Passable(1),
-                },
-                materials.add(ColorMaterial::from(Color::YELLOW_GREEN)),
-            ),
-        ),
-        (
-            "forest",
-            (
-                Terrain {
-                    name: "Forest".to_string(),
-                    movement_cost: MovementCost::Passable(2),
-                },
-                materials.add(ColorMaterial::from(Color::DARK_GREEN)),
-            ),
-        ),
-        (
-            "water",
+    asset_server: &AssetServer,
+) -> HashMap<String, (Terrain, Handle<ColorMaterial>)> {
+    terrain_assets_resource
+        .iter()
+        .map(|(terrain_key, terrain_definition)| {
+            let color_material = match &terrain_definition.display {
+                TerrainDisplay::Color(red, green, blue) => {
+                    ColorMaterial::from(Color::rgb(*red, *green, *blue))
+                }
+                TerrainDisplay::Texture(path) => ColorMaterial::from(asset_server.load(path)),
+            };
+
             (
-                Terrain {
-                    name: "Water".to_string(),
-                    movement_cost: MovementCost::Impassable,
-                },
-                materials.add(ColorMaterial::from(Color::BLUE)),
-            ),
-        ),
-    ])
+                terrain_key.clone(),
+                (
+                    terrain_definition.get_terrain(),
+                    materials.add(color_material),
+                ),
+            )
+        })
+        .collect()
 }
 
 fn hexagonal_plane(hex_layout: &HexLayout) -> Mesh {
@@ -2,9 +2,11 @@ use bevy::input::ButtonInput;
 use bevy::prelude::{debug, warn, Entity, EventWriter, MouseButton, Query, Res, ResMut};
 
 use crate::game::ingame::action_points::ActionPoints;
-use crate::game::ingame::combat::{AttackOrDefault, CombatConfig, CombatEvent};
-use crate::game::ingame::hex::HexComponent;
+use crate::game::ingame::area_of_effect::AreaAttackEvent;
+use crate::game::ingame::combat::{is_in_attack_range, AttackOrDefault, CombatConfig, CombatEvent};
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
 use crate::game::ingame::hovered_hex::{HoveredHex, HoveredUnitResource};
+use crate::game::ingame::line_of_sight::LineOfSightMap;
 use crate::game::ingame::move_unit::MoveUnitEvent;
 use crate::game::ingame::path::CurrentPath;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
@@ -20,10 +22,12 @@ pub(super) fn handle_selected_unit_input(
         (Entity, &HexComponent, &Team, &ActionPoints, &CombatConfig),
         UnitFilter,
     >,
+    hexes: TerrainHexesQuery,
     active_team: Res<ActiveTeam>,
     hovered_hex: Res<HoveredHex>,
     current_path: Res<CurrentPath>,
     mut combat_event: EventWriter<CombatEvent>,
+    mut area_attack_event: EventWriter<AreaAttackEvent>,
     mut move_event: EventWriter<MoveUnitEvent>,
 ) {
     if !buttons.just_pressed(MouseButton::Left) {
@@ -50,15 +54,38 @@ pub(super) fn handle_selected_unit_input(
         else {
             return;
         };
+        let Some(attack) = combat_config.get_attack(selected_unit_resource.selected_attack())
+        else {
+            return;
+        };
 
-        let distance = hovered_entity_hex
-            .0
-            .unsigned_distance_to(selected_unit_hex.0);
-        if distance <= combat_config.range && action_points.can_still_attack_this_turn() {
+        let line_of_sight_map = LineOfSightMap::from_terrain(
+            &hexes,
+            units
+                .iter()
+                .map(|(_, hex_component, _, _, _)| hex_component.0),
+        );
+        let is_in_attack_range = is_in_attack_range(
+            &hexes,
+            &line_of_sight_map,
+            attack.range,
+            selected_unit_hex.0,
+            hovered_entity_hex.0,
+        );
+        if !is_in_attack_range || !action_points.can_still_attack_with(&attack) {
+            return;
+        }
+        if attack.area.is_some() {
+            area_attack_event.send(AreaAttackEvent {
+                attacker: selected_unit,
+                attack,
+                target_hex: hovered_entity_hex.0,
+            });
+        } else {
             combat_event.send(CombatEvent {
                 attacker: selected_unit,
                 defender: hovered_entity,
-                attack: AttackOrDefault::Attack(combat_config.get_default_attack()),
+                attack: AttackOrDefault::Attack(attack),
             });
         }
         return;
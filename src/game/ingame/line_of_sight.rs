@@ -0,0 +1,138 @@
+use bevy::utils::HashMap;
+use hexx::Hex;
+
+use crate::game::ingame::hex::TerrainHexesQuery;
+use crate::game::ingame::terrain::Terrain;
+
+/// Hit modifier for attacks whose line of sight is [`LineOfSight::Obscured`]
+pub const OBSCURED_LINE_OF_SIGHT_MODIFIER: i32 = 2;
+
+/// How a hex affects sight lines passing through it, and the resulting sight between two hexes
+#[derive(
+    serde::Deserialize,
+    serde::Serialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+pub enum LineOfSight {
+    #[default]
+    Clear,
+    Obscured,
+    Blocked,
+}
+
+/// Units obscure the sight through the hex they are standing on
+#[derive(Debug, Default)]
+pub struct LineOfSightMap(HashMap<Hex, LineOfSight>);
+
+impl LineOfSightMap {
+    pub fn new<'a>(
+        terrain_hexes: impl Iterator<Item = (Hex, &'a Terrain)>,
+        unit_hexes: impl Iterator<Item = Hex>,
+    ) -> Self {
+        let mut line_of_sight_map: HashMap<_, _> = terrain_hexes
+            .map(|(hex, terrain)| (hex, terrain.line_of_sight))
+            .collect();
+
+        for unit_hex in unit_hexes {
+            let line_of_sight = line_of_sight_map.entry(unit_hex).or_default();
+            *line_of_sight = (*line_of_sight).max(LineOfSight::Obscured);
+        }
+
+        Self(line_of_sight_map)
+    }
+
+    /// The map of all terrain hexes with the units standing on the given hexes
+    pub fn from_terrain(hexes: &TerrainHexesQuery, unit_hexes: impl Iterator<Item = Hex>) -> Self {
+        Self::new(
+            hexes
+                .iter()
+                .map(|(hex_component, terrain, _)| (hex_component.0, terrain)),
+            unit_hexes,
+        )
+    }
+
+    /// The start and end hex of the line never block the sight, so adjacent hexes can always see each other
+    pub fn get_line_of_sight(&self, from: Hex, to: Hex) -> LineOfSight {
+        from.line_to(to)
+            .filter(|hex| hex != &from && hex != &to)
+            .map(|hex| self.0.get(&hex).copied().unwrap_or_default())
+            .max()
+            .unwrap_or_default()
+    }
+
+    pub fn can_see(&self, from: Hex, to: Hex) -> bool {
+        self.get_line_of_sight(from, to) != LineOfSight::Blocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::ingame::terrain::MovementCost;
+
+    use super::*;
+
+    fn terrain(line_of_sight: LineOfSight) -> Terrain {
+        Terrain {
+            name: format!("{line_of_sight:?}"),
+            movement_cost: MovementCost::Passable(1),
+            attack_modifier: 0,
+            defense_modifier: 0,
+            line_of_sight,
+            stops_charges: false,
+            attack_disadvantage: false,
+        }
+    }
+
+    #[test]
+    fn sight_is_clear_over_open_terrain() {
+        let clear = terrain(LineOfSight::Clear);
+        let map = LineOfSightMap::new(Hex::ZERO.range(3).map(|hex| (hex, &clear)), [].into_iter());
+
+        assert_eq!(
+            map.get_line_of_sight(Hex::ZERO, Hex::new(3, 0)),
+            LineOfSight::Clear
+        );
+    }
+
+    #[test]
+    fn blocking_terrain_between_blocks_the_sight() {
+        let clear = terrain(LineOfSight::Clear);
+        let blocking = terrain(LineOfSight::Blocked);
+        let map = LineOfSightMap::new(
+            Hex::ZERO.range(3).map(|hex| {
+                if hex == Hex::new(1, 0) {
+                    (hex, &blocking)
+                } else {
+                    (hex, &clear)
+                }
+            }),
+            [].into_iter(),
+        );
+
+        assert!(!map.can_see(Hex::ZERO, Hex::new(2, 0)));
+        assert!(map.can_see(Hex::ZERO, Hex::new(1, 0)));
+        assert!(map.can_see(Hex::ZERO, Hex::new(0, 2)));
+    }
+
+    #[test]
+    fn units_between_obscure_the_sight() {
+        let clear = terrain(LineOfSight::Clear);
+        let map = LineOfSightMap::new(
+            Hex::ZERO.range(3).map(|hex| (hex, &clear)),
+            [Hex::ZERO, Hex::new(1, 0), Hex::new(2, 0)].into_iter(),
+        );
+
+        assert_eq!(
+            map.get_line_of_sight(Hex::ZERO, Hex::new(2, 0)),
+            LineOfSight::Obscured
+        );
+        assert!(map.can_see(Hex::ZERO, Hex::new(2, 0)));
+    }
+}
@@ -8,14 +8,25 @@ use crate::game::ingame::action_points::reset_action_points;
 use crate::game::ingame::active_abilities_systems::{
     handle_activated_active_ability, unset_activated_ability,
 };
-use crate::game::ingame::combat::{CombatEvent, CombatPlugin};
+use crate::game::ingame::ammo::AmmoPlugin;
+use crate::game::ingame::area_of_effect::AreaOfEffectPlugin;
+use crate::game::ingame::combat::{handle_combat_event, CombatEvent, CombatPlugin};
+use crate::game::ingame::combat_preview::{
+    show_combat_preview_tooltip, update_combat_preview, CombatPreviewResource,
+};
+use crate::game::ingame::combat_ruleset::CombatRuleset;
 use crate::game::ingame::egui::{handle_ui_event, ui_system, UiEvent};
-use crate::game::ingame::game_log::{display_log_events, handle_log_events, LogEvent, LogRecord};
+use crate::game::ingame::game_log::{
+    display_log_events, handle_log_events, log_dice_seed, LogEvent, LogRecord,
+};
+use crate::game::ingame::healing::HealingPlugin;
 use crate::game::ingame::health_bar::{
-    add_health_bars, update_health_bar_positions, update_health_bar_size, HealthBarResources,
+    add_health_bars, animate_health_bars, update_health_bar_positions, update_health_bar_size,
+    update_status_effect_badges, HealthBarResources,
 };
 use crate::game::ingame::hovered_hex::{update_hovered_hex, HoveredHex, HoveredUnitResource};
 use crate::game::ingame::input_system::{handle_selected_unit_input, update_hovered_unit};
+use crate::game::ingame::morale::MoralePlugin;
 use crate::game::ingame::move_unit::MoveUnitsPlugin;
 use crate::game::ingame::path::{compute_current_path, despawn_old_path, CurrentPath};
 use crate::game::ingame::post_update_systems::update_transform_from_hex;
@@ -28,20 +39,35 @@ use crate::game::menu::menu_ui;
 use crate::game::states::game_state::GameState;
 use crate::game::states::in_game_state::InGameState;
 use crate::game::states::round_state::{round_end_system, ActiveTeam, RoundState};
+use crate::game::util::dice::{insert_dice_rng, DiceSeed};
 
 pub mod action_points;
 mod active_abilities_systems;
+pub mod ammo;
+pub mod area_of_effect;
+pub mod attack_roll;
+pub mod charge;
 pub mod combat;
+pub mod combat_preview;
+pub mod combat_ruleset;
+pub mod damage_type;
 mod egui;
+pub mod elevation;
+pub mod flanking;
 pub mod game_log;
+pub mod healing;
 mod health_bar;
 pub mod hex;
 pub mod hovered_hex;
 mod input_system;
+pub mod line_of_sight;
+pub mod morale;
 mod move_unit;
 mod path;
 pub mod post_update_systems;
 pub mod selected_unit;
+pub mod stat_modifiers;
+pub mod status_effects;
 pub mod team_setup;
 pub mod terrain;
 pub mod unit;
@@ -52,7 +78,9 @@ pub struct IngameLogicPlugin;
 
 impl Plugin for IngameLogicPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((MoveUnitsPlugin, CombatPlugin))
+        app.add_plugins((MoveUnitsPlugin, CombatPlugin, MoralePlugin, AmmoPlugin))
+            .add_plugins(AreaOfEffectPlugin)
+            .add_plugins(HealingPlugin)
             .init_state::<RoundState>()
             .add_event::<LogEvent>()
             .add_event::<CombatEvent>()
@@ -64,6 +92,9 @@ impl Plugin for IngameLogicPlugin {
             .init_resource::<HealthBarResources>()
             .init_resource::<LogRecord>()
             .init_resource::<CurrentPath>()
+            .init_resource::<DiceSeed>()
+            .init_resource::<CombatPreviewResource>()
+            .init_resource::<CombatRuleset>()
             .add_systems(
                 PreUpdate,
                 update_hovered_hex.run_if(
@@ -71,6 +102,7 @@ impl Plugin for IngameLogicPlugin {
                 ),
             )
             .add_systems(Update, menu_ui.run_if(in_state(GameState::Loading)))
+            .add_systems(OnEnter(GameState::InGame), insert_dice_rng)
             .add_systems(
                 PreUpdate,
                 (update_transform_from_hex, update_reachable_hexes_cache)
@@ -79,9 +111,16 @@ impl Plugin for IngameLogicPlugin {
             .add_systems(
                 Update,
                 (
-                    ui_system,
+                    (
+                        update_combat_preview,
+                        ui_system,
+                        show_combat_preview_tooltip,
+                    )
+                        .chain()
+                        .after(update_hovered_unit),
                     display_log_events,
                     add_health_bars,
+                    animate_health_bars,
                     reset_selected_unit,
                     compute_current_path,
                     despawn_old_path,
@@ -101,16 +140,21 @@ impl Plugin for IngameLogicPlugin {
                     update_hex_overlay,
                     update_health_bar_positions,
                     update_health_bar_size,
+                    update_status_effect_badges,
                     handle_log_events,
-                    update_engagement,
+                    update_engagement.before(handle_combat_event),
                 )
                     .run_if(in_state(InGameState::Playing)),
             )
+            .add_systems(OnEnter(InGameState::Playing), log_dice_seed)
             .add_systems(Last, handle_ui_event.run_if(in_state(InGameState::Playing)))
             .add_systems(OnExit(RoundState::ActivateAbility), unset_activated_ability)
             .add_systems(
                 OnEnter(RoundState::RoundEnd),
-                (round_end_system, reset_action_points),
+                (
+                    round_end_system,
+                    reset_action_points.after(round_end_system),
+                ),
             );
     }
 }
@@ -0,0 +1,395 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::{
+    debug, in_state, App, Changed, Component, Condition, Entity, Event, EventReader, EventWriter,
+    IntoSystemConfigs, OnEnter, Plugin, PostUpdate, Query, Res, ResMut, Update, With,
+};
+use bevy::utils::HashSet;
+use hexx::Hex;
+
+use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::combat::HealthPoints;
+use crate::game::ingame::game_log::LogEvent;
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::move_unit::MoveUnitEvent;
+use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::terrain::MovementCost;
+use crate::game::ingame::unit::{UnitFilter, UnitMarker};
+use crate::game::states::in_game_state::InGameState;
+use crate::game::states::round_state::{round_end_system, ActiveTeam, RoundState};
+use crate::game::util::dice::{Dice, DiceRng};
+
+/// Units that fail a morale check by more than this start routing right away
+const ROUTING_MARGIN: usize = 5;
+/// Shaken and routing units within this range of an allied [`Commander`] try to rally at the start of their turn
+const COMMANDER_RALLY_RANGE: u32 = 2;
+
+pub struct MoralePlugin;
+
+impl Plugin for MoralePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<MoraleCheckEvent>()
+            .add_systems(
+                PostUpdate,
+                (check_morale_when_wounded, handle_morale_checks)
+                    .chain()
+                    .run_if(in_state(InGameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                flee_routing_units
+                    .run_if(in_state(RoundState::Input).and_then(in_state(InGameState::Playing))),
+            )
+            .add_systems(
+                OnEnter(RoundState::RoundEnd),
+                (rally_around_commanders, mark_routing_units_to_flee).after(round_end_system),
+            );
+    }
+}
+
+#[derive(Component, Debug)]
+pub struct Morale {
+    /// A morale check succeeds if a D20 roll does not exceed this value
+    value: usize,
+    pub state: MoraleState,
+    /// the unit already checked its morale for dropping below half health points
+    checked_when_wounded: bool,
+    /// the unit was routing at the start of its team's turn and still has to flee
+    flees_this_turn: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MoraleState {
+    #[default]
+    Steady,
+    /// the unit attacks with disadvantage
+    Shaken,
+    /// the unit flees from enemies at the start of its team's turn
+    Routing,
+}
+
+/// Units rally nearby shaken and routing allies at the start of their turn
+#[derive(Component, Debug)]
+pub struct Commander;
+
+#[derive(Event, Debug, Clone)]
+pub struct MoraleCheckEvent {
+    pub unit: Entity,
+    pub reason: MoraleCheckReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoraleCheckReason {
+    Wounded,
+    AllyDied,
+    Flanked,
+    /// succeeding the check makes the unit steady again
+    Rally,
+}
+
+impl Morale {
+    pub fn new(value: usize) -> Self {
+        Self {
+            value,
+            state: MoraleState::Steady,
+            checked_when_wounded: false,
+            flees_this_turn: false,
+        }
+    }
+
+    pub fn get_value(&self) -> usize {
+        self.value
+    }
+
+    pub fn is_shaken(&self) -> bool {
+        self.state == MoraleState::Shaken
+    }
+
+    pub fn rally(&mut self) {
+        self.state = MoraleState::Steady;
+        self.flees_this_turn = false;
+    }
+
+    /// Updates the state according to the outcome of the check, returns whether the check succeeded
+    fn resolve_check(&mut self, reason: &MoraleCheckReason, dice_roll: u8) -> bool {
+        let dice_roll = dice_roll as usize;
+        let succeeded = dice_roll <= self.value;
+
+        match (reason, succeeded) {
+            (MoraleCheckReason::Rally, true) => self.rally(),
+            (MoraleCheckReason::Rally, false) | (_, true) => {}
+            (_, false) => {
+                self.state = match self.state {
+                    MoraleState::Steady if dice_roll <= self.value + ROUTING_MARGIN => {
+                        MoraleState::Shaken
+                    }
+                    _ => MoraleState::Routing,
+                };
+            }
+        }
+
+        succeeded
+    }
+}
+
+impl Display for MoraleState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoraleState::Steady => write!(f, "steady"),
+            MoraleState::Shaken => write!(f, "shaken"),
+            MoraleState::Routing => write!(f, "routing"),
+        }
+    }
+}
+
+impl Display for MoraleCheckReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoraleCheckReason::Wounded => write!(f, "dropping below half health points"),
+            MoraleCheckReason::AllyDied => write!(f, "an adjacent ally died"),
+            MoraleCheckReason::Flanked => write!(f, "being flanked"),
+            MoraleCheckReason::Rally => write!(f, "being rallied by a commander"),
+        }
+    }
+}
+
+fn check_morale_when_wounded(
+    mut units: Query<(Entity, &HealthPoints, &mut Morale), Changed<HealthPoints>>,
+    mut morale_check_event: EventWriter<MoraleCheckEvent>,
+) {
+    for (entity, health_points, mut morale) in &mut units {
+        let is_wounded = health_points.left > 0 && health_points.left * 2 < health_points.get_max();
+        if is_wounded && !morale.checked_when_wounded {
+            morale.checked_when_wounded = true;
+            morale_check_event.send(MoraleCheckEvent {
+                unit: entity,
+                reason: MoraleCheckReason::Wounded,
+            });
+        }
+    }
+}
+
+fn handle_morale_checks(
+    mut morale_check_events: EventReader<MoraleCheckEvent>,
+    mut units: Query<(&mut Morale, &UnitMarker)>,
+    mut dice_rng: ResMut<DiceRng>,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    for morale_check_event in morale_check_events.read() {
+        let Ok((mut morale, unit_marker)) = units.get_mut(morale_check_event.unit) else {
+            debug!("Skipping morale check of removed unit {morale_check_event:?}");
+            continue;
+        };
+
+        let dice_roll = Dice::<20>::roll(&mut dice_rng);
+        let state_before = morale.state;
+        let succeeded = morale.resolve_check(&morale_check_event.reason, dice_roll);
+
+        let outcome = match (succeeded, morale.state) {
+            (true, MoraleState::Steady) if state_before != MoraleState::Steady => {
+                "rallies".to_string()
+            }
+            (true, _) => "holds".to_string(),
+            (false, state) => format!("is {state}"),
+        };
+        log_event.send(LogEvent {
+            message: format!(
+                "{} checks morale after {} ({dice_roll}/{}) and {outcome}",
+                unit_marker.0, morale_check_event.reason, morale.value
+            ),
+        });
+    }
+}
+
+fn rally_around_commanders(
+    active_team: Res<ActiveTeam>,
+    units: Query<(Entity, &HexComponent, &Team, &Morale), UnitFilter>,
+    commanders: Query<(Entity, &HexComponent, &Team), With<Commander>>,
+    mut morale_check_event: EventWriter<MoraleCheckEvent>,
+) {
+    for (entity, hex, _, _) in units.iter().filter(|(_, _, team, morale)| {
+        **team == active_team.0 && morale.state != MoraleState::Steady
+    }) {
+        let is_near_commander = commanders
+            .iter()
+            .filter(|(commander, _, team)| commander != &entity && **team == active_team.0)
+            .any(|(_, commander_hex, _)| {
+                commander_hex.0.unsigned_distance_to(hex.0) <= COMMANDER_RALLY_RANGE
+            });
+        if is_near_commander {
+            morale_check_event.send(MoraleCheckEvent {
+                unit: entity,
+                reason: MoraleCheckReason::Rally,
+            });
+        }
+    }
+}
+
+fn mark_routing_units_to_flee(
+    active_team: Res<ActiveTeam>,
+    mut units: Query<(&Team, &mut Morale)>,
+) {
+    for (team, mut morale) in &mut units {
+        morale.flees_this_turn = *team == active_team.0 && morale.state == MoraleState::Routing;
+    }
+}
+
+/// One routing unit at a time moves away from enemies, spending all its action points
+fn flee_routing_units(
+    mut units: Query<
+        (
+            Entity,
+            &HexComponent,
+            &Team,
+            &UnitMarker,
+            &mut ActionPoints,
+            &mut Morale,
+        ),
+        UnitFilter,
+    >,
+    hexes: TerrainHexesQuery,
+    mut move_unit_event: EventWriter<MoveUnitEvent>,
+    mut log_event: EventWriter<LogEvent>,
+) {
+    let Some((fleeing_unit, start_hex, fleeing_team)) = units
+        .iter()
+        .find(|(_, _, _, _, _, morale)| {
+            morale.flees_this_turn && morale.state == MoraleState::Routing
+        })
+        .map(|(entity, hex, team, _, _, _)| (entity, hex.0, *team))
+    else {
+        return;
+    };
+
+    let enemy_hexes: Vec<_> = units
+        .iter()
+        .filter(|(_, _, team, _, _, _)| **team != fleeing_team)
+        .map(|(_, hex, _, _, _, _)| hex.0)
+        .collect();
+    let occupied_hexes: HashSet<_> = units.iter().map(|(_, hex, _, _, _, _)| hex.0).collect();
+
+    let (_, _, _, unit_marker, mut action_points, mut morale) =
+        units.get_mut(fleeing_unit).unwrap();
+    morale.flees_this_turn = false;
+
+    let flee_path = get_flee_path(start_hex, action_points.left, &enemy_hexes, |from, to| {
+        if occupied_hexes.contains(&to) {
+            return None;
+        }
+        let (from_elevation, (_, to_terrain, to_elevation)) = (
+            hexes.iter().find(|(hex, _, _)| hex.0 == from)?.2,
+            hexes.iter().find(|(hex, _, _)| hex.0 == to)?,
+        );
+        match to_terrain.movement_cost {
+            MovementCost::Impassable => None,
+            MovementCost::Passable(cost) => {
+                Some(cost + from_elevation.get_climbing_cost(to_elevation) as usize)
+            }
+        }
+    });
+    action_points.left = 0;
+
+    log_event.send(LogEvent {
+        message: format!(
+            "{} is routing and flees {} hexes",
+            unit_marker.0,
+            flee_path.len()
+        ),
+    });
+    if !flee_path.is_empty() {
+        move_unit_event.send(MoveUnitEvent {
+            entity: fleeing_unit,
+            path: flee_path,
+        });
+    }
+}
+
+/// Greedily steps to the neighbor farthest away from the nearest enemy, as long as that increases the distance
+fn get_flee_path(
+    start: Hex,
+    action_points: usize,
+    enemy_hexes: &[Hex],
+    get_step_cost: impl Fn(Hex, Hex) -> Option<usize>,
+) -> Vec<Hex> {
+    let distance_to_enemies = |hex: Hex| {
+        enemy_hexes
+            .iter()
+            .map(|enemy_hex| enemy_hex.unsigned_distance_to(hex))
+            .min()
+            .unwrap_or(u32::MAX)
+    };
+
+    let mut path = vec![];
+    let mut current = start;
+    let mut action_points_left = action_points;
+
+    while let Some((next, cost)) = current
+        .all_neighbors()
+        .into_iter()
+        .filter(|neighbor| !path.contains(neighbor) && neighbor != &start)
+        .filter_map(|neighbor| Some((neighbor, get_step_cost(current, neighbor)?)))
+        .filter(|(_, cost)| *cost <= action_points_left)
+        .filter(|(neighbor, _)| distance_to_enemies(*neighbor) > distance_to_enemies(current))
+        .max_by_key(|(neighbor, _)| distance_to_enemies(*neighbor))
+    {
+        action_points_left -= cost;
+        path.push(next);
+        current = next;
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failing_a_check_shakes_and_then_routs_the_unit() {
+        let mut morale = Morale::new(10);
+
+        assert!(morale.resolve_check(&MoraleCheckReason::Flanked, 10));
+        assert_eq!(morale.state, MoraleState::Steady);
+
+        assert!(!morale.resolve_check(&MoraleCheckReason::Flanked, 15));
+        assert_eq!(morale.state, MoraleState::Shaken);
+
+        assert!(!morale.resolve_check(&MoraleCheckReason::AllyDied, 11));
+        assert_eq!(morale.state, MoraleState::Routing);
+    }
+
+    #[test]
+    fn failing_a_check_badly_routs_the_unit_right_away() {
+        let mut morale = Morale::new(10);
+
+        assert!(!morale.resolve_check(&MoraleCheckReason::Wounded, 16));
+        assert_eq!(morale.state, MoraleState::Routing);
+    }
+
+    #[test]
+    fn succeeding_a_rally_check_makes_the_unit_steady() {
+        let mut morale = Morale::new(10);
+        morale.state = MoraleState::Routing;
+
+        assert!(!morale.resolve_check(&MoraleCheckReason::Rally, 20));
+        assert_eq!(morale.state, MoraleState::Routing);
+
+        assert!(morale.resolve_check(&MoraleCheckReason::Rally, 1));
+        assert_eq!(morale.state, MoraleState::Steady);
+    }
+
+    #[test]
+    fn routing_units_flee_away_from_enemies_as_far_as_their_action_points_allow() {
+        let flee_path = get_flee_path(Hex::ZERO, 2, &[Hex::new(-1, 0)], |_, _| Some(1));
+
+        assert_eq!(flee_path.len(), 2);
+        assert_eq!(flee_path[1].unsigned_distance_to(Hex::new(-1, 0)), 3);
+    }
+
+    #[test]
+    fn routing_units_do_not_flee_through_blocked_hexes() {
+        let flee_path = get_flee_path(Hex::ZERO, 5, &[Hex::new(-1, 0)], |_, _| None);
+
+        assert_eq!(flee_path, vec![]);
+    }
+}
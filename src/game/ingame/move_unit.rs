@@ -4,16 +4,23 @@ use std::time::Duration;
 
 use bevy::app::App;
 use bevy::prelude::{
-    in_state, info, trace, Entity, Event, EventReader, EventWriter, IntoSystemConfigs, Local,
-    NextState, Plugin, PostUpdate, Query, Res, ResMut, Resource, Time, Timer, Update, With,
+    in_state, info, trace, Commands, Entity, Event, EventReader, EventWriter, IntoSystemConfigs,
+    Local, NextState, OnEnter, Plugin, PostUpdate, Query, Res, ResMut, Resource, Time, Timer,
+    Update,
 };
 use bevy::time::TimerMode;
 use hexx::Hex;
 
+use crate::game::abilities::passive_combat_abilities::{run_triggered_abilities, AbilityTrigger};
 use crate::game::ingame::action_points::ActionPoints;
-use crate::game::ingame::combat::{AttackOrDefault, CombatEvent};
-use crate::game::ingame::hex::{HexComponent, HexMarker};
-use crate::game::ingame::terrain::{MovementCost, Terrain};
+use crate::game::ingame::charge::{clear_travelled_paths, TravelledPath};
+use crate::game::ingame::combat::{
+    handle_combat_event, AttackOrDefault, CombatConfig, CombatEvent, HealthPoints,
+};
+use crate::game::ingame::hex::{HexComponent, TerrainHexesQuery};
+use crate::game::ingame::stat_modifiers::{Stat, StatModifiers};
+use crate::game::ingame::status_effects::StatusEffects;
+use crate::game::ingame::terrain::MovementCost;
 use crate::game::ingame::unit::UnitFilter;
 use crate::game::ingame::unit_status::UnitStatus;
 use crate::game::states::in_game_state::InGameState;
@@ -42,12 +49,15 @@ impl<Time: TimeInterface> Plugin for AbstractMoveUnitsPlugin<Time> {
             )
             .add_systems(
                 PostUpdate,
-                check_whether_movement_has_ended.run_if(in_state(RoundState::MovingUnit)),
+                check_whether_movement_has_ended
+                    .before(handle_combat_event)
+                    .run_if(in_state(RoundState::MovingUnit)),
             )
             .add_systems(
                 PostUpdate,
                 handle_move_event.run_if(in_state(InGameState::Playing)),
-            );
+            )
+            .add_systems(OnEnter(RoundState::RoundEnd), clear_travelled_paths);
     }
 }
 
@@ -78,40 +88,53 @@ struct MovingUnit {
 
 fn handle_move_event(
     mut move_events: EventReader<MoveUnitEvent>,
-    hexes: Query<(&HexComponent, &Terrain), With<HexMarker>>,
-    mut units: Query<&mut ActionPoints, UnitFilter>,
+    hexes: TerrainHexesQuery,
+    mut units: Query<
+        (
+            &mut ActionPoints,
+            &HexComponent,
+            &StatModifiers,
+            &StatusEffects,
+        ),
+        UnitFilter,
+    >,
     mut moving_unit_resource: ResMut<MovingUnitsResource>,
     mut round_state: ResMut<NextState<RoundState>>,
 ) {
-    for move_event in move_events.read() {
-        let cost: usize = move_event
-            .path
+    let find_hex = |hex: &Hex| {
+        hexes
             .iter()
-            .map(|hex| {
-                hexes
-                    .iter()
-                    .find(|(hex_component, _)| &hex_component.0 == hex)
-                    .expect("A hex on the path must exist on the map")
-            })
-            .map(|(_, terrain)| &terrain.movement_cost)
-            .map(|movement_cost| match movement_cost {
-                MovementCost::Impassable => {
-                    unreachable!("An impassable tile must not be on the way")
-                }
-                MovementCost::Passable(tile_cost) => tile_cost,
-            })
-            .sum();
-
-        let mut action_points = units
+            .find(|(hex_component, _, _)| &hex_component.0 == hex)
+            .expect("A hex on the path must exist on the map")
+    };
+
+    for move_event in move_events.read() {
+        let (mut action_points, start_hex, stat_modifiers, status_effects) = units
             .get_mut(move_event.entity)
             .expect("The moving entity must exist");
 
+        let (_, _, start_elevation) = find_hex(&start_hex.0);
+        let (cost, _) = move_event.path.iter().map(find_hex).fold(
+            (0, start_elevation),
+            |(cost, previous_elevation), (_, terrain, elevation)| {
+                let tile_cost = match terrain.movement_cost {
+                    MovementCost::Impassable => {
+                        unreachable!("An impassable tile must not be on the way")
+                    }
+                    MovementCost::Passable(tile_cost) => tile_cost,
+                };
+                let climbing_cost = previous_elevation.get_climbing_cost(elevation) as usize;
+                (cost + tile_cost + climbing_cost, elevation)
+            },
+        );
+
+        let extra_step_cost = stat_modifiers
+            .resolve(Stat::StepCost, 0, status_effects)
+            .value;
+        let cost = cost + extra_step_cost * move_event.path.len();
+
         let action_points_left_before = action_points.left;
-        action_points.left = if cost > action_points.left {
-            0
-        } else {
-            action_points.left - cost
-        };
+        action_points.left = action_points.left.saturating_sub(cost);
         trace!(
             "Updated action points left from {action_points_left_before} to {}",
             action_points.left
@@ -137,11 +160,22 @@ impl Default for MovementTimer {
     }
 }
 
+/// Triggers `OnMoveStep` abilities on every step and `OnEnterHex` abilities on the last one
 fn move_unit_system<Time: TimeInterface>(
+    mut commands: Commands,
     time: Res<Time>,
     mut timer: Local<MovementTimer>,
     mut moving_unit_resource: ResMut<MovingUnitsResource>,
-    mut units: Query<(&mut HexComponent, &UnitStatus), UnitFilter>,
+    mut units: Query<
+        (
+            &mut HexComponent,
+            &UnitStatus,
+            &mut TravelledPath,
+            &HealthPoints,
+            &CombatConfig,
+        ),
+        UnitFilter,
+    >,
     mut combat_event: EventWriter<CombatEvent>,
 ) {
     if !timer.0.tick(time.delta()).finished() {
@@ -152,14 +186,45 @@ fn move_unit_system<Time: TimeInterface>(
     for moving_unit in &mut moving_unit_resource.0 {
         trace!("Moving unit: {moving_unit:?}");
 
-        let (mut hex_component, unit_status) = units
-            .get_mut(moving_unit.entity)
-            .expect("The moving unit must exist");
+        let Ok((mut hex_component, unit_status, mut travelled_path, health_points, combat_config)) =
+            units.get_mut(moving_unit.entity)
+        else {
+            info!(
+                "{:?} is gone, interrupting its movement",
+                moving_unit.entity
+            );
+            moving_unit.path.clear();
+            continue;
+        };
+        if health_points.left == 0 {
+            info!("{:?} died, interrupting its movement", moving_unit.entity);
+            moving_unit.path.clear();
+            continue;
+        }
 
-        hex_component.0 = moving_unit
+        let next_hex = moving_unit
             .path
             .pop_front()
             .expect("If the path is empty, the MovingUnit should be removed");
+        travelled_path.record_step(hex_component.0, next_hex);
+        hex_component.0 = next_hex;
+
+        run_triggered_abilities(
+            &mut commands,
+            moving_unit.entity,
+            None,
+            combat_config,
+            AbilityTrigger::OnMoveStep,
+        );
+        if moving_unit.path.is_empty() {
+            run_triggered_abilities(
+                &mut commands,
+                moving_unit.entity,
+                None,
+                combat_config,
+                AbilityTrigger::OnEnterHex,
+            );
+        }
 
         for unit_engaged_with in unit_status.get_engaged_with_units() {
             info!(
@@ -195,16 +260,31 @@ fn check_whether_movement_has_ended(
 #[cfg(test)]
 mod tests {
     use bevy::log::{Level, LogPlugin};
-    use bevy::prelude::{default, Handle};
+    use bevy::prelude::{default, FromWorld, Handle};
     use bevy::time::TimePlugin;
 
-    use crate::game::ingame::combat::{CombatConfig, HealthPoints};
+    use crate::game::abilities::passive_combat_abilities::{
+        PassiveCombatAbility, PassiveCombatAbilityRegistry,
+    };
+    use crate::game::ingame::combat::{CombatConfig, CombatPlugin};
+    use crate::game::ingame::combat_ruleset::CombatRuleset;
+    use crate::game::ingame::damage_type::{DamageResistances, DamageType};
+    use crate::game::ingame::elevation::Elevation;
+    use crate::game::ingame::game_log::LogEvent;
+    use crate::game::ingame::hex::HexMarker;
+    use crate::game::ingame::line_of_sight::LineOfSight;
+    use crate::game::ingame::morale::{Morale, MoraleCheckEvent};
     use crate::game::ingame::selected_unit::SelectedUnitResource;
+    use crate::game::ingame::stat_modifiers::{Stat, StatModifiers};
+    use crate::game::ingame::status_effects::StatusEffects;
     use crate::game::ingame::team_setup::Team;
+    use crate::game::ingame::terrain::Terrain;
     use crate::game::ingame::unit::{ProtoUnitBundle, UnitBundle, UnitMarker};
     use crate::game::ingame::unit_status::update_engagement;
+    use crate::game::util::dice::DiceRng;
+    use crate::game::util::dice_expression::DiceExpression;
     use crate::generate_test_app;
-    use crate::tests::AppWrapper;
+    use crate::tests::{add_passive_ability, AppWrapper};
 
     use super::*;
 
@@ -248,6 +328,15 @@ mod tests {
         app.update();
         assert_eq!(app.get_unit_action_points().left, MAX_ACTION_POINTS - 3);
         assert_eq!(app.get::<RoundState>(), &RoundState::Input);
+        assert_eq!(
+            app.app
+                .world
+                .entity(app.unit_entity)
+                .get::<TravelledPath>()
+                .unwrap()
+                .get_hexes(),
+            &[Hex::ZERO, Hex::new(1, 0), Hex::new(2, 0), Hex::new(2, 1)]
+        );
     }
 
     #[test]
@@ -290,6 +379,147 @@ mod tests {
         assert_eq!(app.get_events(&mut combat_event_reader).len(), 0);
     }
 
+    #[test]
+    fn a_unit_killed_by_attacks_of_opportunity_stops_moving() {
+        let mut app = TestApp::build_spawning_unit_at(Hex::ZERO);
+        app.app.add_event::<LogEvent>();
+        app.app.add_event::<MoraleCheckEvent>();
+        // the mover is engaged with both enemies, so the attacks are rolled with advantage
+        app.app.insert_resource(DiceRng::scripted([15, 15, 15, 15]));
+        app.app.init_resource::<CombatRuleset>();
+        app.app.add_plugins(CombatPlugin);
+        app.app
+            .world
+            .entity_mut(app.unit_entity)
+            .insert(HealthPoints::new(1));
+        app.spawn_enemy_unit_at(Hex::new(1, 1));
+        app.spawn_enemy_unit_at(Hex::new(2, 0));
+
+        app.send_event(MoveUnitEvent {
+            entity: app.unit_entity,
+            path: vec![Hex::new(1, 0), Hex::new(2, -1), Hex::new(3, -2)],
+        });
+        app.set_passed_time(Duration::from_secs_f32(MOVE_TICK_SECONDS));
+
+        for _ in 0..10 {
+            app.update();
+        }
+
+        assert!(app.app.world.get_entity(app.unit_entity).is_none());
+        assert_eq!(app.get::<RoundState>(), &RoundState::Input);
+        assert!(app.app.world.resource::<MovingUnitsResource>().0.is_empty());
+    }
+
+    #[test]
+    fn moving_triggers_move_step_abilities_on_every_step_and_enter_hex_abilities_at_the_end() {
+        let mut app = TestApp::build_spawning_unit_at(Hex::ZERO);
+        app.app.add_event::<LogEvent>();
+        add_passive_ability(
+            &mut app.app.world,
+            app.unit_entity,
+            r#"(
+                name: "Momentum",
+                trigger: Passive(OnMoveStep),
+                target: Owner,
+                effects: [ModifyStat(stat: Armor, value: Add(1))],
+            )"#,
+        );
+        add_passive_ability(
+            &mut app.app.world,
+            app.unit_entity,
+            r#"(
+                name: "Dig In",
+                trigger: Passive(OnEnterHex),
+                target: Owner,
+                effects: [ModifyStat(stat: Defense, value: Add(1))],
+            )"#,
+        );
+
+        app.send_event(MoveUnitEvent {
+            entity: app.unit_entity,
+            path: vec![Hex::new(1, 0), Hex::new(2, 0), Hex::new(2, 1)],
+        });
+        app.update();
+        app.update();
+        assert_eq!(app.get::<RoundState>(), &RoundState::MovingUnit);
+
+        app.set_passed_time(Duration::from_secs_f32(MOVE_TICK_SECONDS));
+        app.update();
+        assert_eq!(app.get_unit_hex(), Hex::new(1, 0));
+        assert_eq!(app.get_unit_stat(Stat::Armor), 1);
+        assert_eq!(app.get_unit_stat(Stat::Defense), 0);
+
+        app.update();
+        app.update();
+        assert_eq!(app.get_unit_hex(), Hex::new(2, 1));
+        assert_eq!(app.get_unit_stat(Stat::Armor), 3);
+        assert_eq!(app.get_unit_stat(Stat::Defense), 1);
+    }
+
+    #[test]
+    fn moving_next_to_an_ambusher_triggers_its_attack() {
+        let mut app = TestApp::build_spawning_unit_at(Hex::ZERO);
+        app.app.add_event::<LogEvent>();
+        let mut combat_event_reader = app.get_event_reader::<CombatEvent>();
+        let ambush = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::Ambush);
+        let ambusher = app.spawn_enemy_unit_at(Hex::new(2, 0));
+        app.app
+            .world
+            .get_mut::<CombatConfig>(ambusher)
+            .unwrap()
+            .passive_combat_abilities
+            .push(ambush);
+
+        app.send_event(MoveUnitEvent {
+            entity: app.unit_entity,
+            path: vec![Hex::new(1, 0)],
+        });
+        app.set_passed_time(Duration::from_secs_f32(MOVE_TICK_SECONDS));
+        app.update();
+        app.update();
+        app.update();
+
+        assert_eq!(app.get_unit_hex(), Hex::new(1, 0));
+        let combat_events = app.get_events(&mut combat_event_reader);
+        let combat_event = combat_events.first().expect("ambush attack");
+        assert_eq!(combat_event.attacker, ambusher);
+        assert_eq!(combat_event.defender, app.unit_entity);
+    }
+
+    #[test]
+    fn ambushers_without_an_attack_left_do_not_attack() {
+        let mut app = TestApp::build_spawning_unit_at(Hex::ZERO);
+        app.app.add_event::<LogEvent>();
+        let mut combat_event_reader = app.get_event_reader::<CombatEvent>();
+        let ambush = PassiveCombatAbilityRegistry::from_world(&mut app.app.world)
+            .get_registered_ability(PassiveCombatAbility::Ambush);
+        let ambusher = app.spawn_enemy_unit_at(Hex::new(2, 0));
+        app.app
+            .world
+            .get_mut::<CombatConfig>(ambusher)
+            .unwrap()
+            .passive_combat_abilities
+            .push(ambush);
+        app.app
+            .world
+            .get_mut::<ActionPoints>(ambusher)
+            .unwrap()
+            .attacks_this_round = 1;
+
+        app.send_event(MoveUnitEvent {
+            entity: app.unit_entity,
+            path: vec![Hex::new(1, 0)],
+        });
+        app.set_passed_time(Duration::from_secs_f32(MOVE_TICK_SECONDS));
+        app.update();
+        app.update();
+        app.update();
+
+        assert_eq!(app.get_unit_hex(), Hex::new(1, 0));
+        assert_eq!(app.get_events(&mut combat_event_reader).len(), 0);
+    }
+
     generate_test_app!(unit_entity: Entity);
 
     const MAX_ACTION_POINTS: usize = 10;
@@ -321,9 +551,15 @@ mod tests {
                 app.world.spawn((
                     HexMarker,
                     HexComponent(hex),
+                    Elevation::default(),
                     Terrain {
                         name: "test terrain".to_string(),
                         movement_cost: MovementCost::Passable(1),
+                        attack_modifier: 0,
+                        defense_modifier: 0,
+                        line_of_sight: LineOfSight::Clear,
+                        stops_charges: false,
+                        attack_disadvantage: false,
                     },
                 ));
             });
@@ -339,11 +575,17 @@ mod tests {
                         action_points: ActionPoints::new(MAX_ACTION_POINTS, 1, 1),
                         health_points: HealthPoints::new(5),
                         combat_config: CombatConfig {
-                            damage: 1,
+                            damage: DiceExpression::constant(1),
+                            damage_type: DamageType::Slashing,
                             defense: 1,
+                            damage_resistances: DamageResistances::default(),
                             range: 1,
+                            can_counter: true,
                             passive_combat_abilities: vec![],
+                            attacks: vec![],
+                            ammo: None,
                         },
+                        morale: Morale::new(10),
                         hex,
                     }
                     .into(),
@@ -356,7 +598,9 @@ mod tests {
 
             app.add_systems(
                 PostUpdate,
-                update_engagement.run_if(in_state(InGameState::Playing)),
+                update_engagement
+                    .before(handle_combat_event)
+                    .run_if(in_state(InGameState::Playing)),
             );
 
             app.update();
@@ -376,11 +620,17 @@ mod tests {
                         action_points: ActionPoints::new(MAX_ACTION_POINTS, 1, 1),
                         health_points: HealthPoints::new(5),
                         combat_config: CombatConfig {
-                            damage: 1,
+                            damage: DiceExpression::constant(1),
+                            damage_type: DamageType::Slashing,
                             defense: 1,
+                            damage_resistances: DamageResistances::default(),
                             range: 1,
+                            can_counter: true,
                             passive_combat_abilities: vec![],
+                            attacks: vec![],
+                            ammo: None,
                         },
+                        morale: Morale::new(10),
                         hex,
                     }
                     .into(),
@@ -401,6 +651,15 @@ mod tests {
                 .0
         }
 
+        /// The modifiers of the stat added up on a base of zero
+        fn get_unit_stat(&self, stat: Stat) -> usize {
+            let unit = self.app.world.entity(self.unit_entity);
+            unit.get::<StatModifiers>()
+                .unwrap()
+                .resolve(stat, 0, unit.get::<StatusEffects>().unwrap())
+                .value
+        }
+
         fn get_unit_action_points(&self) -> &ActionPoints {
             self.app
                 .world
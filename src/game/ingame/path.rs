@@ -59,11 +59,10 @@ pub(super) fn compute_current_path(
         return;
     };
 
-    let Some(hexes_way) = a_star(unit_hex.0, hovered_hex, |hex, _| {
+    let Some(hexes_way) = a_star(unit_hex.0, hovered_hex, |from, to| {
         selected_unit_resource
             .cost_map()
-            .get(&hex)
-            .and_then(|movement_cost| movement_cost.get_modified_algorithm_cost())
+            .get_modified_algorithm_cost(from, to)
     }) else {
         current_path.0 = None;
         return;
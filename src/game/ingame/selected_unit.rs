@@ -6,25 +6,34 @@ use bevy::prelude::{
     Handle, KeyCode, NextState, Query, Res, ResMut, Resource, State, With, Without,
 };
 use bevy::utils::HashMap;
-use hexx::algorithms::field_of_movement;
 use hexx::Hex;
 
-use crate::game::abilities::active_abilities::{ActivatedAbilityMarker, ActiveAbility};
+use crate::game::abilities::active_abilities::{
+    ActivatedAbilityMarker, ActiveAbility, UnitMoraleQuery,
+};
 use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::area_of_effect::AreaOfEffectPreviewResource;
 use crate::game::ingame::combat::CombatConfig;
-use crate::game::ingame::hex::{HexComponent, HexMarker, HexOverlayMarker, HexResources};
+use crate::game::ingame::elevation::MovementCostMap;
+use crate::game::ingame::hex::{HexComponent, HexOverlayMarker, HexResources, TerrainHexesQuery};
+use crate::game::ingame::line_of_sight::LineOfSightMap;
+use crate::game::ingame::stat_modifiers::{Stat, StatModifiers};
+use crate::game::ingame::status_effects::StatusEffects;
 use crate::game::ingame::team_setup::Team;
-use crate::game::ingame::terrain::{MovementCost, Terrain};
+use crate::game::ingame::terrain::MovementCost;
 use crate::game::ingame::unit::{UnitFilter, UnitMarker};
 use crate::game::states::round_state::RoundState;
+use crate::game::util::field_of_movement::directed_field_of_movement;
 use crate::game::util::find_units_within_range::FindUnitsWithinRange;
 
 #[derive(Resource, Default)]
 pub struct SelectedUnitResource {
     selected_unit: Option<Entity>,
+    /// index into [`CombatConfig::get_attacks`] of the selected unit
+    selected_attack: usize,
     recompute_cache: bool,
     reachable_hexes: Option<HashSet<Hex>>,
-    cost_map: HashMap<Hex, MovementCost>,
+    cost_map: MovementCostMap,
 }
 
 impl SelectedUnitResource {
@@ -35,20 +44,31 @@ impl SelectedUnitResource {
     pub fn set_selected_unit(&mut self, selected_unit: Option<Entity>) {
         self.needs_reachable_hexes_recomputation();
         self.selected_unit = selected_unit;
+        self.selected_attack = 0;
+    }
+
+    pub fn selected_attack(&self) -> usize {
+        self.selected_attack
+    }
+
+    /// The attack range changes the attackable hexes
+    pub fn set_selected_attack(&mut self, selected_attack: usize) {
+        self.needs_reachable_hexes_recomputation();
+        self.selected_attack = selected_attack;
     }
 
     pub fn reachable_hexes(&self) -> &Option<HashSet<Hex>> {
         &self.reachable_hexes
     }
 
-    pub fn cost_map(&self) -> &HashMap<Hex, MovementCost> {
+    pub fn cost_map(&self) -> &MovementCostMap {
         &self.cost_map
     }
 
     pub fn needs_reachable_hexes_recomputation(&mut self) {
         self.recompute_cache = true;
         self.reachable_hexes = Some(HashSet::new());
-        self.cost_map = HashMap::new();
+        self.cost_map = MovementCostMap::default();
     }
 }
 
@@ -139,9 +159,11 @@ pub type UpdateReachableHexesUnitsQuery<'world, 'state, 'a> = Query<
 
 pub(super) fn update_reachable_hexes_cache(
     units: UpdateReachableHexesUnitsQuery,
-    hexes: Query<(&HexComponent, &Terrain), With<HexMarker>>,
+    hexes: TerrainHexesQuery,
     mut selected_unit_resource: ResMut<SelectedUnitResource>,
     active_abilities: Query<(&ActiveAbility, &Parent), With<ActivatedAbilityMarker>>,
+    status_effects: Query<(&StatModifiers, &StatusEffects)>,
+    unit_morale: UnitMoraleQuery,
 ) {
     if !selected_unit_resource.recompute_cache {
         return;
@@ -154,10 +176,24 @@ pub(super) fn update_reachable_hexes_cache(
     };
 
     let (cost_map, reachable_hexes) = if let Ok((ability, parent)) = active_abilities.get_single() {
-        let reachable_hexes = ability.get_reachable_hexes(&units, &hexes, parent);
-        (HashMap::new(), reachable_hexes)
+        let reachable_hexes = ability.get_reachable_hexes(&units, &hexes, &unit_morale, parent);
+        (MovementCostMap::default(), reachable_hexes)
     } else {
-        compute_for_input_state(&units, &hexes, selected_unit)
+        let extra_step_cost = status_effects
+            .get(selected_unit)
+            .map(|(stat_modifiers, status_effects)| {
+                stat_modifiers
+                    .resolve(Stat::StepCost, 0, status_effects)
+                    .value
+            })
+            .unwrap_or_default();
+        compute_for_input_state(
+            &units,
+            &hexes,
+            selected_unit,
+            selected_unit_resource.selected_attack,
+            extra_step_cost as u32,
+        )
     };
 
     selected_unit_resource.cost_map = cost_map;
@@ -167,9 +203,11 @@ pub(super) fn update_reachable_hexes_cache(
 
 fn compute_for_input_state(
     units: &UpdateReachableHexesUnitsQuery,
-    hexes: &Query<(&HexComponent, &Terrain), With<HexMarker>>,
+    hexes: &TerrainHexesQuery,
     selected_unit: Entity,
-) -> (HashMap<Hex, MovementCost>, Option<HashSet<Hex>>) {
+    selected_attack: usize,
+    extra_step_cost: u32,
+) -> (MovementCostMap, Option<HashSet<Hex>>) {
     let Ok((
         action_points,
         selected_unit_hex,
@@ -179,15 +217,19 @@ fn compute_for_input_state(
     )) = units.get(selected_unit)
     else {
         warn!("Units query did not contain selected unit {selected_unit:?}");
-        return (HashMap::new(), None);
+        return (MovementCostMap::default(), None);
     };
 
-    let mut cost_map: HashMap<_, _> = hexes
+    let mut movement_costs: HashMap<_, _> = hexes
+        .iter()
+        .map(|(hex_component, terrain, _)| (hex_component.0, terrain.movement_cost.clone()))
+        .collect();
+    let elevations: HashMap<_, _> = hexes
         .iter()
-        .map(|(hex_component, terrain)| (hex_component.0, terrain.movement_cost.clone()))
+        .map(|(hex_component, _, elevation)| (hex_component.0, *elevation))
         .collect();
 
-    cost_map.extend(units.iter().map(|(_, hex_component, _, _, _)| {
+    movement_costs.extend(units.iter().map(|(_, hex_component, _, _, _)| {
         let cost = match selected_unit_hex.0.unsigned_distance_to(hex_component.0) {
             0 => MovementCost::Passable(0),
             _ => MovementCost::Impassable,
@@ -195,19 +237,41 @@ fn compute_for_input_state(
         (hex_component.0, cost)
     }));
 
-    let mut reachable_hexes =
-        field_of_movement(selected_unit_hex.0, action_points.left as u32, |hex| {
-            cost_map
-                .get(&hex)
-                .and_then(|movement_cost| movement_cost.get_modified_algorithm_cost())
-        });
+    let cost_map = MovementCostMap::new(movement_costs, elevations.clone())
+        .with_extra_step_cost(extra_step_cost);
+
+    let mut reachable_hexes = directed_field_of_movement(
+        selected_unit_hex.0,
+        action_points.left as u32,
+        |from, to| cost_map.get_modified_algorithm_cost(from, to),
+    );
 
-    if selected_unit_action_points.can_still_attack_this_turn() {
-        let attack_range = selected_unit_combat_config.range;
-        let attackable_units =
-            units.find_units_within_range(selected_unit_hex.0, attack_range, |team| {
-                team != selected_unit_team
-            });
+    let attack = selected_unit_combat_config
+        .get_attack(selected_attack)
+        .filter(|attack| selected_unit_action_points.can_still_attack_with(attack));
+    if let Some(attack) = attack {
+        let get_elevation = |hex: &Hex| elevations.get(hex).copied().unwrap_or_default();
+        let selected_unit_elevation = get_elevation(&selected_unit_hex.0);
+        let attack_range = attack.range;
+        let line_of_sight_map = LineOfSightMap::from_terrain(
+            hexes,
+            units
+                .iter()
+                .map(|(_, hex_component, _, _, _)| hex_component.0),
+        );
+        let attackable_units = units
+            .find_units_within_range(
+                selected_unit_hex.0,
+                selected_unit_elevation.get_max_effective_range(attack_range),
+                |team| team != selected_unit_team,
+            )
+            .into_iter()
+            .filter(|hex| {
+                let effective_range =
+                    selected_unit_elevation.get_effective_range(attack_range, &get_elevation(hex));
+                selected_unit_hex.0.unsigned_distance_to(*hex) <= effective_range
+            })
+            .filter(|hex| line_of_sight_map.can_see(selected_unit_hex.0, *hex));
 
         reachable_hexes.extend(attackable_units);
     }
@@ -223,6 +287,7 @@ pub(super) fn update_hex_overlay(
     >,
     hex_resources: Res<HexResources>,
     selected_unit_resource: Res<SelectedUnitResource>,
+    area_of_effect_preview: Res<AreaOfEffectPreviewResource>,
 ) {
     let Some(reachable_hexes) = &selected_unit_resource.reachable_hexes else {
         for (entity, _, color_material) in &hex_overlays {
@@ -234,13 +299,26 @@ pub(super) fn update_hex_overlay(
     };
 
     for (entity, hex, color_material) in &hex_overlays {
-        let is_reachable = reachable_hexes.contains(&hex.0);
-        if color_material.is_some() && is_reachable {
-            commands.entity(entity).remove::<Handle<ColorMaterial>>();
-        } else if color_material.is_none() && !is_reachable {
-            commands
-                .entity(entity)
-                .insert(hex_resources.not_reachable_overlay_color.clone());
+        let is_affected = area_of_effect_preview
+            .0
+            .as_ref()
+            .is_some_and(|affected_hexes| affected_hexes.contains(&hex.0));
+        let overlay_color = if is_affected {
+            Some(&hex_resources.area_of_effect_overlay_color)
+        } else if !reachable_hexes.contains(&hex.0) {
+            Some(&hex_resources.not_reachable_overlay_color)
+        } else {
+            None
+        };
+
+        match overlay_color {
+            Some(overlay_color) if color_material != Some(overlay_color) => {
+                commands.entity(entity).insert(overlay_color.clone());
+            }
+            None if color_material.is_some() => {
+                commands.entity(entity).remove::<Handle<ColorMaterial>>();
+            }
+            _ => {}
         }
     }
 }
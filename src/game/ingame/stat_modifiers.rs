@@ -0,0 +1,229 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::Component;
+
+use crate::game::ingame::damage_type::DamageResistances;
+use crate::game::ingame::status_effects::StatusEffects;
+
+/// Unit stats that can be changed by modifiers
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Stat {
+    Defense,
+    Armor,
+    /// action points the unit starts its turn with
+    ActionPoints,
+    /// additional action points every step costs
+    StepCost,
+}
+
+impl Display for Stat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stat::Defense => write!(f, "Defense"),
+            Stat::Armor => write!(f, "Armor"),
+            Stat::ActionPoints => write!(f, "Action points"),
+            Stat::StepCost => write!(f, "Extra step cost"),
+        }
+    }
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum ModifierValue {
+    Add(i32),
+    Multiply(f32),
+}
+
+impl Display for ModifierValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModifierValue::Add(value) => write!(f, "{value:+}"),
+            ModifierValue::Multiply(factor) => write!(f, "x{factor}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatModifier {
+    pub stat: Stat,
+    pub value: ModifierValue,
+    /// name of the ability, unit or status effect that caused the modifier
+    pub source: String,
+    /// rounds of the affected unit until the modifier wears off, permanent if not set
+    pub remaining_rounds: Option<usize>,
+}
+
+impl Display for StatModifier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.source, self.value)
+    }
+}
+
+/// The value of a stat after all modifiers were applied, together with where it comes from
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectiveStat {
+    pub stat: Stat,
+    pub base: usize,
+    pub value: usize,
+    pub modifiers: Vec<StatModifier>,
+}
+
+impl Display for EffectiveStat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.stat, self.value)?;
+        if self.modifiers.is_empty() {
+            return Ok(());
+        }
+        let modifiers = self
+            .modifiers
+            .iter()
+            .map(|modifier| modifier.to_string())
+            .collect::<Vec<_>>();
+        write!(f, " [base {}, {}]", self.base, modifiers.join(", "))
+    }
+}
+
+/// Modifiers on top of the base stats of a unit, which stay untouched.
+/// Status effects add their own modifiers for as long as they last.
+#[derive(Component, Debug, Default)]
+pub struct StatModifiers(Vec<StatModifier>);
+
+impl StatModifiers {
+    pub fn add(&mut self, modifier: StatModifier) {
+        self.0.push(modifier);
+    }
+
+    /// Counts down the remaining rounds at the end of the unit's turn and returns the modifiers that wore off
+    pub fn tick(&mut self) -> Vec<StatModifier> {
+        self.0.iter_mut().for_each(|modifier| {
+            modifier.remaining_rounds = modifier
+                .remaining_rounds
+                .map(|remaining_rounds| remaining_rounds.saturating_sub(1))
+        });
+
+        let (expired, active) = self
+            .0
+            .drain(..)
+            .partition(|modifier| modifier.remaining_rounds == Some(0));
+        self.0 = active;
+        expired
+    }
+
+    /// Additive modifiers are applied before multiplicative ones
+    pub fn resolve(
+        &self,
+        stat: Stat,
+        base: usize,
+        status_effects: &StatusEffects,
+    ) -> EffectiveStat {
+        let modifiers = self
+            .0
+            .iter()
+            .cloned()
+            .chain(status_effects.get_stat_modifiers())
+            .filter(|modifier| modifier.stat == stat)
+            .collect::<Vec<_>>();
+
+        let sum = modifiers
+            .iter()
+            .filter_map(|modifier| match modifier.value {
+                ModifierValue::Add(value) => Some(value),
+                ModifierValue::Multiply(_) => None,
+            })
+            .sum::<i32>();
+        let factor = modifiers
+            .iter()
+            .filter_map(|modifier| match modifier.value {
+                ModifierValue::Add(_) => None,
+                ModifierValue::Multiply(factor) => Some(factor),
+            })
+            .product::<f32>();
+        let value = ((base as i32 + sum).max(0) as f32 * factor)
+            .round()
+            .max(0.0) as usize;
+
+        EffectiveStat {
+            stat,
+            base,
+            value,
+            modifiers,
+        }
+    }
+
+    pub fn get_damage_resistances(
+        &self,
+        base: &DamageResistances,
+        status_effects: &StatusEffects,
+    ) -> DamageResistances {
+        DamageResistances {
+            armor: self.resolve(Stat::Armor, base.armor, status_effects).value,
+            ..base.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::ingame::status_effects::{StatusEffect, StatusEffectType};
+
+    use super::*;
+
+    fn modifier(value: ModifierValue, remaining_rounds: Option<usize>) -> StatModifier {
+        StatModifier {
+            stat: Stat::Defense,
+            value,
+            source: "test".to_string(),
+            remaining_rounds,
+        }
+    }
+
+    #[test]
+    fn additive_modifiers_apply_before_multiplicative_ones() {
+        let mut stat_modifiers = StatModifiers::default();
+        stat_modifiers.add(modifier(ModifierValue::Multiply(0.5), None));
+        stat_modifiers.add(modifier(ModifierValue::Add(2), None));
+
+        let defense = stat_modifiers.resolve(Stat::Defense, 10, &StatusEffects::default());
+
+        assert_eq!(defense.value, 6);
+        assert_eq!(
+            defense.to_string(),
+            "Defense: 6 [base 10, test x0.5, test +2]"
+        );
+        assert_eq!(
+            stat_modifiers
+                .resolve(Stat::Armor, 1, &StatusEffects::default())
+                .to_string(),
+            "Armor: 1"
+        );
+    }
+
+    #[test]
+    fn modifiers_expire_and_include_status_effects() {
+        let mut stat_modifiers = StatModifiers::default();
+        stat_modifiers.add(modifier(ModifierValue::Add(1), Some(1)));
+        stat_modifiers.add(modifier(ModifierValue::Add(1), None));
+        let mut status_effects = StatusEffects::default();
+        status_effects.add(StatusEffect {
+            effect_type: StatusEffectType::ArmorBroken,
+            source: "Orc".to_string(),
+            stacks: 2,
+            remaining_rounds: 1,
+        });
+
+        let expired = stat_modifiers.tick();
+
+        assert_eq!(expired, vec![modifier(ModifierValue::Add(1), Some(0))]);
+        assert_eq!(
+            stat_modifiers
+                .resolve(Stat::Defense, 10, &status_effects)
+                .value,
+            11
+        );
+        assert_eq!(
+            stat_modifiers
+                .resolve(Stat::Armor, 3, &status_effects)
+                .to_string(),
+            "Armor: 1 [base 3, Armor broken (Orc) -2]"
+        );
+    }
+}
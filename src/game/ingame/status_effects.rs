@@ -0,0 +1,206 @@
+use std::fmt::{Display, Formatter};
+
+use bevy::prelude::{Color, Component};
+use enum_iterator::Sequence;
+
+#[cfg(doc)]
+use crate::game::ingame::stat_modifiers::StatModifiers;
+use crate::game::ingame::stat_modifiers::{ModifierValue, Stat, StatModifier};
+
+#[derive(
+    serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Hash, PartialEq, Eq, Sequence,
+)]
+pub enum StatusEffectType {
+    /// the unit gets no action points at the start of its turn
+    Stunned,
+    /// the unit takes one damage per stack at the end of its turn
+    Poisoned,
+    /// the unit loses one armor point per stack
+    ArmorBroken,
+    /// every step costs the unit one additional action point per stack
+    Slowed,
+}
+
+impl StatusEffectType {
+    pub fn get_badge_color(&self) -> Color {
+        match self {
+            StatusEffectType::Stunned => Color::YELLOW,
+            StatusEffectType::Poisoned => Color::DARK_GREEN,
+            StatusEffectType::ArmorBroken => Color::GRAY,
+            StatusEffectType::Slowed => Color::BLUE,
+        }
+    }
+}
+
+impl Display for StatusEffectType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusEffectType::Stunned => write!(f, "Stunned"),
+            StatusEffectType::Poisoned => write!(f, "Poisoned"),
+            StatusEffectType::ArmorBroken => write!(f, "Armor broken"),
+            StatusEffectType::Slowed => write!(f, "Slowed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEffect {
+    pub effect_type: StatusEffectType,
+    /// name of the ability or unit that caused the effect
+    pub source: String,
+    pub stacks: usize,
+    /// rounds of the affected unit until the effect wears off
+    pub remaining_rounds: usize,
+}
+
+impl StatusEffect {
+    /// The modifier the effect adds to the stats of the unit while it lasts
+    pub fn get_stat_modifier(&self) -> Option<StatModifier> {
+        let stacks = self.stacks as i32;
+        let (stat, value) = match self.effect_type {
+            StatusEffectType::Stunned => (Stat::ActionPoints, ModifierValue::Multiply(0.0)),
+            StatusEffectType::Poisoned => return None,
+            StatusEffectType::ArmorBroken => (Stat::Armor, ModifierValue::Add(-stacks)),
+            StatusEffectType::Slowed => (Stat::StepCost, ModifierValue::Add(stacks)),
+        };
+        Some(StatModifier {
+            stat,
+            value,
+            source: format!("{} ({})", self.effect_type, self.source),
+            remaining_rounds: Some(self.remaining_rounds),
+        })
+    }
+}
+
+impl Display for StatusEffect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.effect_type)?;
+        if self.stacks > 1 {
+            write!(f, " x{}", self.stacks)?;
+        }
+        write!(f, " ({} rounds, {})", self.remaining_rounds, self.source)
+    }
+}
+
+/// Temporary effects on a unit. Their stat changes are resolved together with the [`StatModifiers`].
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn get_effects(&self) -> &[StatusEffect] {
+        &self.0
+    }
+
+    /// Effects of the same type stack up and last as long as the longest of them
+    pub fn add(&mut self, status_effect: StatusEffect) {
+        match self
+            .0
+            .iter_mut()
+            .find(|effect| effect.effect_type == status_effect.effect_type)
+        {
+            Some(effect) => {
+                effect.stacks += status_effect.stacks;
+                effect.remaining_rounds =
+                    effect.remaining_rounds.max(status_effect.remaining_rounds);
+                effect.source = status_effect.source;
+            }
+            None => self.0.push(status_effect),
+        }
+    }
+
+    pub fn get_stacks(&self, effect_type: StatusEffectType) -> usize {
+        self.0
+            .iter()
+            .filter(|effect| effect.effect_type == effect_type)
+            .map(|effect| effect.stacks)
+            .sum()
+    }
+
+    /// Counts down the remaining rounds at the end of the unit's turn and returns the effects that wore off
+    pub fn tick(&mut self) -> Vec<StatusEffect> {
+        self.0
+            .iter_mut()
+            .for_each(|effect| effect.remaining_rounds = effect.remaining_rounds.saturating_sub(1));
+
+        let (expired, active) = self
+            .0
+            .drain(..)
+            .partition(|effect| effect.remaining_rounds == 0);
+        self.0 = active;
+        expired
+    }
+
+    pub fn get_stat_modifiers(&self) -> impl Iterator<Item = StatModifier> + '_ {
+        self.0.iter().filter_map(StatusEffect::get_stat_modifier)
+    }
+
+    pub fn get_poison_damage(&self) -> usize {
+        self.get_stacks(StatusEffectType::Poisoned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::ingame::damage_type::DamageResistances;
+    use crate::game::ingame::stat_modifiers::StatModifiers;
+
+    use super::*;
+
+    fn effect(effect_type: StatusEffectType, remaining_rounds: usize) -> StatusEffect {
+        StatusEffect {
+            effect_type,
+            source: "test".to_string(),
+            stacks: 1,
+            remaining_rounds,
+        }
+    }
+
+    #[test]
+    fn effects_of_the_same_type_stack() {
+        let mut status_effects = StatusEffects::default();
+
+        status_effects.add(effect(StatusEffectType::ArmorBroken, 1));
+        status_effects.add(effect(StatusEffectType::ArmorBroken, 3));
+
+        assert_eq!(
+            status_effects.get_effects(),
+            &[StatusEffect {
+                stacks: 2,
+                ..effect(StatusEffectType::ArmorBroken, 3)
+            }]
+        );
+        let damage_resistances = StatModifiers::default().get_damage_resistances(
+            &DamageResistances {
+                armor: 3,
+                ..Default::default()
+            },
+            &status_effects,
+        );
+        assert_eq!(damage_resistances.armor, 1);
+    }
+
+    #[test]
+    fn effects_wear_off_after_their_rounds() {
+        let mut status_effects = StatusEffects::default();
+        status_effects.add(effect(StatusEffectType::Stunned, 1));
+        status_effects.add(effect(StatusEffectType::Poisoned, 2));
+
+        let stat_modifiers = StatModifiers::default();
+        let action_points = |status_effects: &StatusEffects| {
+            stat_modifiers
+                .resolve(Stat::ActionPoints, 4, status_effects)
+                .value
+        };
+        assert_eq!(action_points(&status_effects), 0);
+
+        let expired = status_effects.tick();
+
+        assert_eq!(expired, vec![effect(StatusEffectType::Stunned, 0)]);
+        assert_eq!(action_points(&status_effects), 4);
+        assert_eq!(status_effects.get_poison_damage(), 1);
+
+        status_effects.tick();
+
+        assert_eq!(status_effects.get_effects(), &[]);
+    }
+}
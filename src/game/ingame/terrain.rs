@@ -2,13 +2,24 @@ use bevy::prelude::Component;
 use std::cmp::max;
 use std::fmt::{Display, Formatter};
 
+use crate::game::ingame::line_of_sight::LineOfSight;
+
 #[derive(Component, Debug, Clone)]
 pub struct Terrain {
     pub name: String,
     pub movement_cost: MovementCost,
+    /// added to the attack roll of units attacking from this terrain
+    pub attack_modifier: i32,
+    /// added to the defense of units standing on this terrain
+    pub defense_modifier: i32,
+    pub line_of_sight: LineOfSight,
+    /// charges into or through this terrain get no bonus
+    pub stops_charges: bool,
+    /// units attacking from this terrain roll with disadvantage
+    pub attack_disadvantage: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
 pub enum MovementCost {
     Impassable,
     Passable(usize),
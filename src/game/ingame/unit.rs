@@ -5,7 +5,11 @@ use bevy::prelude::{
 };
 use hexx::Hex;
 
+use crate::game::ingame::charge::TravelledPath;
 use crate::game::ingame::hex::{HexComponent, HexMarker, HEX_RADIUS};
+use crate::game::ingame::morale::Morale;
+use crate::game::ingame::stat_modifiers::StatModifiers;
+use crate::game::ingame::status_effects::StatusEffects;
 use crate::game::ingame::team_setup::Team;
 use crate::game::ingame::unit_status::UnitStatus;
 
@@ -21,9 +25,13 @@ pub struct UnitBundle {
     unit_marker: UnitMarker,
     player: Team,
     unit_status: UnitStatus,
+    status_effects: StatusEffects,
+    stat_modifiers: StatModifiers,
+    travelled_path: TravelledPath,
     action_points: ActionPoints,
     health_points: HealthPoints,
     combat_config: CombatConfig,
+    morale: Morale,
     hex_component: HexComponent,
 }
 
@@ -35,6 +43,7 @@ pub struct ProtoUnitBundle {
     pub action_points: ActionPoints,
     pub health_points: HealthPoints,
     pub combat_config: CombatConfig,
+    pub morale: Morale,
     pub hex: Hex,
 }
 
@@ -48,6 +57,7 @@ impl From<ProtoUnitBundle> for UnitBundle {
             action_points,
             health_points,
             combat_config,
+            morale,
             hex,
         } = value;
 
@@ -64,9 +74,13 @@ impl From<ProtoUnitBundle> for UnitBundle {
             unit_marker,
             player,
             unit_status: UnitStatus::default(),
+            status_effects: StatusEffects::default(),
+            stat_modifiers: StatModifiers::default(),
+            travelled_path: TravelledPath::default(),
             action_points,
             health_points,
             combat_config,
+            morale,
             hex_component: HexComponent(hex),
         }
     }
@@ -1,9 +1,14 @@
-use bevy::prelude::{debug, Component, DetectChanges, Entity, Query, Ref, ResMut};
+use bevy::prelude::{
+    debug, Commands, Component, DetectChanges, Entity, Query, Ref, Res, ResMut, State,
+};
 use bevy::utils::HashSet;
 
+use crate::game::abilities::passive_combat_abilities::{run_triggered_abilities, AbilityTrigger};
+use crate::game::ingame::combat::CombatConfig;
 use crate::game::ingame::hex::HexComponent;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
 use crate::game::ingame::team_setup::Team;
+use crate::game::states::round_state::RoundState;
 
 #[derive(Component, Debug, Default)]
 pub struct UnitStatus {
@@ -28,9 +33,13 @@ impl UnitStatus {
     }
 }
 
+/// Units that get engaged by a moving enemy trigger their `OnEngaged` abilities
 pub(super) fn update_engagement(
+    mut commands: Commands,
     mut selected_unit_resource: ResMut<SelectedUnitResource>,
     mut units: Query<(Entity, &mut UnitStatus, Ref<HexComponent>, &Team)>,
+    combat_configs: Query<&CombatConfig>,
+    round_state: Res<State<RoundState>>,
 ) {
     let moved_unit_and_enemy_neighbors: Vec<_> = units
         .iter()
@@ -88,6 +97,18 @@ pub(super) fn update_engagement(
             if !adjacent_unit_status.is_engaged_with(&changed_entity) {
                 debug!("Engaging {adjacent_entity:?} with {changed_entity:?}");
                 adjacent_unit_status.engage_with(changed_entity);
+
+                if round_state.get() == &RoundState::MovingUnit {
+                    if let Ok(combat_config) = combat_configs.get(adjacent_entity) {
+                        run_triggered_abilities(
+                            &mut commands,
+                            adjacent_entity,
+                            Some(changed_entity),
+                            combat_config,
+                            AbilityTrigger::OnEngaged,
+                        );
+                    }
+                }
             }
         }
     }
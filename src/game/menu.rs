@@ -1,26 +1,94 @@
-use bevy::prelude::{NextState, Res, ResMut, State};
-use bevy_egui::egui::Window;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::{Local, NextState, Res, ResMut, State};
+use bevy_egui::egui::{Button, ComboBox, TextEdit, Window};
 use bevy_egui::EguiContexts;
+use enum_iterator::all;
 
+use crate::game::asset_loading::map_assets::MapAssetsResource;
 use crate::game::asset_loading::nation_assets::LoadingState;
+use crate::game::asset_loading::AssetLoadingErrors;
+use crate::game::ingame::combat_ruleset::{CombatRuleset, CriticalHitRule, FumbleRule};
 use crate::game::states::game_state::GameState;
 use crate::game::states::quickstart::QuickstartState;
+use crate::game::util::dice::DiceSeed;
+
+/// The settings of the next game
+#[derive(SystemParam)]
+pub struct GameSettings<'w, 's> {
+    dice_seed: ResMut<'w, DiceSeed>,
+    seed_input: Local<'s, String>,
+    combat_ruleset: ResMut<'w, CombatRuleset>,
+}
 
 pub fn menu_ui(
     mut contexts: EguiContexts,
     loading_state: Res<State<LoadingState>>,
+    asset_loading_errors: Res<AssetLoadingErrors>,
+    map_assets_resource: Option<Res<MapAssetsResource>>,
     mut next_game_state: ResMut<NextState<GameState>>,
     mut next_quickstart_state: ResMut<NextState<QuickstartState>>,
+    mut game_settings: GameSettings,
 ) {
+    let GameSettings {
+        dice_seed,
+        seed_input,
+        combat_ruleset,
+    } = &mut game_settings;
     Window::new("Menu").show(contexts.ctx_mut(), |ui| match loading_state.get() {
         LoadingState::LoadingDynamicAssets | LoadingState::LoadingNationAssetsDefinition => {
             ui.label("Loading...");
         }
+        LoadingState::Failed => {
+            ui.label("Some asset files are invalid:");
+            for asset_loading_error in &asset_loading_errors.0 {
+                ui.label(asset_loading_error);
+            }
+        }
         LoadingState::Done => {
+            ui.horizontal(|ui| {
+                ui.label("Dice seed:");
+                ui.add(TextEdit::singleline(&mut **seed_input).hint_text("random"));
+            });
+            let seed = seed_input.trim().parse::<u64>().ok();
+            if !seed_input.trim().is_empty() && seed.is_none() {
+                ui.label("The seed must be a positive number");
+            }
+
+            ComboBox::from_label("Natural 20")
+                .selected_text(combat_ruleset.critical_hit.to_string())
+                .show_ui(ui, |ui| {
+                    for critical_hit_rule in all::<CriticalHitRule>() {
+                        ui.selectable_value(
+                            &mut combat_ruleset.critical_hit,
+                            critical_hit_rule,
+                            critical_hit_rule.to_string(),
+                        );
+                    }
+                });
+            ComboBox::from_label("Natural 1")
+                .selected_text(combat_ruleset.fumble.to_string())
+                .show_ui(ui, |ui| {
+                    for fumble_rule in all::<FumbleRule>() {
+                        ui.selectable_value(
+                            &mut combat_ruleset.fumble,
+                            fumble_rule,
+                            fumble_rule.to_string(),
+                        );
+                    }
+                });
+
             if ui.button("Start").clicked() {
+                dice_seed.0 = seed;
                 next_game_state.set(GameState::InGame);
             }
-            if ui.button("Quickstart").clicked() {
+            // quickstart plays on the first map
+            let has_maps = map_assets_resource
+                .is_some_and(|map_assets_resource| !map_assets_resource.get_maps().is_empty());
+            if ui
+                .add_enabled(has_maps, Button::new("Quickstart"))
+                .clicked()
+            {
+                dice_seed.0 = seed;
                 next_game_state.set(GameState::InGame);
                 next_quickstart_state.set(QuickstartState::DoIt);
             }
@@ -1,4 +1,5 @@
 use crate::game::abilities::active_abilities::ActiveAbilityRegistry;
+use crate::game::abilities::defined_abilities::{DefinedAbility, DefinedAbilityRegistry};
 use crate::game::abilities::passive_combat_abilities::PassiveCombatAbilityRegistry;
 use bevy::app::App;
 #[cfg(not(test))]
@@ -13,11 +14,13 @@ use hexx::Hex;
 use crate::game::asset_loading::nation_asset_resource::NationAssetsResource;
 use crate::game::asset_loading::nation_assets::UnitKey;
 use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::ammo::Supplier;
 use crate::game::ingame::combat::{CombatConfig, HealthPoints};
 use crate::game::ingame::hex::{setup_hex_grid, HexComponent, HexMarker};
 #[cfg(not(test))]
 use crate::game::ingame::hovered_hex::update_hovered_hex;
 use crate::game::ingame::hovered_hex::HoveredHex;
+use crate::game::ingame::morale::{Commander, Morale};
 #[cfg(not(test))]
 use crate::game::ingame::post_update_systems::update_transform_from_hex;
 use crate::game::ingame::team_setup::Team;
@@ -223,9 +226,16 @@ fn handle_deploy_unit_event(
     mut deploy_unit_events: EventReader<DeployUnitEvent>,
     passive_combat_ability_registry: Local<PassiveCombatAbilityRegistry>,
     active_ability_registry: Local<ActiveAbilityRegistry>,
+    defined_ability_registry: Res<DefinedAbilityRegistry>,
 ) {
     for event in deploy_unit_events.read() {
         let unit_assets = nation_assets_resource.get_unit_assets(&event.unit);
+        let defined_abilities: Vec<_> = unit_assets
+            .stats
+            .abilities
+            .iter()
+            .filter_map(|name| defined_ability_registry.get(name))
+            .collect();
 
         let unit_bundle: UnitBundle = ProtoUnitBundle {
             texture: unit_assets.image.clone(),
@@ -238,17 +248,12 @@ fn handle_deploy_unit_event(
                 unit_assets.stats.attack_action_point_cost,
             ),
             health_points: HealthPoints::new(unit_assets.stats.max_health_points),
-            combat_config: CombatConfig {
-                damage: unit_assets.stats.damage,
-                defense: unit_assets.stats.defense,
-                range: unit_assets.stats.range,
-                passive_combat_abilities: unit_assets
-                    .stats
-                    .passive_combat_abilities
-                    .into_iter()
-                    .map(|ability| passive_combat_ability_registry.get_registered_ability(ability))
-                    .collect(),
-            },
+            combat_config: CombatConfig::from_unit_stats(
+                &unit_assets.stats,
+                &passive_combat_ability_registry,
+                &defined_abilities,
+            ),
+            morale: Morale::new(unit_assets.stats.morale),
             hex: event.hex,
         }
         .into();
@@ -257,11 +262,30 @@ fn handle_deploy_unit_event(
             .spawn(unit_bundle)
             .with_children(|parent| {
                 for active_ability_type in &unit_assets.stats.active_abilities {
-                    parent
-                        .spawn(active_ability_registry.get_registered_ability(active_ability_type));
+                    let mut ability =
+                        active_ability_registry.get_registered_ability(active_ability_type);
+                    if let Some(ammo) = unit_assets
+                        .stats
+                        .active_ability_ammo
+                        .get(active_ability_type)
+                    {
+                        ability = ability.with_ammo(*ammo);
+                    }
+                    parent.spawn(ability);
+                }
+                for ability in &defined_abilities {
+                    if let DefinedAbility::Active(ability) = ability {
+                        parent.spawn(ability.clone());
+                    }
                 }
             })
             .id();
+        if unit_assets.stats.commander {
+            commands.entity(entity).insert(Commander);
+        }
+        if unit_assets.stats.supplier {
+            commands.entity(entity).insert(Supplier);
+        }
 
         debug!("Deployed unit {entity:?} for event: {event:?}");
     }
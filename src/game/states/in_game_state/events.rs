@@ -6,7 +6,7 @@ pub(super) fn skip_events(
     mut next_in_game_state: ResMut<NextState<InGameState>>,
 ) {
     if in_game_state.get() == &InGameState::Events {
-        info!("Skipping events to {:?}", InGameState::DeployUnits);
-        next_in_game_state.set(InGameState::DeployUnits);
+        info!("Skipping events to {:?}", InGameState::PickMap);
+        next_in_game_state.set(InGameState::PickMap);
     }
 }
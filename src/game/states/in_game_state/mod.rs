@@ -2,25 +2,28 @@ use bevy::app::App;
 use bevy::prelude::{NextState, OnEnter, Plugin, ResMut, Resource, State, States};
 use bevy::utils::HashMap;
 
+use crate::game::asset_loading::map_assets::MapKey;
 use crate::game::asset_loading::nation_asset_resource::NationKey;
 use crate::game::ingame::team_setup::Team;
 use crate::game::states::game_state::GameState;
 use crate::game::states::in_game_state::deploy_units::DeployUnitsPlugin;
 use crate::game::states::in_game_state::events::skip_events;
 use crate::game::states::in_game_state::pick_commander::skip_pick_commander;
+use crate::game::states::in_game_state::pick_map::PickMapPlugin;
 use crate::game::states::in_game_state::pick_nation::{PickNationEvent, PickNationPlugin};
 use crate::game::states::round_state::start_round_system;
 
 mod deploy_units;
 mod events;
 mod pick_commander;
+mod pick_map;
 mod pick_nation;
 
 pub struct StartupFlowPlugin;
 
 impl Plugin for StartupFlowPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins((PickNationPlugin, DeployUnitsPlugin))
+        app.add_plugins((PickNationPlugin, PickMapPlugin, DeployUnitsPlugin))
             .init_state::<InGameState>()
             .add_event::<PickNationEvent>()
             .init_resource::<PickedNationsResource>()
@@ -38,6 +41,7 @@ pub enum InGameState {
     PickNation,
     PickCommander,
     Events,
+    PickMap,
     DeployUnits,
     Playing,
 }
@@ -47,6 +51,9 @@ pub struct PickedNationsResource {
     pub nations_by_player: HashMap<Team, PickedNation>,
 }
 
+#[derive(Resource, Debug)]
+pub struct PickedMapResource(pub MapKey);
+
 #[derive(Debug)]
 pub struct PickedNation {
     pub nation: NationKey,
@@ -69,15 +76,27 @@ mod tests {
     use bevy::prelude::{AssetApp, AssetPlugin, ColorMaterial, Entity, Handle, Mesh, MouseButton};
     use hexx::Hex;
 
+    use crate::game::abilities::defined_abilities::DefinedAbilityRegistry;
+    use crate::game::asset_loading::map_assets::{
+        MapAssetsResource, MapDefinition, MapHexDefinition,
+    };
     use crate::game::asset_loading::nation_asset_resource::NationAssetsResource;
     use crate::game::asset_loading::nation_assets::{UnitKey, UnitStats};
+    use crate::game::asset_loading::terrain_assets::{
+        TerrainAssetsResource, TerrainDefinition, TerrainDisplay,
+    };
+    use crate::game::ingame::damage_type::DamageType;
     use crate::game::ingame::hex::HexComponent;
     use crate::game::ingame::hovered_hex::HoveredHex;
+    use crate::game::ingame::line_of_sight::LineOfSight;
+    use crate::game::ingame::terrain::MovementCost;
     use crate::game::ingame::unit::UnitMarker;
     use crate::game::states::in_game_state::deploy_units::{
         DeploymentDoneEvent, SelectedUnitToDeploy,
     };
+    use crate::game::states::in_game_state::pick_map::PickMapEvent;
     use crate::game::states::round_state::{ActiveTeam, RoundState};
+    use crate::game::util::dice_expression::DiceExpression;
     use crate::generate_test_app;
     use crate::tests::AppWrapper;
 
@@ -89,6 +108,8 @@ mod tests {
     const NATION_2: &str = "nation 2";
     const UNIT_2: &str = "unit 2";
 
+    const MAP: &str = "maps/test.map.ron";
+
     #[test]
     fn startup_flow() {
         let mut app = TestApp::init();
@@ -108,6 +129,13 @@ mod tests {
         app.update();
         app.update();
 
+        assert_eq!(app.get_ingame_state(), &InGameState::PickMap);
+        app.pick_map(MapKey(MAP.to_string()));
+        assert_eq!(
+            app.app.world.resource::<PickedMapResource>().0,
+            MapKey(MAP.to_string())
+        );
+
         let unit_1_key = UnitKey {
             nation: NATION_1.to_string(),
             name: UNIT_1.to_string(),
@@ -179,13 +207,25 @@ mod tests {
                             name: UNIT_1.to_string(),
                             max_action_points: 0,
                             max_health_points: 0,
-                            damage: 0,
+                            damage: DiceExpression::constant(0),
+                            damage_type: DamageType::Slashing,
                             defense: 0,
+                            armor: 0,
+                            resistances: vec![],
+                            vulnerabilities: vec![],
                             attack_action_point_cost: 0,
                             max_attacks_per_round: 0,
                             range: 0,
+                            ammo: None,
+                            can_counter: true,
+                            morale: 0,
+                            commander: false,
+                            supplier: false,
                             passive_combat_abilities: vec![],
                             active_abilities: vec![],
+                            active_ability_ammo: Default::default(),
+                            attacks: vec![],
+                            abilities: vec![],
                         },
                     ),
                     (
@@ -194,17 +234,64 @@ mod tests {
                             name: UNIT_2.to_string(),
                             max_action_points: 0,
                             max_health_points: 0,
-                            damage: 0,
+                            damage: DiceExpression::constant(0),
+                            damage_type: DamageType::Slashing,
                             defense: 0,
+                            armor: 0,
+                            resistances: vec![],
+                            vulnerabilities: vec![],
                             attack_action_point_cost: 0,
                             max_attacks_per_round: 0,
                             range: 0,
+                            ammo: None,
+                            can_counter: true,
+                            morale: 0,
+                            commander: false,
+                            supplier: false,
                             passive_combat_abilities: vec![],
                             active_abilities: vec![],
+                            active_ability_ammo: Default::default(),
+                            attacks: vec![],
+                            abilities: vec![],
                         },
                     ),
                 ]),
             });
+            app.insert_resource(MapAssetsResource {
+                maps: HashMap::from([(
+                    MAP.to_string(),
+                    MapDefinition {
+                        name: "test map".to_string(),
+                        width: 9,
+                        height: 9,
+                        hexes: Hex::ZERO
+                            .spiral_range(0..5)
+                            .map(|hex| MapHexDefinition {
+                                x: hex.x,
+                                y: hex.y,
+                                terrain: "plains".to_string(),
+                                elevation: 0,
+                            })
+                            .collect(),
+                    },
+                )]),
+            });
+            app.insert_resource(TerrainAssetsResource {
+                terrains: HashMap::from([(
+                    "plains".to_string(),
+                    TerrainDefinition {
+                        name: "Plains".to_string(),
+                        movement_cost: MovementCost::Passable(1),
+                        display: TerrainDisplay::Color(0., 1., 0.),
+                        attack_modifier: 0,
+                        defense_modifier: 0,
+                        line_of_sight: LineOfSight::Clear,
+                        stops_charges: false,
+                        attack_disadvantage: false,
+                    },
+                )]),
+            });
+            app.init_resource::<DefinedAbilityRegistry>();
             app.init_resource::<ActiveTeam>();
             app.init_resource::<HoveredHex>();
             app.add_plugins((AssetPlugin::default(), InputPlugin, StartupFlowPlugin));
@@ -243,6 +330,12 @@ mod tests {
             self.app.update();
         }
 
+        fn pick_map(&mut self, map: MapKey) {
+            self.send_event(PickMapEvent { map });
+            self.app.update();
+            self.app.update();
+        }
+
         fn deploy_unit_at(&mut self, unit: UnitKey, hex: Hex) {
             self.set_unit_to_deploy(unit);
             self.set_hovered_hex(hex);
@@ -0,0 +1,74 @@
+use bevy::app::App;
+use bevy::prelude::EventReader;
+#[cfg(not(test))]
+use bevy::prelude::Update;
+use bevy::prelude::{
+    in_state, info, Commands, Event, IntoSystemConfigs, NextState, Plugin, PostUpdate, ResMut,
+};
+
+use crate::game::asset_loading::map_assets::MapKey;
+#[cfg(not(test))]
+use crate::game::states::in_game_state::pick_map::ui::pick_map_menu;
+use crate::game::states::in_game_state::{InGameState, PickedMapResource};
+
+pub(super) struct PickMapPlugin;
+
+impl Plugin for PickMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickMapEvent>();
+
+        #[cfg(not(test))]
+        app.add_systems(Update, pick_map_menu.run_if(in_state(InGameState::PickMap)));
+        app.add_systems(
+            PostUpdate,
+            handle_pick_map_event.run_if(in_state(InGameState::PickMap)),
+        );
+    }
+}
+
+#[derive(Event, Debug)]
+pub(super) struct PickMapEvent {
+    pub map: MapKey,
+}
+
+#[cfg(not(test))]
+mod ui {
+    use bevy::prelude::{EventWriter, Res};
+    use bevy_egui::egui::Window;
+    use bevy_egui::EguiContexts;
+
+    use crate::game::asset_loading::map_assets::MapAssetsResource;
+    use crate::game::states::in_game_state::pick_map::PickMapEvent;
+
+    pub(super) fn pick_map_menu(
+        mut contexts: EguiContexts,
+        mut pick_map_event: EventWriter<PickMapEvent>,
+        map_assets_resource: Res<MapAssetsResource>,
+    ) {
+        Window::new("Pick Map").show(contexts.ctx_mut(), |ui| {
+            for map_key in map_assets_resource.get_maps() {
+                let Some(map) = map_assets_resource.get_map(&map_key) else {
+                    continue;
+                };
+                let label = format!("{} ({}x{})", map.name, map.width, map.height);
+                if ui.button(label).clicked() {
+                    pick_map_event.send(PickMapEvent { map: map_key });
+                }
+            }
+        });
+    }
+}
+
+pub(super) fn handle_pick_map_event(
+    mut pick_map_events: EventReader<PickMapEvent>,
+    mut commands: Commands,
+    mut in_game_state: ResMut<NextState<InGameState>>,
+) {
+    let Some(event) = pick_map_events.read().next() else {
+        return;
+    };
+
+    in_game_state.set(InGameState::DeployUnits);
+    info!("Picked map {:?}", event.map);
+    commands.insert_resource(PickedMapResource(event.map.clone()));
+}
@@ -1,27 +1,38 @@
 use bevy::app::App;
 use bevy::prelude::{
-    default, Commands, NextState, OnEnter, Plugin, Res, ResMut, SpriteBundle, States, Transform,
-    Vec3,
+    default, Commands, IntoSystemConfigs, Local, NextState, OnEnter, Plugin, Res, ResMut,
+    SpriteBundle, States, Transform, Vec3,
 };
 use hexx::Hex;
 
+use crate::game::abilities::defined_abilities::DefinedAbilityRegistry;
+use crate::game::abilities::passive_combat_abilities::PassiveCombatAbilityRegistry;
+use crate::game::asset_loading::map_assets::{MapAssetsResource, MapKey};
 use crate::game::asset_loading::nation_asset_resource::NationAssetsResource;
 use crate::game::ingame::action_points::ActionPoints;
+use crate::game::ingame::charge::TravelledPath;
 use crate::game::ingame::combat::{CombatConfig, HealthPoints};
 use crate::game::ingame::hex::{setup_hex_grid, HexComponent};
+use crate::game::ingame::morale::Morale;
+use crate::game::ingame::stat_modifiers::StatModifiers;
+use crate::game::ingame::status_effects::StatusEffects;
 use crate::game::ingame::team_setup::Team;
 use crate::game::ingame::unit::UnitMarker;
 use crate::game::ingame::unit_status::UnitStatus;
 use crate::game::ingame::z_ordering::ZOrdering;
-use crate::game::states::in_game_state::{InGameState, PickedNation, PickedNationsResource};
+use crate::game::states::in_game_state::{
+    InGameState, PickedMapResource, PickedNation, PickedNationsResource,
+};
 use crate::game::states::round_state::RoundState;
 
 pub struct QuickstartPlugin;
 
 impl Plugin for QuickstartPlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<QuickstartState>()
-            .add_systems(OnEnter(QuickstartState::DoIt), (setup_hex_grid, quickstart));
+        app.init_state::<QuickstartState>().add_systems(
+            OnEnter(QuickstartState::DoIt),
+            (pick_quickstart_map, setup_hex_grid, quickstart).chain(),
+        );
     }
 }
 
@@ -32,13 +43,32 @@ pub enum QuickstartState {
     DoIt,
 }
 
+fn pick_quickstart_map(mut commands: Commands, map_assets_resource: Res<MapAssetsResource>) {
+    // the menu only offers quickstart when there is a map, setup_hex_grid reports a missing one
+    let map = map_assets_resource
+        .get_maps()
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| MapKey(String::new()));
+
+    commands.insert_resource(PickedMapResource(map));
+}
+
 fn quickstart(
     mut commands: Commands,
     nation_assets_resource: Res<NationAssetsResource>,
     mut picked_nations: ResMut<PickedNationsResource>,
     mut in_game_state: ResMut<NextState<InGameState>>,
     mut round_state: ResMut<NextState<RoundState>>,
+    passive_combat_ability_registry: Local<PassiveCombatAbilityRegistry>,
+    defined_ability_registry: Res<DefinedAbilityRegistry>,
 ) {
+    let get_defined_abilities = |names: &[String]| -> Vec<_> {
+        names
+            .iter()
+            .filter_map(|name| defined_ability_registry.get(name))
+            .collect()
+    };
     in_game_state.set(InGameState::Playing);
     round_state.set(RoundState::Input);
 
@@ -74,6 +104,9 @@ fn quickstart(
             })
             .insert(Team::Red)
             .insert(UnitStatus::default())
+            .insert(StatusEffects::default())
+            .insert(StatModifiers::default())
+            .insert(TravelledPath::default())
             .insert(UnitMarker(manf_assets.stats.name.clone()))
             .insert(ActionPoints::new(
                 manf_assets.stats.max_action_points,
@@ -81,12 +114,12 @@ fn quickstart(
                 manf_assets.stats.attack_action_point_cost,
             ))
             .insert(HealthPoints::new(manf_assets.stats.max_health_points))
-            .insert(CombatConfig {
-                damage: manf_assets.stats.damage,
-                defense: manf_assets.stats.defense,
-                range: manf_assets.stats.range,
-                passive_combat_abilities: vec![],
-            })
+            .insert(Morale::new(manf_assets.stats.morale))
+            .insert(CombatConfig::from_unit_stats(
+                &manf_assets.stats,
+                &passive_combat_ability_registry,
+                &get_defined_abilities(&manf_assets.stats.abilities),
+            ))
             .insert(HexComponent(Hex::new(4, i as i32 - 4)));
 
         commands
@@ -99,18 +132,21 @@ fn quickstart(
             .insert(UnitMarker(tree_assets.stats.name.clone()))
             .insert(Team::Blue)
             .insert(UnitStatus::default())
+            .insert(StatusEffects::default())
+            .insert(StatModifiers::default())
+            .insert(TravelledPath::default())
             .insert(ActionPoints::new(
                 tree_assets.stats.max_action_points,
                 tree_assets.stats.max_attacks_per_round,
                 tree_assets.stats.attack_action_point_cost,
             ))
             .insert(HealthPoints::new(tree_assets.stats.max_health_points))
-            .insert(CombatConfig {
-                damage: tree_assets.stats.damage,
-                defense: tree_assets.stats.defense,
-                range: tree_assets.stats.range,
-                passive_combat_abilities: vec![],
-            })
+            .insert(Morale::new(tree_assets.stats.morale))
+            .insert(CombatConfig::from_unit_stats(
+                &tree_assets.stats,
+                &passive_combat_ability_registry,
+                &get_defined_abilities(&tree_assets.stats.abilities),
+            ))
             .insert(HexComponent(Hex::new(-4, i as i32)));
     }
 }
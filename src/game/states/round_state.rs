@@ -1,7 +1,15 @@
-use bevy::prelude::{NextState, ResMut, Resource, State, States};
+use bevy::prelude::{
+    Commands, Entity, EventWriter, NextState, Query, ResMut, Resource, State, States,
+};
 
+use crate::game::abilities::passive_combat_abilities::{run_triggered_abilities, AbilityTrigger};
+use crate::game::ingame::combat::{CombatConfig, HealthPoints};
+use crate::game::ingame::game_log::LogEvent;
 use crate::game::ingame::selected_unit::SelectedUnitResource;
+use crate::game::ingame::stat_modifiers::StatModifiers;
+use crate::game::ingame::status_effects::StatusEffects;
 use crate::game::ingame::team_setup::Team;
+use crate::game::ingame::unit::UnitMarker;
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, States, Default)]
 pub enum RoundState {
@@ -10,6 +18,9 @@ pub enum RoundState {
     Input,
     PreCombat,
     Combat,
+    /// collects the passive abilities that change the counterattack before it is rolled
+    PreCounterAttack,
+    CounterAttack,
     PostCombat,
     MovingUnit,
     ActivateAbility,
@@ -34,13 +45,78 @@ pub(super) fn start_round_system(
     }
 }
 
+/// Triggers `OnRoundEnd` abilities of the active team and `OnRoundStart` abilities of the next one
 pub fn round_end_system(
+    mut commands: Commands,
     mut round_state: ResMut<NextState<RoundState>>,
     mut active_team: ResMut<ActiveTeam>,
     mut selected_unit_resource: ResMut<SelectedUnitResource>,
+    mut units: Query<(
+        Entity,
+        &Team,
+        &UnitMarker,
+        &mut StatusEffects,
+        &mut StatModifiers,
+        &mut HealthPoints,
+        &CombatConfig,
+    )>,
+    mut log_event: EventWriter<LogEvent>,
 ) {
     selected_unit_resource.set_selected_unit(None);
 
+    for (
+        entity,
+        _,
+        unit_marker,
+        mut status_effects,
+        mut stat_modifiers,
+        mut health_points,
+        combat_config,
+    ) in units
+        .iter_mut()
+        .filter(|(_, team, ..)| **team == active_team.0)
+    {
+        run_triggered_abilities(
+            &mut commands,
+            entity,
+            None,
+            combat_config,
+            AbilityTrigger::OnRoundEnd,
+        );
+        let poison_damage = status_effects.get_poison_damage();
+        if poison_damage > 0 {
+            health_points.take_damage(poison_damage, None);
+            log_event.send(LogEvent {
+                message: format!("{} takes {poison_damage} poison damage", unit_marker.0),
+            });
+            if health_points.left > 0 {
+                run_triggered_abilities(
+                    &mut commands,
+                    entity,
+                    None,
+                    combat_config,
+                    AbilityTrigger::OnDamaged,
+                );
+            }
+        }
+        for expired_effect in status_effects.tick() {
+            log_event.send(LogEvent {
+                message: format!(
+                    "{} is no longer affected by {}",
+                    unit_marker.0, expired_effect.effect_type
+                ),
+            });
+        }
+        for expired_modifier in stat_modifiers.tick() {
+            log_event.send(LogEvent {
+                message: format!(
+                    "{} is no longer affected by {}",
+                    unit_marker.0, expired_modifier
+                ),
+            });
+        }
+    }
+
     let next_team = match active_team.0 {
         Team::Red => Team::Blue,
         Team::Blue => Team::Red,
@@ -48,5 +124,101 @@ pub fn round_end_system(
 
     active_team.0 = next_team;
 
+    for (entity, .., combat_config) in units.iter().filter(|(_, team, ..)| **team == next_team) {
+        run_triggered_abilities(
+            &mut commands,
+            entity,
+            None,
+            combat_config,
+            AbilityTrigger::OnRoundStart,
+        );
+    }
+
     round_state.set(RoundState::Input);
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{App, OnEnter};
+    use hexx::Hex;
+
+    use crate::game::ingame::combat::CombatEvent;
+    use crate::game::ingame::stat_modifiers::Stat;
+    use crate::generate_test_app;
+    use crate::tests::{add_passive_ability, spawn_unit, AppWrapper};
+
+    use super::*;
+
+    #[test]
+    fn ending_the_round_triggers_round_end_and_round_start_abilities() {
+        let mut app = TestApp::build();
+        for unit in [app.red_unit, app.blue_unit] {
+            add_passive_ability(
+                &mut app.app.world,
+                unit,
+                r#"(
+                    name: "Vigilance",
+                    trigger: Passive(OnRoundEnd),
+                    target: Owner,
+                    effects: [ModifyStat(stat: Defense, value: Add(1))],
+                )"#,
+            );
+            add_passive_ability(
+                &mut app.app.world,
+                unit,
+                r#"(
+                    name: "Second Wind",
+                    trigger: Passive(OnRoundStart),
+                    target: Owner,
+                    effects: [ModifyStat(stat: ActionPoints, value: Add(1))],
+                )"#,
+            );
+        }
+
+        app.app
+            .world
+            .resource_mut::<NextState<RoundState>>()
+            .set(RoundState::RoundEnd);
+        app.update();
+
+        assert_eq!(app.app.world.resource::<ActiveTeam>().0, Team::Blue);
+        assert_eq!(app.get_stat(app.red_unit, Stat::Defense), 11);
+        assert_eq!(app.get_stat(app.red_unit, Stat::ActionPoints), 10);
+        assert_eq!(app.get_stat(app.blue_unit, Stat::Defense), 10);
+        assert_eq!(app.get_stat(app.blue_unit, Stat::ActionPoints), 11);
+    }
+
+    generate_test_app!(red_unit: Entity, blue_unit: Entity);
+
+    impl TestApp {
+        fn build() -> TestApp {
+            let mut app = App::new();
+
+            app.init_state::<RoundState>();
+            app.add_event::<LogEvent>();
+            app.add_event::<CombatEvent>();
+            app.init_resource::<ActiveTeam>();
+            app.init_resource::<SelectedUnitResource>();
+            app.add_systems(OnEnter(RoundState::RoundEnd), round_end_system);
+
+            let red_unit = spawn_unit(&mut app, Team::Red, Hex::ZERO);
+            let blue_unit = spawn_unit(&mut app, Team::Blue, Hex::new(3, 0));
+
+            app.update();
+
+            TestApp {
+                app,
+                red_unit,
+                blue_unit,
+            }
+        }
+
+        fn get_stat(&self, unit: Entity, stat: Stat) -> usize {
+            let unit = self.app.world.entity(unit);
+            unit.get::<StatModifiers>()
+                .unwrap()
+                .resolve(stat, 10, unit.get::<StatusEffects>().unwrap())
+                .value
+        }
+    }
+}
@@ -1,10 +1,84 @@
-use rand::Rng;
+use std::collections::VecDeque;
+
+use bevy::prelude::{FromWorld, Resource, World};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 pub struct Dice<const N: u8>;
 
 impl<const N: u8> Dice<N> {
-    pub fn roll() -> u8 {
-        rand::thread_rng().gen_range(1..=N)
+    pub fn roll(dice_rng: &mut DiceRng) -> u8 {
+        dice_rng.roll(N)
+    }
+}
+
+/// The single source of all dice rolls, so that a game can be reproduced from its seed
+#[derive(Resource, Debug)]
+pub struct DiceRng {
+    seed: u64,
+    roll_source: RollSource,
+}
+
+#[derive(Debug)]
+enum RollSource {
+    Seeded(Box<StdRng>),
+    /// Returns the given results in order, meant for tests
+    Scripted(VecDeque<u8>),
+}
+
+/// The seed the [`DiceRng`] of the next game starts from, a random seed is picked without one
+#[derive(Resource, Debug, Default)]
+pub struct DiceSeed(pub Option<u64>);
+
+impl FromWorld for DiceRng {
+    fn from_world(world: &mut World) -> Self {
+        let seed = world
+            .get_resource::<DiceSeed>()
+            .and_then(|dice_seed| dice_seed.0);
+        Self::from_seed(seed.unwrap_or_else(|| rand::thread_rng().gen()))
+    }
+}
+
+/// Every game gets new dice, seeded with the current [`DiceSeed`]
+pub fn insert_dice_rng(world: &mut World) {
+    let dice_rng = DiceRng::from_world(world);
+    world.insert_resource(dice_rng);
+}
+
+impl DiceRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            roll_source: RollSource::Seeded(Box::new(StdRng::seed_from_u64(seed))),
+        }
+    }
+
+    /// Rolls return exactly the given results, panics when more rolls are requested than scripted
+    pub fn scripted(results: impl IntoIterator<Item = u8>) -> Self {
+        Self {
+            seed: 0,
+            roll_source: RollSource::Scripted(results.into_iter().collect()),
+        }
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn roll(&mut self, sides: u8) -> u8 {
+        match &mut self.roll_source {
+            RollSource::Seeded(rng) => rng.gen_range(1..=sides),
+            RollSource::Scripted(results) => {
+                let result = results
+                    .pop_front()
+                    .expect("scripted dice rolls are exhausted");
+                assert!(
+                    (1..=sides).contains(&result),
+                    "scripted dice roll {result} does not fit a d{sides}"
+                );
+                result
+            }
+        }
     }
 }
 
@@ -28,7 +102,7 @@ mod tests {
     }
 
     fn test_dice_roll<const N: u8>() {
-        let result = Dice::<N>::roll();
+        let result = Dice::<N>::roll(&mut DiceRng::from_world(&mut World::new()));
 
         assert!(result >= 1, "Expected {result} to be greater than 1");
         assert!(
@@ -36,4 +110,37 @@ mod tests {
             "Expected {result} to be less or equal than {N}"
         );
     }
+
+    #[test]
+    fn same_seed_rolls_the_same_results() {
+        let mut dice_rng = DiceRng::from_seed(42);
+        let mut other_dice_rng = DiceRng::from_seed(42);
+
+        let results: Vec<_> = (0..10).map(|_| Dice::<20>::roll(&mut dice_rng)).collect();
+        let other_results: Vec<_> = (0..10)
+            .map(|_| Dice::<20>::roll(&mut other_dice_rng))
+            .collect();
+
+        assert_eq!(results, other_results);
+        assert_eq!(dice_rng.get_seed(), 42);
+    }
+
+    #[test]
+    fn the_dice_seed_is_used_when_the_dice_are_created() {
+        let mut world = World::new();
+        world.insert_resource(DiceSeed(Some(42)));
+
+        let dice_rng = DiceRng::from_world(&mut world);
+
+        assert_eq!(dice_rng.get_seed(), 42);
+    }
+
+    #[test]
+    fn scripted_rolls_are_returned_in_order() {
+        let mut dice_rng = DiceRng::scripted([20, 1, 5]);
+
+        assert_eq!(Dice::<20>::roll(&mut dice_rng), 20);
+        assert_eq!(Dice::<20>::roll(&mut dice_rng), 1);
+        assert_eq!(Dice::<6>::roll(&mut dice_rng), 5);
+    }
 }
@@ -0,0 +1,345 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error};
+
+use crate::game::util::dice::DiceRng;
+
+/// A sum of dice and a flat modifier like `2d6+1`, `1d4` or `3`
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct DiceExpression {
+    dice: Vec<DiceTerm>,
+    modifier: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DiceTerm {
+    count: u8,
+    sides: u8,
+}
+
+/// The result of rolling a [`DiceExpression`], keeping every single die for the log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiceExpressionRoll {
+    pub expression: DiceExpression,
+    /// (sides, result) of every die rolled
+    pub dice_results: Vec<(u8, u8)>,
+    pub total: usize,
+}
+
+impl DiceExpression {
+    pub fn constant(value: usize) -> Self {
+        Self {
+            dice: vec![],
+            modifier: value as i32,
+        }
+    }
+
+    /// Same expression with twice the number of dice, the modifier is not doubled
+    pub fn with_doubled_dice(&self) -> Self {
+        Self {
+            dice: self
+                .dice
+                .iter()
+                .map(|dice_term| DiceTerm {
+                    count: dice_term.count * 2,
+                    sides: dice_term.sides,
+                })
+                .collect(),
+            modifier: self.modifier,
+        }
+    }
+
+    pub fn roll(&self, dice_rng: &mut DiceRng) -> DiceExpressionRoll {
+        let dice_results: Vec<_> = self
+            .dice
+            .iter()
+            .flat_map(|dice_term| (0..dice_term.count).map(|_| dice_term.sides))
+            .map(|sides| (sides, dice_rng.roll(sides)))
+            .collect();
+
+        let dice_sum: i32 = dice_results.iter().map(|(_, result)| *result as i32).sum();
+
+        DiceExpressionRoll {
+            expression: self.clone(),
+            dice_results,
+            total: (dice_sum + self.modifier).max(0) as usize,
+        }
+    }
+
+    pub fn get_min(&self) -> usize {
+        let dice_count: i32 = self
+            .dice
+            .iter()
+            .map(|dice_term| dice_term.count as i32)
+            .sum();
+        (dice_count + self.modifier).max(0) as usize
+    }
+
+    pub fn get_max(&self) -> usize {
+        let dice_max: i32 = self
+            .dice
+            .iter()
+            .map(|dice_term| dice_term.count as i32 * dice_term.sides as i32)
+            .sum();
+        (dice_max + self.modifier).max(0) as usize
+    }
+
+    /// The chance of every possible total, sorted by total
+    pub fn get_distribution(&self) -> Vec<(usize, f32)> {
+        let mut dice_sums = BTreeMap::from([(0, 1.)]);
+        for sides in self
+            .dice
+            .iter()
+            .flat_map(|dice_term| (0..dice_term.count).map(|_| dice_term.sides))
+        {
+            let mut next_dice_sums = BTreeMap::new();
+            for (sum, chance) in dice_sums {
+                for result in 1..=sides as i32 {
+                    *next_dice_sums.entry(sum + result).or_default() += chance / sides as f32;
+                }
+            }
+            dice_sums = next_dice_sums;
+        }
+
+        let mut totals = BTreeMap::new();
+        for (sum, chance) in dice_sums {
+            *totals
+                .entry((sum + self.modifier).max(0) as usize)
+                .or_default() += chance;
+        }
+        totals.into_iter().collect()
+    }
+}
+
+impl FromStr for DiceExpression {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let expression: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+        if expression.is_empty() {
+            bail!("Dice expression must not be empty");
+        }
+
+        let mut dice = vec![];
+        let mut modifier = 0;
+
+        for (sign, term) in split_terms(&expression) {
+            let term_error = || anyhow!("Invalid term '{term}' in dice expression '{value}'");
+
+            match term.split_once('d') {
+                Some((count, sides)) => {
+                    if sign < 0 {
+                        bail!("Dice can not be subtracted in dice expression '{value}'");
+                    }
+                    let count = if count.is_empty() {
+                        1
+                    } else {
+                        count.parse().map_err(|_| term_error())?
+                    };
+                    let sides = sides.parse().map_err(|_| term_error())?;
+                    if count == 0 || sides == 0 {
+                        return Err(term_error());
+                    }
+                    dice.push(DiceTerm { count, sides });
+                }
+                None => {
+                    let constant: i32 = term.parse().map_err(|_| term_error())?;
+                    modifier += sign * constant;
+                }
+            }
+        }
+
+        Ok(Self { dice, modifier })
+    }
+}
+
+/// Splits `2d6+1-2` into `[(1, "2d6"), (1, "1"), (-1, "2")]`
+fn split_terms(expression: &str) -> Vec<(i32, &str)> {
+    let mut terms = vec![];
+    let mut sign = 1;
+    let mut term_start = 0;
+
+    for (index, character) in expression.char_indices() {
+        let next_sign = match character {
+            '+' => 1,
+            '-' => -1,
+            _ => continue,
+        };
+        terms.push((sign, &expression[term_start..index]));
+        sign = next_sign;
+        term_start = index + 1;
+    }
+    terms.push((sign, &expression[term_start..]));
+
+    terms
+}
+
+impl TryFrom<String> for DiceExpression {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<DiceExpression> for String {
+    fn from(dice_expression: DiceExpression) -> Self {
+        dice_expression.to_string()
+    }
+}
+
+impl Display for DiceExpression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let terms: Vec<_> = self
+            .dice
+            .iter()
+            .map(|dice_term| format!("{}d{}", dice_term.count, dice_term.sides))
+            .collect();
+        write!(f, "{}", terms.join("+"))?;
+
+        match (terms.is_empty(), self.modifier) {
+            (true, modifier) => write!(f, "{modifier}"),
+            (false, 0) => Ok(()),
+            (false, modifier) => write!(f, "{modifier:+}"),
+        }
+    }
+}
+
+impl Display for DiceExpressionRoll {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.dice_results.is_empty() {
+            return write!(f, "{}", self.total);
+        }
+
+        let dice_results: Vec<_> = self
+            .dice_results
+            .iter()
+            .map(|(sides, result)| format!("d{sides}: {result}"))
+            .collect();
+        write!(f, "{} = [{}]", self.expression, dice_results.join(", "))?;
+        if self.expression.modifier != 0 {
+            write!(f, " {:+}", self.expression.modifier)?;
+        }
+        write!(f, " = {}", self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_dice_expressions() {
+        assert_eq!(
+            "1d6+1".parse::<DiceExpression>().unwrap(),
+            DiceExpression {
+                dice: vec![DiceTerm { count: 1, sides: 6 }],
+                modifier: 1,
+            }
+        );
+        assert_eq!(
+            "2d4".parse::<DiceExpression>().unwrap(),
+            DiceExpression {
+                dice: vec![DiceTerm { count: 2, sides: 4 }],
+                modifier: 0,
+            }
+        );
+        assert_eq!(
+            "3".parse::<DiceExpression>().unwrap(),
+            DiceExpression::constant(3)
+        );
+        assert_eq!(
+            " d8 + 1d4 - 1 ".parse::<DiceExpression>().unwrap(),
+            DiceExpression {
+                dice: vec![
+                    DiceTerm { count: 1, sides: 8 },
+                    DiceTerm { count: 1, sides: 4 }
+                ],
+                modifier: -1,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dice_expressions() {
+        for invalid in ["", "1d", "d", "0d6", "1d0", "1d6+", "x", "2-1d4", "1d6d6"] {
+            assert!(
+                invalid.parse::<DiceExpression>().is_err(),
+                "Expected '{invalid}' to be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn displays_in_parseable_form() {
+        for expression in ["1d6+1", "2d4", "3", "1d8+1d4-1"] {
+            assert_eq!(
+                expression.parse::<DiceExpression>().unwrap().to_string(),
+                expression
+            );
+        }
+    }
+
+    #[test]
+    fn doubling_dice_keeps_the_modifier() {
+        let expression = "2d4+1".parse::<DiceExpression>().unwrap();
+
+        assert_eq!(expression.with_doubled_dice().to_string(), "4d4+1");
+    }
+
+    #[test]
+    fn rolls_every_die_and_adds_the_modifier() {
+        let mut dice_rng = DiceRng::scripted([2, 3]);
+        let roll = "2d4+1"
+            .parse::<DiceExpression>()
+            .unwrap()
+            .roll(&mut dice_rng);
+
+        assert_eq!(roll.dice_results, vec![(4, 2), (4, 3)]);
+        assert_eq!(roll.total, 6);
+        assert_eq!(roll.to_string(), "2d4+1 = [d4: 2, d4: 3] +1 = 6");
+    }
+
+    #[test]
+    fn total_does_not_drop_below_zero() {
+        let mut dice_rng = DiceRng::scripted([1]);
+        let roll = "1d4-3"
+            .parse::<DiceExpression>()
+            .unwrap()
+            .roll(&mut dice_rng);
+
+        assert_eq!(roll.total, 0);
+    }
+
+    #[test]
+    fn distribution_covers_every_total() {
+        let distribution = "2d4-3"
+            .parse::<DiceExpression>()
+            .unwrap()
+            .get_distribution();
+
+        assert_eq!(
+            distribution,
+            vec![
+                (0, 3. / 16.),
+                (1, 3. / 16.),
+                (2, 4. / 16.),
+                (3, 3. / 16.),
+                (4, 2. / 16.),
+                (5, 1. / 16.)
+            ]
+        );
+    }
+
+    #[test]
+    fn deserializes_from_ron_string() {
+        let expression: DiceExpression = ron::from_str("\"1d6+1\"").unwrap();
+        assert_eq!(expression.to_string(), "1d6+1");
+
+        let error = ron::from_str::<DiceExpression>("\"1d\"").unwrap_err();
+        assert!(error.to_string().contains("Invalid term '1d'"));
+    }
+}
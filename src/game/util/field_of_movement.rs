@@ -0,0 +1,79 @@
+use std::collections::{HashSet, VecDeque};
+
+use bevy::utils::HashMap;
+use hexx::Hex;
+
+/// Like [`hexx::algorithms::field_of_movement`], but the cost of a step may depend on the hex it comes from.
+/// Every step costs 1 plus the result of `cost(from, to)`, `None` means the step is not possible.
+pub fn directed_field_of_movement(
+    coord: Hex,
+    budget: u32,
+    cost: impl Fn(Hex, Hex) -> Option<u32>,
+) -> HashSet<Hex> {
+    let mut computed_costs = HashMap::from([(coord, 0)]);
+    let mut to_visit = VecDeque::from([coord]);
+
+    while let Some(current) = to_visit.pop_front() {
+        let current_cost = computed_costs[&current];
+
+        for neighbor in current.all_neighbors() {
+            let Some(step_cost) = cost(current, neighbor) else {
+                continue;
+            };
+            let neighbor_cost = current_cost + 1 + step_cost;
+            if neighbor_cost > budget {
+                continue;
+            }
+            if computed_costs
+                .get(&neighbor)
+                .is_some_and(|known_cost| *known_cost <= neighbor_cost)
+            {
+                continue;
+            }
+
+            computed_costs.insert(neighbor, neighbor_cost);
+            to_visit.push_back(neighbor);
+        }
+    }
+
+    computed_costs.into_keys().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use hexx::algorithms::field_of_movement;
+
+    use super::*;
+
+    #[test]
+    fn matches_hexx_field_of_movement_for_undirected_costs() {
+        let cost = |hex: Hex| match hex.x {
+            1 => Some(1),
+            -1 => None,
+            _ => Some(0),
+        };
+
+        let expected = field_of_movement(Hex::ZERO, 4, cost);
+        let actual = directed_field_of_movement(Hex::ZERO, 4, |_, to| cost(to));
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn uses_the_direction_of_the_step() {
+        let uphill = Hex::new(1, 0);
+        let climbing_cost = |from: Hex, to: Hex| {
+            if from != uphill && to == uphill {
+                Some(1)
+            } else {
+                Some(0)
+            }
+        };
+
+        let reachable_from_below = directed_field_of_movement(Hex::ZERO, 1, climbing_cost);
+        let reachable_from_above = directed_field_of_movement(uphill, 1, climbing_cost);
+
+        assert!(!reachable_from_below.contains(&uphill));
+        assert!(reachable_from_above.contains(&Hex::ZERO));
+    }
+}
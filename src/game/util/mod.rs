@@ -1,2 +1,4 @@
 pub mod dice;
+pub mod dice_expression;
+pub mod field_of_movement;
 pub mod find_units_within_range;
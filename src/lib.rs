@@ -7,7 +7,20 @@ pub mod scan_assets;
 mod tests {
     use bevy::ecs::event::ManualEventReader;
     use bevy::log::debug;
-    use bevy::prelude::{App, Event, Events, State, States};
+    use bevy::prelude::{
+        App, BuildWorldChildren, Entity, Event, Events, Handle, State, States, World,
+    };
+    use hexx::Hex;
+
+    use crate::game::abilities::defined_abilities::{DefinedAbility, DefinedAbilityRegistry};
+    use crate::game::asset_loading::ability_assets::AbilityDefinition;
+    use crate::game::ingame::action_points::ActionPoints;
+    use crate::game::ingame::combat::{CombatConfig, HealthPoints};
+    use crate::game::ingame::damage_type::{DamageResistances, DamageType};
+    use crate::game::ingame::morale::Morale;
+    use crate::game::ingame::team_setup::Team;
+    use crate::game::ingame::unit::{ProtoUnitBundle, UnitBundle, UnitMarker};
+    use crate::game::util::dice_expression::DiceExpression;
 
     pub trait AppWrapper {
         fn app(&self) -> &App;
@@ -58,4 +71,58 @@ mod tests {
             }
         };
     }
+
+    /// Spawns a melee unit dealing 1 damage, tests change its components where they need other stats
+    pub fn spawn_unit(app: &mut App, team: Team, hex: Hex) -> Entity {
+        app.world
+            .spawn::<UnitBundle>(
+                ProtoUnitBundle {
+                    texture: Handle::default(),
+                    transform: Default::default(),
+                    unit_marker: UnitMarker(format!("{team} unit")),
+                    player: team,
+                    action_points: ActionPoints::new(10, 1, 1),
+                    health_points: HealthPoints::new(5),
+                    combat_config: CombatConfig {
+                        damage: DiceExpression::constant(1),
+                        damage_type: DamageType::Slashing,
+                        defense: 10,
+                        damage_resistances: DamageResistances::default(),
+                        range: 1,
+                        can_counter: true,
+                        passive_combat_abilities: vec![],
+                        attacks: vec![],
+                        ammo: None,
+                    },
+                    morale: Morale::new(10),
+                    hex,
+                }
+                .into(),
+            )
+            .id()
+    }
+
+    /// Gives the unit the passive ability of the ability file, like units deployed with it
+    pub fn add_passive_ability(world: &mut World, unit: Entity, ability_file: &str) {
+        let definition: AbilityDefinition = ron::from_str(ability_file).unwrap();
+        let registry = DefinedAbilityRegistry::new(world, [definition.clone()]);
+        let Some(DefinedAbility::Passive(ability)) = registry.get(&definition.name) else {
+            panic!("{} is not a passive ability", definition.name);
+        };
+        world
+            .get_mut::<CombatConfig>(unit)
+            .unwrap()
+            .passive_combat_abilities
+            .push(ability.clone());
+    }
+
+    /// Spawns the active ability of the ability file as a child of the unit and returns it
+    pub fn add_active_ability(world: &mut World, unit: Entity, ability_file: &str) -> Entity {
+        let definition: AbilityDefinition = ron::from_str(ability_file).unwrap();
+        let registry = DefinedAbilityRegistry::new(world, [definition.clone()]);
+        let Some(DefinedAbility::Active(ability)) = registry.get(&definition.name) else {
+            panic!("{} is not an active ability", definition.name);
+        };
+        world.spawn(ability.clone()).set_parent(unit).id()
+    }
 }
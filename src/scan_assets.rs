@@ -2,9 +2,14 @@ use ron::ser::PrettyConfig;
 use std::fs;
 use std::fs::{DirEntry, File};
 
-use crate::common::{DynamicNationAssetsDefinition, NationAssetsDefinition, UnitAssetsDefinition};
+use crate::common::{
+    AbilityAssetsDefinition, DynamicNationAssetsDefinition, MapAssetsDefinition,
+    NationAssetsDefinition, UnitAssetsDefinition,
+};
 
 pub const GENERATED_NATIONS_ASSETS_FILE: &str = "generated_nations.assets.ron";
+const MAP_FILE_ENDING: &str = ".map.ron";
+const ABILITY_FILE_ENDING: &str = ".ability.ron";
 
 pub fn write_nations_assets() -> ron::Result<()> {
     println!("Writing dynamic nations assets file...");
@@ -31,7 +36,39 @@ fn scan_assets() -> DynamicNationAssetsDefinition {
         })
         .collect();
 
-    DynamicNationAssetsDefinition(nation_assets)
+    DynamicNationAssetsDefinition {
+        nations: nation_assets,
+        maps: scan_maps(),
+        abilities: scan_abilities(),
+    }
+}
+
+fn scan_maps() -> Vec<MapAssetsDefinition> {
+    let maps_dir = fs::read_dir("assets/maps").unwrap();
+
+    let mut maps: Vec<_> = maps_dir
+        .map(|dir_entry| dir_entry.unwrap())
+        .map(|dir_entry| dir_entry.file_name().into_string().unwrap())
+        .filter(|file_name| file_name.ends_with(MAP_FILE_ENDING))
+        .map(|file_name| MapAssetsDefinition { path: file_name })
+        .collect();
+    maps.sort_by(|left, right| left.path.cmp(&right.path));
+
+    maps
+}
+
+fn scan_abilities() -> Vec<AbilityAssetsDefinition> {
+    let abilities_dir = fs::read_dir("assets/abilities").unwrap();
+
+    let mut abilities: Vec<_> = abilities_dir
+        .map(|dir_entry| dir_entry.unwrap())
+        .map(|dir_entry| dir_entry.file_name().into_string().unwrap())
+        .filter(|file_name| file_name.ends_with(ABILITY_FILE_ENDING))
+        .map(|file_name| AbilityAssetsDefinition { path: file_name })
+        .collect();
+    abilities.sort_by(|left, right| left.path.cmp(&right.path));
+
+    abilities
 }
 
 fn get_unit_assets(nation_dir: DirEntry) -> Vec<UnitAssetsDefinition> {